@@ -12,6 +12,8 @@ pub struct Model {
     pub in_at: Date,
     pub out_at: Option<Date>,
     pub job: String,
+    pub full_time: bool,
+    pub hidden: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -21,7 +23,7 @@ pub enum Relation {
         from = "Column::UserId",
         to = "super::user::Column::Id",
         on_update = "NoAction",
-        on_delete = "NoAction"
+        on_delete = "Cascade"
     )]
     User,
 }
@@ -1,4 +1,16 @@
 pub mod prelude;
 
 pub mod user;
-pub mod career;
\ No newline at end of file
+pub mod career;
+pub mod refresh_token;
+pub mod pending_authorization;
+pub mod user_identity;
+pub mod revoked_token;
+pub mod user_revocation;
+pub mod role;
+pub mod permission;
+pub mod role_permission;
+pub mod user_role;
+pub mod personal_access_token;
+pub mod email_verification;
+pub mod user_login_history;
\ No newline at end of file
@@ -3,4 +3,16 @@
 pub mod prelude;
 
 pub mod career;
+pub mod refresh_token;
+pub mod pending_authorization;
+pub mod user_identity;
+pub mod revoked_token;
+pub mod user_revocation;
+pub mod role;
+pub mod permission;
+pub mod role_permission;
+pub mod user_role;
+pub mod personal_access_token;
+pub mod email_verification;
+pub mod user_login_history;
 pub mod user;
@@ -1,4 +1,16 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.9.2
 
 pub use super::career::Entity as Career;
+pub use super::refresh_token::Entity as RefreshToken;
+pub use super::pending_authorization::Entity as PendingAuthorization;
+pub use super::user_identity::Entity as UserIdentity;
+pub use super::revoked_token::Entity as RevokedToken;
+pub use super::user_revocation::Entity as UserRevocation;
+pub use super::role::Entity as Role;
+pub use super::permission::Entity as Permission;
+pub use super::role_permission::Entity as RolePermission;
+pub use super::user_role::Entity as UserRole;
+pub use super::personal_access_token::Entity as PersonalAccessToken;
+pub use super::email_verification::Entity as EmailVerification;
+pub use super::user_login_history::Entity as UserLoginHistory;
 pub use super::user::Entity as User;
@@ -1,12 +1,40 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20221220_000002_create_refresh_token_table;
+mod m20221222_000003_create_revocation_tables;
+mod m20221223_000004_create_pending_authorization_table;
+mod m20221226_000005_create_user_identity_table;
+mod m20221228_000006_create_role_tables;
+mod m20221230_000007_create_personal_access_token_table;
+mod m20230102_000008_add_career_full_time;
+mod m20230104_000009_add_user_last_signed_in_at;
+mod m20230106_000010_create_email_verification_table;
+mod m20230108_000011_add_user_login_index;
+mod m20230110_000012_add_search_vectors;
+mod m20230112_000013_add_career_user_foreign_key;
+mod m20230114_000014_add_career_hidden;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20221220_000002_create_refresh_token_table::Migration),
+            Box::new(m20221222_000003_create_revocation_tables::Migration),
+            Box::new(m20221223_000004_create_pending_authorization_table::Migration),
+            Box::new(m20221226_000005_create_user_identity_table::Migration),
+            Box::new(m20221228_000006_create_role_tables::Migration),
+            Box::new(m20221230_000007_create_personal_access_token_table::Migration),
+            Box::new(m20230102_000008_add_career_full_time::Migration),
+            Box::new(m20230104_000009_add_user_last_signed_in_at::Migration),
+            Box::new(m20230106_000010_create_email_verification_table::Migration),
+            Box::new(m20230108_000011_add_user_login_index::Migration),
+            Box::new(m20230110_000012_add_search_vectors::Migration),
+            Box::new(m20230112_000013_add_career_user_foreign_key::Migration),
+            Box::new(m20230114_000014_add_career_hidden::Migration),
+        ]
     }
 }
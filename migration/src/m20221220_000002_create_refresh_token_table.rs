@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .create_table(
+            Table::create()
+              .table(RefreshToken::Table)
+              .if_not_exists()
+              .col(
+                ColumnDef::new(RefreshToken::Id)
+                  .big_integer().not_null().auto_increment().primary_key()
+              )
+              .col(ColumnDef::new(RefreshToken::UserId).big_integer().not_null())
+              .col(ColumnDef::new(RefreshToken::FamilyId).uuid().not_null())
+              .col(ColumnDef::new(RefreshToken::TokenHash).string().not_null().unique_key())
+              .col(ColumnDef::new(RefreshToken::ExpiresAt).timestamp_with_time_zone().not_null())
+              .col(ColumnDef::new(RefreshToken::UsedAt).timestamp_with_time_zone().null())
+              .col(ColumnDef::new(RefreshToken::RevokedAt).timestamp_with_time_zone().null())
+              .col(ColumnDef::new(RefreshToken::CreatedAt).timestamp_with_time_zone().not_null())
+              .foreign_key(
+                ForeignKey::create()
+                  .name("fk_refresh_token_user_id")
+                  .from(RefreshToken::Table, RefreshToken::UserId)
+                  .to(User::Table, User::Id)
+                  .on_delete(ForeignKeyAction::Cascade)
+              )
+              .to_owned()
+          ).await?;
+
+        manager
+          .create_index(
+            Index::create()
+              .name("idx_refresh_token_family_id")
+              .table(RefreshToken::Table)
+              .col(RefreshToken::FamilyId)
+              .to_owned()
+          ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .drop_table(Table::drop().table(RefreshToken::Table).to_owned())
+          .await
+    }
+}
+
+#[derive(Iden)]
+enum RefreshToken {
+  Table,
+  Id,
+  UserId,
+  FamilyId,
+  TokenHash,
+  ExpiresAt,
+  UsedAt,
+  RevokedAt,
+  CreatedAt,
+}
+
+#[derive(Iden)]
+enum User {
+  Table,
+  Id,
+}
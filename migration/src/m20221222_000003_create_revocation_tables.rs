@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .create_table(
+            Table::create()
+              .table(RevokedToken::Table)
+              .if_not_exists()
+              .col(ColumnDef::new(RevokedToken::Jti).uuid().not_null().primary_key())
+              .col(ColumnDef::new(RevokedToken::UserId).big_integer().not_null())
+              .col(ColumnDef::new(RevokedToken::ExpiresAt).timestamp_with_time_zone().not_null())
+              .to_owned()
+          ).await?;
+
+        manager
+          .create_table(
+            Table::create()
+              .table(UserRevocation::Table)
+              .if_not_exists()
+              .col(ColumnDef::new(UserRevocation::UserId).big_integer().not_null().primary_key())
+              .col(ColumnDef::new(UserRevocation::RevokedBefore).timestamp_with_time_zone().not_null())
+              .to_owned()
+          ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .drop_table(Table::drop().table(UserRevocation::Table).to_owned())
+          .await?;
+        manager
+          .drop_table(Table::drop().table(RevokedToken::Table).to_owned())
+          .await
+    }
+}
+
+#[derive(Iden)]
+enum RevokedToken {
+  Table,
+  Jti,
+  UserId,
+  ExpiresAt,
+}
+
+#[derive(Iden)]
+enum UserRevocation {
+  Table,
+  UserId,
+  RevokedBefore,
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .create_table(
+            Table::create()
+              .table(PendingAuthorization::Table)
+              .if_not_exists()
+              .col(ColumnDef::new(PendingAuthorization::State).string().not_null().primary_key())
+              .col(ColumnDef::new(PendingAuthorization::PkceVerifier).string().not_null())
+              .col(ColumnDef::new(PendingAuthorization::ExpiresAt).timestamp_with_time_zone().not_null())
+              .col(ColumnDef::new(PendingAuthorization::CreatedAt).timestamp_with_time_zone().not_null())
+              .to_owned()
+          ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .drop_table(Table::drop().table(PendingAuthorization::Table).to_owned())
+          .await
+    }
+}
+
+#[derive(Iden)]
+enum PendingAuthorization {
+  Table,
+  State,
+  PkceVerifier,
+  ExpiresAt,
+  CreatedAt,
+}
@@ -0,0 +1,83 @@
+use sea_orm_migration::{prelude::*, sea_orm::{ConnectionTrait, Statement}};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // no FK to user: the id is reserved here before the user row is created
+        manager
+          .create_table(
+            Table::create()
+              .table(UserIdentity::Table)
+              .if_not_exists()
+              .col(ColumnDef::new(UserIdentity::Provider).string().not_null())
+              .col(ColumnDef::new(UserIdentity::Subject).string().not_null())
+              .col(ColumnDef::new(UserIdentity::UserId).big_integer().not_null())
+              .col(ColumnDef::new(UserIdentity::CreatedAt).timestamp_with_time_zone().not_null())
+              .primary_key(Index::create().col(UserIdentity::Provider).col(UserIdentity::Subject))
+              .to_owned()
+          ).await?;
+
+        manager
+          .create_index(
+            Index::create()
+              .name("idx-user_identity-user_id")
+              .table(UserIdentity::Table)
+              .col(UserIdentity::UserId)
+              .to_owned()
+          ).await?;
+
+        manager
+          .alter_table(
+            Table::alter()
+              .table(PendingAuthorization::Table)
+              .add_column(ColumnDef::new(PendingAuthorization::Provider).string().not_null().default("github"))
+              .to_owned()
+          ).await?;
+
+        // existing users were keyed by their GitHub id; from now on ids come from the sequence
+        let conn = manager.get_connection();
+        conn.execute(Statement::from_string(
+          manager.get_database_backend(),
+          r#"INSERT INTO "user_identity" ("provider", "subject", "user_id", "created_at")
+             SELECT 'github', "id"::text, "id", "created_at" FROM "user"
+             ON CONFLICT DO NOTHING"#.to_owned(),
+        )).await?;
+        conn.execute(Statement::from_string(
+          manager.get_database_backend(),
+          r#"SELECT setval(pg_get_serial_sequence('"user"', 'id'), COALESCE((SELECT MAX("id") FROM "user"), 0) + 1, false)"#.to_owned(),
+        )).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .alter_table(
+            Table::alter()
+              .table(PendingAuthorization::Table)
+              .drop_column(PendingAuthorization::Provider)
+              .to_owned()
+          ).await?;
+        manager
+          .drop_table(Table::drop().table(UserIdentity::Table).to_owned())
+          .await
+    }
+}
+
+#[derive(Iden)]
+enum UserIdentity {
+  Table,
+  Provider,
+  Subject,
+  UserId,
+  CreatedAt,
+}
+
+#[derive(Iden)]
+enum PendingAuthorization {
+  Table,
+  Provider,
+}
@@ -0,0 +1,163 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// keep in sync with `domain::auth::role::DEFAULT_ROLES`
+const PERMISSIONS: [(&str, &str); 4] = [
+  ("user:write", "edit the profile of any user"),
+  ("career:write", "add, edit and remove careers of any user"),
+  ("session:revoke", "sign any user out everywhere"),
+  ("role:assign", "grant and take away roles"),
+];
+
+const ROLES: [(&str, &str, &[&str]); 2] = [
+  ("admin", "full access", &["user:write", "career:write", "session:revoke", "role:assign"]),
+  ("moderator", "edits and hides content of other users", &["user:write", "career:write"]),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .create_table(
+            Table::create()
+              .table(Role::Table)
+              .if_not_exists()
+              .col(ColumnDef::new(Role::Name).string().not_null().primary_key())
+              .col(ColumnDef::new(Role::Description).string().not_null())
+              .to_owned()
+          ).await?;
+
+        manager
+          .create_table(
+            Table::create()
+              .table(Permission::Table)
+              .if_not_exists()
+              .col(ColumnDef::new(Permission::Name).string().not_null().primary_key())
+              .col(ColumnDef::new(Permission::Description).string().not_null())
+              .to_owned()
+          ).await?;
+
+        manager
+          .create_table(
+            Table::create()
+              .table(RolePermission::Table)
+              .if_not_exists()
+              .col(ColumnDef::new(RolePermission::Role).string().not_null())
+              .col(ColumnDef::new(RolePermission::Permission).string().not_null())
+              .primary_key(Index::create().col(RolePermission::Role).col(RolePermission::Permission))
+              .foreign_key(
+                ForeignKey::create()
+                  .name("fk-role_permission-role")
+                  .from(RolePermission::Table, RolePermission::Role)
+                  .to(Role::Table, Role::Name)
+                  .on_delete(ForeignKeyAction::Cascade)
+              )
+              .foreign_key(
+                ForeignKey::create()
+                  .name("fk-role_permission-permission")
+                  .from(RolePermission::Table, RolePermission::Permission)
+                  .to(Permission::Table, Permission::Name)
+                  .on_delete(ForeignKeyAction::Cascade)
+              )
+              .to_owned()
+          ).await?;
+
+        manager
+          .create_table(
+            Table::create()
+              .table(UserRole::Table)
+              .if_not_exists()
+              .col(ColumnDef::new(UserRole::UserId).big_integer().not_null())
+              .col(ColumnDef::new(UserRole::Role).string().not_null())
+              .col(ColumnDef::new(UserRole::CreatedAt).timestamp_with_time_zone().not_null())
+              .primary_key(Index::create().col(UserRole::UserId).col(UserRole::Role))
+              .foreign_key(
+                ForeignKey::create()
+                  .name("fk-user_role-user_id")
+                  .from(UserRole::Table, UserRole::UserId)
+                  .to(User::Table, User::Id)
+                  .on_delete(ForeignKeyAction::Cascade)
+              )
+              .foreign_key(
+                ForeignKey::create()
+                  .name("fk-user_role-role")
+                  .from(UserRole::Table, UserRole::Role)
+                  .to(Role::Table, Role::Name)
+                  .on_delete(ForeignKeyAction::Cascade)
+              )
+              .to_owned()
+          ).await?;
+
+        let mut permissions = Query::insert();
+        permissions.into_table(Permission::Table).columns([Permission::Name, Permission::Description]);
+        for (name, description) in PERMISSIONS {
+          permissions.values_panic([name.into(), description.into()]);
+        }
+        manager.exec_stmt(permissions).await?;
+
+        let mut roles = Query::insert();
+        roles.into_table(Role::Table).columns([Role::Name, Role::Description]);
+        let mut grants = Query::insert();
+        grants.into_table(RolePermission::Table).columns([RolePermission::Role, RolePermission::Permission]);
+        for (name, description, permissions) in ROLES {
+          roles.values_panic([name.into(), description.into()]);
+          for permission in permissions {
+            grants.values_panic([name.into(), (*permission).into()]);
+          }
+        }
+        manager.exec_stmt(roles).await?;
+        manager.exec_stmt(grants).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .drop_table(Table::drop().table(UserRole::Table).to_owned())
+          .await?;
+        manager
+          .drop_table(Table::drop().table(RolePermission::Table).to_owned())
+          .await?;
+        manager
+          .drop_table(Table::drop().table(Permission::Table).to_owned())
+          .await?;
+        manager
+          .drop_table(Table::drop().table(Role::Table).to_owned())
+          .await
+    }
+}
+
+#[derive(Iden)]
+enum Role {
+  Table,
+  Name,
+  Description,
+}
+
+#[derive(Iden)]
+enum Permission {
+  Table,
+  Name,
+  Description,
+}
+
+#[derive(Iden)]
+enum RolePermission {
+  Table,
+  Role,
+  Permission,
+}
+
+#[derive(Iden)]
+enum UserRole {
+  Table,
+  UserId,
+  Role,
+  CreatedAt,
+}
+
+#[derive(Iden)]
+enum User {
+  Table,
+  Id,
+}
@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .create_table(
+            Table::create()
+              .table(PersonalAccessToken::Table)
+              .if_not_exists()
+              .col(
+                ColumnDef::new(PersonalAccessToken::Id)
+                  .big_integer().not_null().auto_increment().primary_key()
+              )
+              .col(ColumnDef::new(PersonalAccessToken::UserId).big_integer().not_null())
+              .col(ColumnDef::new(PersonalAccessToken::Name).string().not_null())
+              .col(ColumnDef::new(PersonalAccessToken::TokenHash).string().not_null().unique_key())
+              // space separated, like OAuth scopes
+              .col(ColumnDef::new(PersonalAccessToken::Scopes).string().not_null())
+              .col(ColumnDef::new(PersonalAccessToken::ExpiresAt).timestamp_with_time_zone().null())
+              .col(ColumnDef::new(PersonalAccessToken::LastUsedAt).timestamp_with_time_zone().null())
+              .col(ColumnDef::new(PersonalAccessToken::CreatedAt).timestamp_with_time_zone().not_null())
+              .foreign_key(
+                ForeignKey::create()
+                  .name("fk_personal_access_token_user_id")
+                  .from(PersonalAccessToken::Table, PersonalAccessToken::UserId)
+                  .to(User::Table, User::Id)
+                  .on_delete(ForeignKeyAction::Cascade)
+              )
+              .to_owned()
+          ).await?;
+
+        manager
+          .create_index(
+            Index::create()
+              .name("idx_personal_access_token_user_id_name")
+              .table(PersonalAccessToken::Table)
+              .col(PersonalAccessToken::UserId)
+              .col(PersonalAccessToken::Name)
+              .unique()
+              .to_owned()
+          ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .drop_table(Table::drop().table(PersonalAccessToken::Table).to_owned())
+          .await
+    }
+}
+
+#[derive(Iden)]
+enum PersonalAccessToken {
+  Table,
+  Id,
+  UserId,
+  Name,
+  TokenHash,
+  Scopes,
+  ExpiresAt,
+  LastUsedAt,
+  CreatedAt,
+}
+
+#[derive(Iden)]
+enum User {
+  Table,
+  Id,
+}
@@ -0,0 +1,34 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // existing careers were all entered as regular jobs
+        manager
+          .alter_table(
+            Table::alter()
+              .table(Career::Table)
+              .add_column(ColumnDef::new(Career::FullTime).boolean().not_null().default(true))
+              .to_owned()
+          ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .alter_table(
+            Table::alter()
+              .table(Career::Table)
+              .drop_column(Career::FullTime)
+              .to_owned()
+          ).await
+    }
+}
+
+#[derive(Iden)]
+enum Career {
+  Table,
+  FullTime,
+}
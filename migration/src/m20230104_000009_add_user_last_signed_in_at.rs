@@ -0,0 +1,34 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // null until the next sign-in
+        manager
+          .alter_table(
+            Table::alter()
+              .table(User::Table)
+              .add_column(ColumnDef::new(User::LastSignedInAt).timestamp_with_time_zone().null())
+              .to_owned()
+          ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .alter_table(
+            Table::alter()
+              .table(User::Table)
+              .drop_column(User::LastSignedInAt)
+              .to_owned()
+          ).await
+    }
+}
+
+#[derive(Iden)]
+enum User {
+  Table,
+  LastSignedInAt,
+}
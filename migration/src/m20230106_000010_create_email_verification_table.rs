@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .create_table(
+            Table::create()
+              .table(EmailVerification::Table)
+              .if_not_exists()
+              // sha256 of the token mailed to the new address
+              .col(ColumnDef::new(EmailVerification::TokenHash).string().not_null().primary_key())
+              .col(ColumnDef::new(EmailVerification::UserId).big_integer().not_null())
+              .col(ColumnDef::new(EmailVerification::Email).string().not_null())
+              .col(ColumnDef::new(EmailVerification::ExpiresAt).timestamp_with_time_zone().not_null())
+              .col(ColumnDef::new(EmailVerification::CreatedAt).timestamp_with_time_zone().not_null())
+              .foreign_key(
+                ForeignKey::create()
+                  .name("fk_email_verification_user_id")
+                  .from(EmailVerification::Table, EmailVerification::UserId)
+                  .to(User::Table, User::Id)
+                  .on_delete(ForeignKeyAction::Cascade)
+              )
+              .to_owned()
+          ).await?;
+
+        manager
+          .create_index(
+            Index::create()
+              .name("idx_email_verification_user_id")
+              .table(EmailVerification::Table)
+              .col(EmailVerification::UserId)
+              .to_owned()
+          ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .drop_table(Table::drop().table(EmailVerification::Table).to_owned())
+          .await
+    }
+}
+
+#[derive(Iden)]
+enum EmailVerification {
+  Table,
+  TokenHash,
+  UserId,
+  Email,
+  ExpiresAt,
+  CreatedAt,
+}
+
+#[derive(Iden)]
+enum User {
+  Table,
+  Id,
+}
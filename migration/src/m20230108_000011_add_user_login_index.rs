@@ -0,0 +1,60 @@
+use sea_orm_migration::{prelude::*, sea_orm::{ConnectionTrait, Statement}};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // logins are matched case-insensitively, like GitHub does; fails while two users
+        // share a login in different case, rename one of them first
+        manager.get_connection().execute(Statement::from_string(
+          manager.get_database_backend(),
+          r#"CREATE UNIQUE INDEX "idx_user_login_lower" ON "user" (LOWER("login"))"#.to_owned(),
+        )).await?;
+
+        // handles users had before, so old links can redirect to the current one
+        manager
+          .create_table(
+            Table::create()
+              .table(UserLoginHistory::Table)
+              .if_not_exists()
+              // lowercased
+              .col(ColumnDef::new(UserLoginHistory::Login).string().not_null().primary_key())
+              .col(ColumnDef::new(UserLoginHistory::UserId).big_integer().not_null())
+              .col(ColumnDef::new(UserLoginHistory::CreatedAt).timestamp_with_time_zone().not_null())
+              .foreign_key(
+                ForeignKey::create()
+                  .name("fk_user_login_history_user_id")
+                  .from(UserLoginHistory::Table, UserLoginHistory::UserId)
+                  .to(User::Table, User::Id)
+                  .on_delete(ForeignKeyAction::Cascade)
+              )
+              .to_owned()
+          ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .drop_table(Table::drop().table(UserLoginHistory::Table).to_owned())
+          .await?;
+
+        manager
+          .drop_index(Index::drop().name("idx_user_login_lower").table(User::Table).to_owned())
+          .await
+    }
+}
+
+#[derive(Iden)]
+enum UserLoginHistory {
+  Table,
+  Login,
+  UserId,
+  CreatedAt,
+}
+
+#[derive(Iden)]
+enum User {
+  Table,
+  Id,
+}
@@ -0,0 +1,39 @@
+use sea_orm_migration::{prelude::*, sea_orm::{ConnectionTrait, Statement}};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// the `simple` configuration neither stems nor drops stop words, names and companies are kept as typed
+const UP: [&str; 4] = [
+  r#"ALTER TABLE "user" ADD COLUMN "search_vector" tsvector
+     GENERATED ALWAYS AS (to_tsvector('simple', coalesce("name", '') || ' ' || coalesce("login", ''))) STORED"#,
+  r#"CREATE INDEX "idx_user_search_vector" ON "user" USING GIN ("search_vector")"#,
+  r#"ALTER TABLE "career" ADD COLUMN "search_vector" tsvector
+     GENERATED ALWAYS AS (to_tsvector('simple', coalesce("company", '') || ' ' || coalesce("job", ''))) STORED"#,
+  r#"CREATE INDEX "idx_career_search_vector" ON "career" USING GIN ("search_vector")"#,
+];
+
+// dropping the columns drops their indexes too
+const DOWN: [&str; 2] = [
+  r#"ALTER TABLE "career" DROP COLUMN "search_vector""#,
+  r#"ALTER TABLE "user" DROP COLUMN "search_vector""#,
+];
+
+async fn run(manager: &SchemaManager<'_>, statements: &[&str]) -> Result<(), DbErr> {
+    for sql in statements {
+        manager.get_connection().execute(Statement::from_string(manager.get_database_backend(), sql.to_string())).await?;
+    }
+
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        run(manager, &UP).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        run(manager, &DOWN).await
+    }
+}
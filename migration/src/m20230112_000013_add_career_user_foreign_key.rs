@@ -0,0 +1,51 @@
+use sea_orm_migration::{prelude::*, sea_orm::{ConnectionTrait, Statement}};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // careers of users that never existed are left for an operator to deal with, not deleted here
+        let orphans = manager.get_connection().query_all(Statement::from_string(
+          manager.get_database_backend(),
+          r#"SELECT DISTINCT "user_id" FROM "career" WHERE "user_id" NOT IN (SELECT "id" FROM "user") ORDER BY "user_id""#.to_owned(),
+        )).await?;
+        if !orphans.is_empty() {
+          let user_ids = orphans.iter().map(|row| row.try_get::<i64>("", "user_id").map(|id| id.to_string())).collect::<Result<Vec<_>, _>>()?;
+          return Err(DbErr::Migration(format!(
+            "careers reference {} user(s) that do not exist, user_id: {}; move or delete those careers before adding fk_career_user_id",
+            user_ids.len(),
+            user_ids.join(", "),
+          )));
+        }
+
+        manager
+          .create_foreign_key(
+            ForeignKey::create()
+              .name("fk_career_user_id")
+              .from(Career::Table, Career::UserId)
+              .to(User::Table, User::Id)
+              .on_delete(ForeignKeyAction::Cascade)
+              .to_owned()
+          ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .drop_foreign_key(ForeignKey::drop().name("fk_career_user_id").table(Career::Table).to_owned())
+          .await
+    }
+}
+
+#[derive(Iden)]
+enum Career {
+  Table,
+  UserId,
+}
+
+#[derive(Iden)]
+enum User {
+  Table,
+  Id,
+}
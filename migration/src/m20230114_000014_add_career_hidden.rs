@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// keep in sync with `domain::auth::role::DEFAULT_ROLES`
+const PERMISSION: (&str, &str) = ("career:hide", "hide and show careers of any user");
+const GRANTED_TO: [&str; 2] = ["admin", "moderator"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+          .alter_table(
+            Table::alter()
+              .table(Career::Table)
+              .add_column(ColumnDef::new(Career::Hidden).boolean().not_null().default(false))
+              .to_owned()
+          ).await?;
+
+        let (name, description) = PERMISSION;
+        manager.exec_stmt(
+          Query::insert()
+            .into_table(Permission::Table)
+            .columns([Permission::Name, Permission::Description])
+            .values_panic([name.into(), description.into()])
+            .to_owned()
+        ).await?;
+
+        let mut grants = Query::insert();
+        grants.into_table(RolePermission::Table).columns([RolePermission::Role, RolePermission::Permission]);
+        for role in GRANTED_TO {
+          grants.values_panic([role.into(), name.into()]);
+        }
+        manager.exec_stmt(grants).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // the grants go with the permission
+        manager.exec_stmt(
+          Query::delete()
+            .from_table(Permission::Table)
+            .and_where(Expr::col(Permission::Name).eq(PERMISSION.0))
+            .to_owned()
+        ).await?;
+
+        manager
+          .alter_table(
+            Table::alter()
+              .table(Career::Table)
+              .drop_column(Career::Hidden)
+              .to_owned()
+          ).await
+    }
+}
+
+#[derive(Iden)]
+enum Career {
+  Table,
+  Hidden,
+}
+
+#[derive(Iden)]
+enum Permission {
+  Table,
+  Name,
+  Description,
+}
+
+#[derive(Iden)]
+enum RolePermission {
+  Table,
+  Role,
+  Permission,
+}
@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+
+use crate::{api::error::ApiError, domain::auth::assign_role::{grant, revoke, Request}, middleware::auth_user::AuthUser, repositories::role::Repository};
+
+#[derive(Deserialize)]
+pub struct RolePath {
+  pub id: i64,
+  pub role: String,
+}
+
+pub async fn grant_role(repo: web::Data<Arc<dyn Repository>>, auth: AuthUser, path: web::Path<RolePath>) -> Result<impl Responder, ApiError> {
+  let path = path.into_inner();
+  grant(repo.get_ref().clone(), &auth.principal(), Request { user_id: path.id, role: path.role }).await?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn revoke_role(repo: web::Data<Arc<dyn Repository>>, auth: AuthUser, path: web::Path<RolePath>) -> Result<impl Responder, ApiError> {
+  let path = path.into_inner();
+  revoke(repo.get_ref().clone(), &auth.principal(), Request { user_id: path.id, role: path.role }).await?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
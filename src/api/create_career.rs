@@ -1,28 +1,19 @@
 use std::sync::Arc;
 
-use actix_web::{HttpResponse, web};
+use actix_web::{HttpResponse, Responder, web};
 use serde::Serialize;
 
-use crate::{domain::career::create_career::{execute, Request}, repositories::career::PgRepository};
+use crate::{api::error::ApiError, domain::career::create_career::{execute, Request, Response}, infrastructure::settings::Settings, middleware::auth_user::AuthUser, repositories::career::Repository};
 
 #[derive(Serialize)]
-pub struct Res {
-  pub data: String,
+pub struct Res<T> {
+  pub data: T,
 }
 
-pub async fn create_career(req: web::Json<Request>) -> HttpResponse {
-  let repo = Arc::new(PgRepository::try_new().await);
+pub async fn create_career(repo: web::Data<Arc<dyn Repository>>, settings: web::Data<Settings>, auth: AuthUser, req: web::Json<Request>) -> Result<impl Responder, ApiError> {
+  let res = execute(repo.get_ref().clone(), &settings.career, auth.principal(), req.0).await?;
 
-  match execute(repo, req.0).await {
-    Ok(res) => {
-      HttpResponse::Ok().json(Res {
-        data: res.job,
-      })
-    },
-    Err(_) => {
-      HttpResponse::InternalServerError().json(Res {
-        data: "internal server error".to_string()
-      })
-    },
-  }
-}
\ No newline at end of file
+  Ok(HttpResponse::Ok().json(Res::<Response> {
+    data: res,
+  }))
+}
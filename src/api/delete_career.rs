@@ -0,0 +1,11 @@
+use std::sync::Arc;
+
+use actix_web::{HttpResponse, Responder, web};
+
+use crate::{api::{error::ApiError, update_career::CareerPath}, domain::career::delete_career, middleware::auth_user::AuthUser, repositories::career::Repository};
+
+pub async fn delete_career(repo: web::Data<Arc<dyn Repository>>, auth: AuthUser, path: web::Path<CareerPath>) -> Result<impl Responder, ApiError> {
+  delete_career::execute(repo.get_ref().clone(), auth.principal(), delete_career::Request { id: path.id }).await?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
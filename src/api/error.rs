@@ -0,0 +1,464 @@
+use std::fmt::{Display, Formatter};
+
+use actix_web::{error::{JsonPayloadError, PathError, QueryPayloadError}, http::{header::{self, HeaderValue}, StatusCode}, HttpResponse, ResponseError};
+use serde::Serialize;
+
+use crate::{
+  domain::{
+    auth::{assign_role, authorization_code, fetch_access_token, personal_access_token, refresh_token, revoke_sessions, sign_out},
+    career::{create_career, delete_career, hide_career, experience_summary, entity::{FieldError, ValidationError}, find_by_user_id, update_career},
+    resume::export_resume,
+    search::search_profiles,
+    user::{fetch_one_user, fetch_by_login, list_users, update_user, update_channels, verify_email},
+  },
+  middleware::{auth_user::AuthError, trace_id},
+};
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+pub const TRACE_ID_HEADER: &str = "x-trace-id";
+const RETRY_AFTER_SECONDS: u32 = 5;
+
+/// Every error a handler returns, rendered as an RFC 7807 `application/problem+json` body.
+///
+/// `code` is stable and meant for clients to branch on; `detail` is for humans and may change.
+#[derive(Debug)]
+pub struct ApiError {
+  status: StatusCode,
+  code: &'static str,
+  detail: Option<String>,
+  errors: Vec<FieldError>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all="camelCase")]
+struct Problem<'a> {
+  #[serde(rename="type")]
+  kind: &'static str,
+  title: &'static str,
+  status: u16,
+  code: &'static str,
+  #[serde(skip_serializing_if="Option::is_none")]
+  detail: Option<&'a str>,
+  #[serde(skip_serializing_if="<[FieldError]>::is_empty")]
+  errors: &'a [FieldError],
+  trace_id: &'a str,
+}
+
+impl ApiError {
+  pub fn new(status: StatusCode, code: &'static str) -> Self {
+    Self { status, code, detail: None, errors: vec![] }
+  }
+
+  pub fn with_detail(self, detail: impl Into<String>) -> Self {
+    Self { detail: Some(detail.into()), ..self }
+  }
+
+  pub fn bad_request(code: &'static str) -> Self {
+    Self::new(StatusCode::BAD_REQUEST, code)
+  }
+
+  pub fn forbidden() -> Self {
+    Self::new(StatusCode::FORBIDDEN, "forbidden")
+  }
+
+  pub fn not_found(code: &'static str) -> Self {
+    Self::new(StatusCode::NOT_FOUND, code)
+  }
+
+  pub fn conflict(code: &'static str) -> Self {
+    Self::new(StatusCode::CONFLICT, code)
+  }
+
+  /// 422 with one `{ field, message }` per violation.
+  pub fn validation(errors: Vec<FieldError>) -> Self {
+    Self { errors, ..Self::new(StatusCode::UNPROCESSABLE_ENTITY, "validation_failed") }
+  }
+
+  /// 503, the client is told to retry shortly.
+  pub fn unavailable() -> Self {
+    Self::new(StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+  }
+
+  pub fn internal() -> Self {
+    Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal")
+  }
+
+  fn problem<'a>(&'a self, trace_id: &'a str) -> Problem<'a> {
+    Problem {
+      kind: "about:blank",
+      title: self.status.canonical_reason().unwrap_or("Error"),
+      status: self.status.as_u16(),
+      code: self.code,
+      detail: self.detail.as_deref(),
+      errors: &self.errors,
+      trace_id,
+    }
+  }
+}
+
+impl Display for ApiError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match &self.detail {
+      Some(detail) => write!(f, "{} {}: {}", self.status.as_u16(), self.code, detail),
+      None => write!(f, "{} {}", self.status.as_u16(), self.code),
+    }
+  }
+}
+
+impl ResponseError for ApiError {
+  fn status_code(&self) -> StatusCode {
+    self.status
+  }
+
+  fn error_response(&self) -> HttpResponse {
+    // quoted by clients when reporting a problem, and logged for server errors
+    let trace_id = trace_id::current();
+    if self.status.is_server_error() {
+      log::error!("[{}] {}", trace_id, self);
+    }
+
+    let mut res = HttpResponse::build(self.status);
+    res.content_type(PROBLEM_JSON);
+    res.insert_header((TRACE_ID_HEADER, trace_id.as_str()));
+    if self.status == StatusCode::UNAUTHORIZED {
+      res.insert_header((header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer")));
+    }
+    if self.status == StatusCode::SERVICE_UNAVAILABLE {
+      res.insert_header((header::RETRY_AFTER, RETRY_AFTER_SECONDS));
+    }
+    res.body(serde_json::to_string(&self.problem(&trace_id)).unwrap_or_default())
+  }
+}
+
+fn field_errors(errors: &[ValidationError]) -> Vec<FieldError> {
+  errors.iter().map(FieldError::from).collect()
+}
+
+impl From<AuthError> for ApiError {
+  fn from(e: AuthError) -> Self {
+    match e {
+      AuthError::Missing => Self::new(StatusCode::UNAUTHORIZED, "missing_token"),
+      AuthError::Invalid => Self::new(StatusCode::UNAUTHORIZED, "invalid_token"),
+      AuthError::Forbidden => Self::forbidden(),
+      AuthError::Unavailable => Self::unavailable(),
+    }
+  }
+}
+
+impl From<JsonPayloadError> for ApiError {
+  fn from(e: JsonPayloadError) -> Self {
+    Self::bad_request("malformed_body").with_detail(e.to_string())
+  }
+}
+
+impl From<PathError> for ApiError {
+  fn from(e: PathError) -> Self {
+    Self::bad_request("malformed_path").with_detail(e.to_string())
+  }
+}
+
+impl From<QueryPayloadError> for ApiError {
+  fn from(e: QueryPayloadError) -> Self {
+    Self::bad_request("malformed_query").with_detail(e.to_string())
+  }
+}
+
+impl From<fetch_one_user::Error> for ApiError {
+  fn from(e: fetch_one_user::Error) -> Self {
+    match e {
+      fetch_one_user::Error::BadRequest => Self::bad_request("invalid_user_id"),
+      fetch_one_user::Error::NotFound => Self::not_found("user_not_found"),
+      fetch_one_user::Error::Unavailable => Self::unavailable(),
+      fetch_one_user::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<fetch_by_login::Error> for ApiError {
+  fn from(e: fetch_by_login::Error) -> Self {
+    match e {
+      fetch_by_login::Error::BadRequest => Self::bad_request("invalid_login"),
+      fetch_by_login::Error::NotFound => Self::not_found("user_not_found"),
+      fetch_by_login::Error::Unavailable => Self::unavailable(),
+      fetch_by_login::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<list_users::Error> for ApiError {
+  fn from(e: list_users::Error) -> Self {
+    match e {
+      list_users::Error::InvalidLimit => Self::bad_request("invalid_limit"),
+      list_users::Error::InvalidCursor => Self::bad_request("invalid_cursor"),
+      list_users::Error::SignInRequired => Self::new(StatusCode::UNAUTHORIZED, "missing_token"),
+      list_users::Error::Unavailable => Self::unavailable(),
+      list_users::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<search_profiles::Error> for ApiError {
+  fn from(e: search_profiles::Error) -> Self {
+    match e {
+      search_profiles::Error::BadRequest => Self::bad_request("invalid_search"),
+      search_profiles::Error::Unavailable => Self::unavailable(),
+      search_profiles::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<export_resume::Error> for ApiError {
+  fn from(e: export_resume::Error) -> Self {
+    match e {
+      export_resume::Error::BadRequest => Self::bad_request("invalid_user_id"),
+      export_resume::Error::NotFound => Self::not_found("user_not_found"),
+      export_resume::Error::Unavailable => Self::unavailable(),
+      export_resume::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<update_user::Error> for ApiError {
+  fn from(e: update_user::Error) -> Self {
+    match e {
+      update_user::Error::BadRequest => Self::bad_request("invalid_user_id"),
+      update_user::Error::Validation(errors) => Self::validation(errors),
+      update_user::Error::Forbidden => Self::forbidden(),
+      update_user::Error::Unavailable => Self::unavailable(),
+      update_user::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<update_channels::Error> for ApiError {
+  fn from(e: update_channels::Error) -> Self {
+    match e {
+      update_channels::Error::BadRequest => Self::bad_request("invalid_user_id"),
+      update_channels::Error::Validation(errors) => Self::validation(
+        errors.iter().map(|e| FieldError { field: e.kind().field(), message: e.to_string() }).collect()
+      ),
+      update_channels::Error::Forbidden => Self::forbidden(),
+      update_channels::Error::Unavailable => Self::unavailable(),
+      update_channels::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<verify_email::Error> for ApiError {
+  fn from(e: verify_email::Error) -> Self {
+    match e {
+      verify_email::Error::InvalidToken => Self::bad_request("invalid_verification_token").with_detail("unknown, used or expired link"),
+      verify_email::Error::Unavailable => Self::unavailable(),
+      verify_email::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<authorization_code::Error> for ApiError {
+  fn from(e: authorization_code::Error) -> Self {
+    match e {
+      authorization_code::Error::UnknownProvider => Self::bad_request("unknown_provider"),
+      authorization_code::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<fetch_access_token::Error> for ApiError {
+  fn from(e: fetch_access_token::Error) -> Self {
+    match e {
+      fetch_access_token::Error::BadRequest => Self::bad_request("sign_in_failed"),
+      fetch_access_token::Error::InvalidState => Self::bad_request("invalid_state").with_detail("unknown or expired state"),
+      fetch_access_token::Error::Unavailable => Self::unavailable(),
+      fetch_access_token::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<refresh_token::Error> for ApiError {
+  fn from(e: refresh_token::Error) -> Self {
+    match e {
+      refresh_token::Error::Invalid => Self::new(StatusCode::UNAUTHORIZED, "invalid_refresh_token"),
+      refresh_token::Error::Expired => Self::new(StatusCode::UNAUTHORIZED, "refresh_token_expired"),
+      refresh_token::Error::Reused => Self::new(StatusCode::UNAUTHORIZED, "refresh_token_reused"),
+      refresh_token::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<sign_out::Error> for ApiError {
+  fn from(e: sign_out::Error) -> Self {
+    match e {
+      sign_out::Error::NotASession => Self::bad_request("not_a_session").with_detail("personal access tokens are revoked with DELETE /tokens/{id}"),
+      sign_out::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<revoke_sessions::Error> for ApiError {
+  fn from(e: revoke_sessions::Error) -> Self {
+    match e {
+      revoke_sessions::Error::Forbidden => Self::forbidden(),
+      revoke_sessions::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<assign_role::Error> for ApiError {
+  fn from(e: assign_role::Error) -> Self {
+    match e {
+      assign_role::Error::Forbidden => Self::forbidden(),
+      assign_role::Error::UnknownUser => Self::not_found("user_not_found"),
+      assign_role::Error::UnknownRole => Self::not_found("role_not_found"),
+      assign_role::Error::NotAssigned => Self::not_found("role_not_assigned"),
+      assign_role::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<personal_access_token::Error> for ApiError {
+  fn from(e: personal_access_token::Error) -> Self {
+    let invalid = |field: &'static str, message: String| Self::validation(vec![FieldError { field, message }]);
+
+    match e {
+      personal_access_token::Error::InvalidName => invalid("name", "name must be 1 to 100 characters".to_string()),
+      personal_access_token::Error::InvalidScope(scope) => invalid("scopes", format!("scope `{}` is not allowed", scope)),
+      personal_access_token::Error::InvalidExpiry => invalid("expiresInDays", "expiresInDays must be 1 to 366".to_string()),
+      personal_access_token::Error::Conflict => Self::conflict("token_name_taken"),
+      personal_access_token::Error::NotFound => Self::not_found("token_not_found"),
+      personal_access_token::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<create_career::Error> for ApiError {
+  fn from(e: create_career::Error) -> Self {
+    match e {
+      create_career::Error::Validation(errors) => Self::validation(field_errors(&errors)),
+      create_career::Error::UnknownUser => Self::not_found("user_not_found"),
+      create_career::Error::Forbidden => Self::forbidden(),
+      create_career::Error::Unavailable => Self::unavailable(),
+      create_career::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<find_by_user_id::Error> for ApiError {
+  fn from(e: find_by_user_id::Error) -> Self {
+    match e {
+      find_by_user_id::Error::BadRequest => Self::bad_request("invalid_user_id"),
+      find_by_user_id::Error::InvalidLimit => Self::bad_request("invalid_limit"),
+      find_by_user_id::Error::InvalidCursor => Self::bad_request("invalid_cursor"),
+      find_by_user_id::Error::Unavailable => Self::unavailable(),
+      find_by_user_id::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<experience_summary::Error> for ApiError {
+  fn from(e: experience_summary::Error) -> Self {
+    match e {
+      experience_summary::Error::BadRequest => Self::bad_request("invalid_user_id"),
+      experience_summary::Error::Unavailable => Self::unavailable(),
+      experience_summary::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<update_career::Error> for ApiError {
+  fn from(e: update_career::Error) -> Self {
+    match e {
+      update_career::Error::Validation(errors) => Self::validation(field_errors(&errors)),
+      update_career::Error::NotFound => Self::not_found("career_not_found"),
+      update_career::Error::Forbidden => Self::forbidden(),
+      update_career::Error::Unavailable => Self::unavailable(),
+      update_career::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<delete_career::Error> for ApiError {
+  fn from(e: delete_career::Error) -> Self {
+    match e {
+      delete_career::Error::NotFound => Self::not_found("career_not_found"),
+      delete_career::Error::Forbidden => Self::forbidden(),
+      delete_career::Error::Unavailable => Self::unavailable(),
+      delete_career::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+impl From<hide_career::Error> for ApiError {
+  fn from(e: hide_career::Error) -> Self {
+    match e {
+      hide_career::Error::NotFound => Self::not_found("career_not_found"),
+      hide_career::Error::Forbidden => Self::forbidden(),
+      hide_career::Error::Unavailable => Self::unavailable(),
+      hide_career::Error::Unknown => Self::internal(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use actix_web::body::to_bytes;
+  use serde_json::{json, Value};
+
+  use super::*;
+
+  async fn body(e: ApiError) -> (HttpResponse, Value) {
+    let res = e.error_response();
+    let (res, body) = res.into_parts();
+    (res.set_body(()).map_into_boxed_body(), serde_json::from_slice(&to_bytes(body).await.unwrap()).unwrap())
+  }
+
+  #[actix_web::test]
+  async fn it_should_be_render_a_problem_document() {
+    let (res, body) = body(ApiError::from(delete_career::Error::NotFound)).await;
+
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert_eq!(res.headers().get(header::CONTENT_TYPE).unwrap(), PROBLEM_JSON);
+    assert_eq!(body["type"], json!("about:blank"));
+    assert_eq!(body["title"], json!("Not Found"));
+    assert_eq!(body["status"], json!(404));
+    assert_eq!(body["code"], json!("career_not_found"));
+    assert!(body.get("errors").is_none());
+    assert_eq!(body["traceId"].as_str(), res.headers().get(TRACE_ID_HEADER).and_then(|h| h.to_str().ok()));
+  }
+
+  #[actix_web::test]
+  async fn it_should_be_list_field_errors() {
+    let (res, body) = body(ApiError::from(create_career::Error::Validation(vec![ValidationError::EmptyCompanyName, ValidationError::OutBeforeIn]))).await;
+
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], json!("validation_failed"));
+    assert_eq!(body["errors"][0]["field"], json!("company"));
+    assert_eq!(body["errors"][1]["field"], json!("outAt"));
+  }
+
+  #[actix_web::test]
+  async fn it_should_be_ask_for_a_bearer_token_when_unauthorized() {
+    let (res, body) = body(ApiError::from(AuthError::Missing)).await;
+
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(res.headers().get(header::WWW_AUTHENTICATE).unwrap(), "Bearer");
+    assert_eq!(body["code"], json!("missing_token"));
+  }
+
+  #[actix_web::test]
+  async fn it_should_be_ask_to_retry_when_the_database_is_unavailable() {
+    let (res, body) = body(ApiError::from(fetch_one_user::Error::Unavailable)).await;
+
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "5");
+    assert_eq!(body["code"], json!("unavailable"));
+  }
+
+  #[actix_web::test]
+  async fn it_should_be_hide_internal_details() {
+    let (res, body) = body(ApiError::from(update_user::Error::Unknown)).await;
+
+    assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body["code"], json!("internal"));
+    assert!(body.get("detail").is_none());
+  }
+}
@@ -0,0 +1,18 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpResponse, Responder};
+use serde::Serialize;
+
+use crate::{api::error::ApiError, domain::career::experience_summary::{execute, Request}, repositories::career::Repository};
+
+#[derive(Serialize)]
+pub struct Res<T> {
+  pub data: T,
+}
+
+/// Years of experience at a glance, computed from the user's careers.
+pub async fn fetch_experience(repo: web::Data<Arc<dyn Repository>>, req: web::Path<Request>) -> Result<impl Responder, ApiError> {
+  let summary = execute(repo.get_ref().clone(), req.into_inner()).await?;
+
+  Ok(HttpResponse::Ok().json(Res { data: summary }))
+}
@@ -1,30 +1,57 @@
-use actix_web::{web, HttpResponse};
+use std::sync::Arc;
+
+use actix_web::{web, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 
-use crate::domain::auth::fetch_access_token::{execute, Request};
+use crate::{api::error::ApiError, domain::auth::{entity::TokenPair, fetch_access_token::{execute, Repositories, Request}, provider::Providers}, infrastructure::{keys::KeyStore, settings::Settings}, repositories::{user::Repository, refresh_token, pending_authorization, identity, role}};
 
+/// Body of `/signin`: the code and `state` the provider redirected back with.
 #[derive(Debug, Deserialize)]
 pub struct SignInDto {
   pub provider: String,
   pub auth_code: String,
+  pub state: String,
+}
+
+impl From<SignInDto> for Request {
+  fn from(dto: SignInDto) -> Self {
+    Self {
+      provider: dto.provider,
+      auth_code: dto.auth_code,
+      state: dto.state,
+    }
+  }
 }
 
 #[derive(Serialize)]
-pub struct Res {
-  pub data: String,
+pub struct Res<T> {
+  pub data: T,
 }
 
-pub async fn fetch_access_token(req: web::Json<Request>) -> HttpResponse {
-  match execute(req.0).await {
-    Ok(res) => {
-      HttpResponse::Ok().json(Res {
-        data: res,
-      })
-    },
-    Err(_) => {
-      HttpResponse::InternalServerError().json(Res {
-        data: "internal server error".to_string()
-      })
-    }
-  }
-}
\ No newline at end of file
+// one extractor per shared dependency, as actix hands them out
+#[allow(clippy::too_many_arguments)]
+pub async fn fetch_access_token(
+  repo: web::Data<Arc<dyn Repository>>,
+  identities: web::Data<Arc<dyn identity::Repository>>,
+  roles: web::Data<Arc<dyn role::Repository>>,
+  token_repo: web::Data<Arc<dyn refresh_token::Repository>>,
+  authorizations: web::Data<Arc<dyn pending_authorization::Repository>>,
+  providers: web::Data<Arc<Providers>>,
+  settings: web::Data<Settings>,
+  keys: web::Data<Arc<KeyStore>>,
+  req: web::Json<SignInDto>,
+) -> Result<impl Responder, ApiError> {
+  let repos = Repositories {
+    users: repo.get_ref().clone(),
+    identities: identities.get_ref().clone(),
+    roles: roles.get_ref().clone(),
+    tokens: token_repo.get_ref().clone(),
+    authorizations: authorizations.get_ref().clone(),
+  };
+
+  let res = execute(repos, &providers, &settings, keys.get_ref().clone(), Request::from(req.0)).await?;
+
+  Ok(HttpResponse::Ok().json(Res::<TokenPair> {
+    data: res,
+  }))
+}
@@ -1,9 +1,9 @@
 use std::sync::Arc;
 
-use actix_web::{web, HttpResponse};
+use actix_web::{web, http::header::LOCATION, HttpRequest, HttpResponse, Responder};
 use serde::{Serialize, Deserialize};
 
-use crate::{domain::career::{find_by_user_id::{execute, Request, FetchCareerDto, Error}}, repositories::career::PgRepository};
+use crate::{api::error::ApiError, domain::{career::{entity::CareerDto, find_by_user_id::{execute, Options, Request, Response}}, user::fetch_by_login}, middleware::auth_user::AuthUser, repositories::{career::Repository, user}};
 
 #[derive(Deserialize)]
 pub struct Info {
@@ -11,20 +11,51 @@ pub struct Info {
 }
 
 #[derive(Serialize)]
+#[serde(rename_all="camelCase")]
 pub struct Res {
-  pub data: Vec<FetchCareerDto>
+  pub data: Vec<CareerDto>,
+  pub total: u64,
+  pub next_cursor: Option<String>,
 }
 
-pub async fn fetch_career(req: web::Path<Info>) -> HttpResponse {
-  let repo = Arc::new(PgRepository::try_new().await);
-
-  match execute(repo, Request { user_id: req.user_id }).await {
-    Ok(res) => HttpResponse::Ok().json(Res {
+impl From<Response> for Res {
+  fn from(res: Response) -> Self {
+    Self {
       data: res.careers,
-    }),
-    Err(e) => match e {
-      Error::BadRequest => HttpResponse::BadRequest().finish(),
-      Error::Unknown => HttpResponse::InternalServerError().finish(),
-    } 
+      total: res.total,
+      next_cursor: res.next_cursor,
+    }
   }
-}
\ No newline at end of file
+}
+
+/// One page of a user's careers; pass `nextCursor` back as `cursor` for the next one.
+pub async fn fetch_career(repo: web::Data<Arc<dyn Repository>>, auth: AuthUser, req: web::Path<Info>, options: web::Query<Options>) -> Result<impl Responder, ApiError> {
+  let res = execute(repo.get_ref().clone(), Request { user_id: req.user_id, viewer: auth.principal(), options: options.into_inner() }).await?;
+
+  Ok(HttpResponse::Ok().json(Res::from(res)))
+}
+
+/// Careers behind a vanity url; an old handle redirects to the current one while no one else holds it.
+pub async fn fetch_career_by_login(
+  users: web::Data<Arc<dyn user::Repository>>,
+  repo: web::Data<Arc<dyn Repository>>,
+  auth: AuthUser,
+  http: HttpRequest,
+  req: web::Path<fetch_by_login::Request>,
+  options: web::Query<Options>,
+) -> Result<impl Responder, ApiError> {
+  let user = match fetch_by_login::execute(users.get_ref().clone(), req.into_inner()).await? {
+    fetch_by_login::Response::Found(user) => user,
+    fetch_by_login::Response::Moved { login } => {
+      // the page asked for stays the same
+      let location = match http.query_string() {
+        "" => format!("/users/by-login/{}/careers", login),
+        query => format!("/users/by-login/{}/careers?{}", login, query),
+      };
+      return Ok(HttpResponse::TemporaryRedirect().insert_header((LOCATION, location)).finish());
+    },
+  };
+  let res = execute(repo.get_ref().clone(), Request { user_id: user.id, viewer: auth.principal(), options: options.into_inner() }).await?;
+
+  Ok(HttpResponse::Ok().json(Res::from(res)))
+}
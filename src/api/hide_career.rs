@@ -0,0 +1,17 @@
+use std::sync::Arc;
+
+use actix_web::{HttpResponse, Responder, web};
+
+use crate::{api::{error::ApiError, update_career::CareerPath}, domain::career::hide_career, middleware::auth_user::AuthUser, repositories::career::Repository};
+
+pub async fn hide_career(repo: web::Data<Arc<dyn Repository>>, auth: AuthUser, path: web::Path<CareerPath>) -> Result<impl Responder, ApiError> {
+  hide_career::execute(repo.get_ref().clone(), auth.principal(), hide_career::Request { id: path.id, hidden: true }).await?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn show_career(repo: web::Data<Arc<dyn Repository>>, auth: AuthUser, path: web::Path<CareerPath>) -> Result<impl Responder, ApiError> {
+  hide_career::execute(repo.get_ref().clone(), auth.principal(), hide_career::Request { id: path.id, hidden: false }).await?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,9 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpResponse};
+
+use crate::infrastructure::keys::KeyStore;
+
+pub async fn jwks(keys: web::Data<Arc<KeyStore>>) -> HttpResponse {
+  HttpResponse::Ok().json(keys.jwks())
+}
@@ -1,5 +1,18 @@
+pub mod error;
 pub mod fetch_access_token;
 pub mod authorization_code;
 pub mod create_career;
 pub mod fetch_career;
-pub mod user;
\ No newline at end of file
+pub mod user;
+pub mod jwks;
+pub mod refresh_token;
+pub mod sign_out;
+pub mod revoke_sessions;
+pub mod assign_role;
+pub mod personal_access_token;
+pub mod update_career;
+pub mod delete_career;
+pub mod hide_career;
+pub mod search;
+pub mod experience_summary;
+pub mod resume;
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use actix_web::{http::StatusCode, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::{api::error::ApiError, domain::auth::personal_access_token::{create, list, revoke, CreateRequest}, middleware::auth_user::AuthUser, repositories::personal_access_token::Repository};
+
+#[derive(Serialize)]
+pub struct Res<T> {
+  pub data: T,
+}
+
+#[derive(Deserialize)]
+pub struct TokenPath {
+  pub id: i64,
+}
+
+pub async fn create_token(repo: web::Data<Arc<dyn Repository>>, auth: AuthUser, req: web::Json<CreateRequest>) -> Result<impl Responder, ApiError> {
+  // tokens are managed from a signed-in session, a leaked token must not mint more
+  if auth.0.scopes.is_some() {
+    return Err(ApiError::new(StatusCode::FORBIDDEN, "session_required"));
+  }
+
+  let created = create(repo.get_ref().clone(), &auth.principal(), req.0).await?;
+
+  Ok(HttpResponse::Created().json(Res { data: created }))
+}
+
+pub async fn list_tokens(repo: web::Data<Arc<dyn Repository>>, auth: AuthUser) -> Result<impl Responder, ApiError> {
+  let tokens = list(repo.get_ref().clone(), &auth.principal()).await?;
+
+  Ok(HttpResponse::Ok().json(Res { data: tokens }))
+}
+
+pub async fn revoke_token(repo: web::Data<Arc<dyn Repository>>, auth: AuthUser, path: web::Path<TokenPath>) -> Result<impl Responder, ApiError> {
+  revoke(repo.get_ref().clone(), &auth.principal(), path.id).await?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
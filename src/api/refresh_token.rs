@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpResponse, Responder};
+use serde::Serialize;
+
+use crate::{api::error::ApiError, domain::auth::{entity::TokenPair, refresh_token::{execute, Request}}, infrastructure::{keys::KeyStore, settings::Settings}, repositories::{user, role, refresh_token::Repository}};
+
+#[derive(Serialize)]
+pub struct Res<T> {
+  pub data: T,
+}
+
+pub async fn refresh_token(
+  user_repo: web::Data<Arc<dyn user::Repository>>,
+  roles: web::Data<Arc<dyn role::Repository>>,
+  repo: web::Data<Arc<dyn Repository>>,
+  settings: web::Data<Settings>,
+  keys: web::Data<Arc<KeyStore>>,
+  req: web::Json<Request>,
+) -> Result<impl Responder, ApiError> {
+  let res = execute(user_repo.get_ref().clone(), roles.get_ref().clone(), repo.get_ref().clone(), &keys, &settings.jwt, req.0).await?;
+
+  Ok(HttpResponse::Ok().json(Res::<TokenPair> {
+    data: res,
+  }))
+}
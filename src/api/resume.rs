@@ -0,0 +1,22 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::{
+  api::error::ApiError,
+  domain::resume::export_resume::{execute, Request},
+  middleware::auth_user::AuthUser,
+  repositories::{career, user},
+};
+
+/// The bare jsonresume.org document, not wrapped in `data`, so resume themes can read it as is.
+pub async fn export_resume(
+  users: web::Data<Arc<dyn user::Repository>>,
+  careers: web::Data<Arc<dyn career::Repository>>,
+  auth: AuthUser,
+  req: web::Path<Request>,
+) -> Result<impl Responder, ApiError> {
+  let resume = execute(users.get_ref().clone(), careers.get_ref().clone(), auth.id, req.into_inner()).await?;
+
+  Ok(HttpResponse::Ok().json(resume))
+}
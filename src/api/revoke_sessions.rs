@@ -0,0 +1,23 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+
+use crate::{api::error::ApiError, domain::auth::revoke_sessions::{execute, Request}, middleware::auth_user::AuthUser, repositories::{revocation, refresh_token, personal_access_token}};
+
+#[derive(Deserialize)]
+pub struct RevokeSessionsPath {
+  pub id: i64,
+}
+
+pub async fn revoke_sessions(
+  revocations: web::Data<Arc<dyn revocation::Repository>>,
+  token_repo: web::Data<Arc<dyn refresh_token::Repository>>,
+  personal_tokens: web::Data<Arc<dyn personal_access_token::Repository>>,
+  auth: AuthUser,
+  path: web::Path<RevokeSessionsPath>,
+) -> Result<impl Responder, ApiError> {
+  execute(revocations.get_ref().clone(), token_repo.get_ref().clone(), personal_tokens.get_ref().clone(), auth.principal(), Request { user_id: path.id }).await?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
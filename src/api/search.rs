@@ -0,0 +1,24 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpResponse, Responder};
+use serde::Serialize;
+
+use crate::{
+  api::{error::ApiError, user::for_viewer},
+  domain::{search::search_profiles::{execute, Request}, user::fetch_one_user},
+  middleware::auth_user::{AuthUser, Optional},
+  repositories::search::Repository,
+};
+
+#[derive(Serialize)]
+pub struct Res<T> {
+  pub data: T,
+}
+
+/// Public, profiles best matching `q` first.
+pub async fn search(repo: web::Data<Arc<dyn Repository>>, viewer: Optional<AuthUser>, req: web::Query<Request>) -> Result<impl Responder, ApiError> {
+  let users = execute(repo.get_ref().clone(), req.into_inner()).await?;
+  let data = users.into_iter().map(|user| for_viewer(fetch_one_user::Response::from(user), viewer.0.as_ref())).collect::<Vec<_>>();
+
+  Ok(HttpResponse::Ok().json(Res { data }))
+}
@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+
+use crate::{api::error::ApiError, domain::auth::sign_out::{execute, Request}, middleware::auth_user::AuthUser, repositories::{revocation, refresh_token}};
+
+#[derive(Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct SignOutDto {
+  pub refresh_token: Option<String>,
+}
+
+pub async fn sign_out(
+  revocations: web::Data<Arc<dyn revocation::Repository>>,
+  token_repo: web::Data<Arc<dyn refresh_token::Repository>>,
+  auth: AuthUser,
+  req: Option<web::Json<SignOutDto>>,
+) -> Result<impl Responder, ApiError> {
+  let req = Request {
+    claims: auth.0,
+    refresh_token: req.and_then(|req| req.0.refresh_token),
+  };
+
+  execute(revocations.get_ref().clone(), token_repo.get_ref().clone(), req).await?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
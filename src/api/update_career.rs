@@ -0,0 +1,24 @@
+use std::sync::Arc;
+
+use actix_web::{HttpResponse, Responder, web};
+use serde::{Deserialize, Serialize};
+
+use crate::{api::error::ApiError, domain::career::update_career, infrastructure::settings::Settings, middleware::auth_user::AuthUser, repositories::career::Repository};
+
+#[derive(Serialize)]
+pub struct Res<T> {
+  pub data: T,
+}
+
+#[derive(Deserialize)]
+pub struct CareerPath {
+  pub id: i64,
+}
+
+pub async fn update_career(repo: web::Data<Arc<dyn Repository>>, settings: web::Data<Settings>, auth: AuthUser, path: web::Path<CareerPath>, req: web::Json<update_career::Request>) -> Result<impl Responder, ApiError> {
+  let req = update_career::Request { id: path.id, ..req.0 };
+
+  let res = update_career::execute(repo.get_ref().clone(), &settings.career, auth.principal(), req).await?;
+
+  Ok(HttpResponse::Ok().json(Res { data: res }))
+}
@@ -1,21 +1,94 @@
 use std::sync::Arc;
 
-use actix_web::{web, HttpResponse};
+use actix_web::{web, http::header::LOCATION, HttpResponse, Responder};
+use serde::Serialize;
 
-use crate::{domain::user::{ update_user::{execute, Request}, fetch_one_user::{Request as ReqFetchUser, execute as fetch_user_execute}}, repositories::user::PgRepository};
+use crate::{
+  api::error::ApiError,
+  domain::user::{update_user::{execute, EmailChange, Request}, fetch_one_user::{self, Request as ReqFetchUser, execute as fetch_user_execute}, fetch_by_login, list_users, update_channels, verify_email},
+  infrastructure::{mailer::Mailer, settings::Settings},
+  middleware::auth_user::{AuthUser, Optional},
+  repositories::{user::Repository, email_verification},
+};
 
-pub async fn update_user(req: web::Json<Request>) -> HttpResponse {
-  let repo = Arc::new(PgRepository::try_new().await);
-  match execute(repo, req.0).await {
-    Ok(_) => {HttpResponse::Ok().finish()},
-    Err(_) => todo!(),
+#[derive(Serialize)]
+pub struct Res<T> {
+  pub data: T,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct Page<T> {
+  pub data: Vec<T>,
+  pub next_cursor: Option<String>,
+}
+
+/// A changed email answers with the `pendingEmail` its verification link was mailed to.
+pub async fn update_user(
+  repo: web::Data<Arc<dyn Repository>>,
+  verifications: web::Data<Arc<dyn email_verification::Repository>>,
+  mailer: web::Data<Arc<dyn Mailer>>,
+  settings: web::Data<Settings>,
+  auth: AuthUser,
+  req: web::Json<Request>,
+) -> Result<impl Responder, ApiError> {
+  let email_change = EmailChange {
+    verifications: verifications.get_ref().clone(),
+    mailer: mailer.get_ref().clone(),
+    settings: &settings.mail,
+  };
+  let res = execute(repo.get_ref().clone(), email_change, auth.principal(), req.0).await?;
+
+  Ok(HttpResponse::Ok().json(Res { data: res }))
+}
+
+pub async fn update_user_channels(repo: web::Data<Arc<dyn Repository>>, auth: AuthUser, req: web::Json<update_channels::Request>) -> Result<impl Responder, ApiError> {
+  let channels = update_channels::execute(repo.get_ref().clone(), auth.principal(), req.0).await?;
+
+  Ok(HttpResponse::Ok().json(Res { data: channels }))
+}
+
+/// Public, the token from the mailed link is the proof.
+pub async fn verify_email(
+  repo: web::Data<Arc<dyn Repository>>,
+  verifications: web::Data<Arc<dyn email_verification::Repository>>,
+  req: web::Json<verify_email::Request>,
+) -> Result<impl Responder, ApiError> {
+  verify_email::execute(repo.get_ref().clone(), verifications.get_ref().clone(), req.0).await?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
+
+// the email, last sign-in and hidden contact channels are only shown to the profile's owner
+pub(crate) fn for_viewer(mut res: fetch_one_user::Response, viewer: Option<&AuthUser>) -> fetch_one_user::Response {
+  if viewer.is_none_or(|viewer| viewer.id != res.id) {
+    res.email = None;
+    res.last_signed_in_at = None;
+    res.channels = res.channels.visible();
   }
+
+  res
+}
+
+/// Public profile.
+pub async fn fetch_user(repo: web::Data<Arc<dyn Repository>>, viewer: Optional<AuthUser>, req: web::Path<ReqFetchUser>) -> Result<impl Responder, ApiError> {
+  let res = fetch_user_execute(repo.get_ref().clone(), ReqFetchUser { id: req.id }).await?;
+
+  Ok(HttpResponse::Ok().json(for_viewer(res, viewer.0.as_ref())))
 }
 
-pub async fn fetch_user(req: web::Path<ReqFetchUser>) -> HttpResponse {
-  let repo = Arc::new(PgRepository::try_new().await);
-  match fetch_user_execute(repo, ReqFetchUser { id: req.id }).await {
-    Ok(res) => HttpResponse::Ok().json(res),
-    Err(_) => todo!(),
+/// Public profile behind a vanity url; an old handle redirects to the current one while no one else holds it.
+pub async fn fetch_user_by_login(repo: web::Data<Arc<dyn Repository>>, viewer: Optional<AuthUser>, req: web::Path<fetch_by_login::Request>) -> Result<impl Responder, ApiError> {
+  match fetch_by_login::execute(repo.get_ref().clone(), req.into_inner()).await? {
+    fetch_by_login::Response::Found(user) => Ok(HttpResponse::Ok().json(for_viewer(fetch_one_user::Response::from(user), viewer.0.as_ref()))),
+    fetch_by_login::Response::Moved { login } => Ok(HttpResponse::TemporaryRedirect().insert_header((LOCATION, format!("/users/by-login/{}", login))).finish()),
   }
-}
\ No newline at end of file
+}
+
+/// Public directory, one page at a time; pass `nextCursor` back as `cursor` for the next one.
+pub async fn list_users(repo: web::Data<Arc<dyn Repository>>, viewer: Optional<AuthUser>, req: web::Query<list_users::Request>) -> Result<impl Responder, ApiError> {
+  let res = list_users::execute(repo.get_ref().clone(), viewer.0.as_ref().map(AuthUser::principal), req.into_inner()).await?;
+  let data = res.users.into_iter().map(|user| for_viewer(fetch_one_user::Response::from(user), viewer.0.as_ref())).collect();
+
+  Ok(HttpResponse::Ok().json(Page { data, next_cursor: res.next_cursor }))
+}
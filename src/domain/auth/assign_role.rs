@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use crate::repositories::role::{Repository, AssignError};
+
+use super::{entity::Principal, role::{ADMIN, MODERATOR, ROLE_ASSIGN}};
+
+pub struct Request {
+  pub user_id: i64,
+  pub role: String,
+}
+
+#[derive(Debug)]
+pub enum Error {
+  Forbidden,
+  UnknownUser,
+  UnknownRole,
+  /// the user did not have the role
+  NotAssigned,
+  Unknown,
+}
+
+impl From<AssignError> for Error {
+  fn from(e: AssignError) -> Self {
+    match e {
+      AssignError::UnknownUser => Error::UnknownUser,
+      AssignError::UnknownRole => Error::UnknownRole,
+      AssignError::Unknown => Error::Unknown,
+    }
+  }
+}
+
+/// Grants a role. Takes effect once the user's access token is refreshed.
+pub async fn grant(repo: Arc<dyn Repository>, principal: &Principal, req: Request) -> Result<(), Error> {
+  if !principal.has_permission(ROLE_ASSIGN) {
+    return Err(Error::Forbidden);
+  }
+
+  Ok(repo.assign(req.user_id, &req.role).await?)
+}
+
+/// Takes a role away. Access tokens issued before keep it until they expire.
+pub async fn revoke(repo: Arc<dyn Repository>, principal: &Principal, req: Request) -> Result<(), Error> {
+  if !principal.has_permission(ROLE_ASSIGN) {
+    return Err(Error::Forbidden);
+  }
+
+  match repo.unassign(req.user_id, &req.role).await? {
+    true => Ok(()),
+    false => Err(Error::NotAssigned),
+  }
+}
+
+/// Makes the very first admin, there is nobody yet who could grant it.
+/// Only reachable through the `promote-admin` command.
+pub async fn bootstrap_admin(repo: Arc<dyn Repository>, user_id: i64) -> Result<(), Error> {
+  Ok(repo.assign(user_id, ADMIN).await?)
+}
+
+/// Makes a moderator without going through an admin account.
+/// Only reachable through the `promote-moderator` command.
+pub async fn bootstrap_moderator(repo: Arc<dyn Repository>, user_id: i64) -> Result<(), Error> {
+  Ok(repo.assign(user_id, MODERATOR).await?)
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{domain::auth::role::{CAREER_HIDE, USER_WRITE}, repositories::role::InMemoryRepository};
+
+  use super::*;
+
+  fn admin() -> Principal {
+    Principal { user_id: 1, permissions: vec![ROLE_ASSIGN.to_string()] }
+  }
+
+  fn request(user_id: i64, role: &str) -> Request {
+    Request { user_id, role: role.to_string() }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_grant_and_revoke_a_role() {
+    let repo = Arc::new(InMemoryRepository::new());
+
+    assert!(grant(repo.clone(), &admin(), request(2, MODERATOR)).await.is_ok());
+    assert_eq!(repo.grants(2).await.unwrap().roles, vec![MODERATOR.to_string()]);
+    assert!(repo.grants(2).await.unwrap().permissions.contains(&USER_WRITE.to_string()));
+
+    assert!(revoke(repo.clone(), &admin(), request(2, MODERATOR)).await.is_ok());
+    assert!(repo.grants(2).await.unwrap().roles.is_empty());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_a_forbidden_error_without_the_permission() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let moderator = Principal { user_id: 2, permissions: vec![USER_WRITE.to_string()] };
+
+    match grant(repo.clone(), &moderator, request(2, ADMIN)).await {
+      Err(Error::Forbidden) => {},
+      _ => unreachable!(),
+    }
+    assert!(repo.grants(2).await.unwrap().roles.is_empty());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_reject_an_unknown_role() {
+    match grant(Arc::new(InMemoryRepository::new()), &admin(), request(2, "owner")).await {
+      Err(Error::UnknownRole) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_not_assigned_when_revoking_a_missing_role() {
+    match revoke(Arc::new(InMemoryRepository::new()), &admin(), request(2, MODERATOR)).await {
+      Err(Error::NotAssigned) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_bootstrap_an_admin() {
+    let repo = Arc::new(InMemoryRepository::new());
+
+    assert!(bootstrap_admin(repo.clone(), 1).await.is_ok());
+    assert!(bootstrap_admin(repo.clone(), 1).await.is_ok());
+
+    let grants = repo.grants(1).await.unwrap();
+    assert_eq!(grants.roles, vec![ADMIN.to_string()]);
+    assert!(grants.permissions.contains(&ROLE_ASSIGN.to_string()));
+  }
+
+  #[tokio::test]
+  async fn it_should_be_bootstrap_a_moderator_who_can_hide_careers() {
+    let repo = Arc::new(InMemoryRepository::new());
+
+    assert!(bootstrap_moderator(repo.clone(), 2).await.is_ok());
+
+    let grants = repo.grants(2).await.unwrap();
+    assert_eq!(grants.roles, vec![MODERATOR.to_string()]);
+    assert!(grants.permissions.contains(&CAREER_HIDE.to_string()));
+    assert!(!grants.permissions.contains(&ROLE_ASSIGN.to_string()));
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_error_when_the_repo_fails() {
+    match bootstrap_admin(Arc::new(InMemoryRepository::new().with_error()), 1).await {
+      Err(Error::Unknown) => {},
+      _ => unreachable!(),
+    }
+  }
+}
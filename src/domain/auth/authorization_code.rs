@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use chrono::{Duration, FixedOffset, Utc};
+use oauth2::{PkceCodeChallenge, CsrfToken, url::Url};
+use serde::Deserialize;
+
+use crate::{infrastructure::settings::AuthSettings, repositories::pending_authorization::Repository};
+
+use super::{entity::PendingAuthorizationEntity, provider::Providers};
+
+const DEFAULT_PROVIDER: &str = "github";
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+  pub provider: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+  UnknownProvider,
+  Unknown,
+}
+
+/// Builds the provider's authorize url and remembers its `state` and PKCE verifier for `/signin`.
+pub async fn execute(repo: Arc<dyn Repository>, providers: &Providers, settings: &AuthSettings, req: Request) -> Result<Url, Error> {
+  let provider = match providers.get(req.provider.as_deref().unwrap_or(DEFAULT_PROVIDER)) {
+    Some(provider) => provider,
+    None => return Err(Error::UnknownProvider),
+  };
+
+  let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+  let state = CsrfToken::new_random();
+  let authorization = PendingAuthorizationEntity {
+    state: state.secret().to_owned(),
+    pkce_verifier: pkce_verifier.secret().to_owned(),
+    provider: provider.name().to_string(),
+    expires_at: Utc::now().with_timezone(&FixedOffset::east(9 * 3600)) + Duration::seconds(settings.authorization_ttl),
+  };
+
+  let auth_url = provider.authorize_url(state, pkce_challenge).await.map_err(|_| Error::Unknown)?;
+
+  match repo.insert(authorization).await {
+    Ok(_) => Ok(auth_url),
+    Err(_) => Err(Error::Unknown),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{infrastructure::settings::Settings, repositories::pending_authorization::InMemoryRepository};
+
+  use super::*;
+
+  async fn authorize(repo: Arc<InMemoryRepository>, provider: Option<&str>) -> Result<Url, Error> {
+    let settings = Settings::test();
+
+    execute(repo, &Providers::new(&settings), &settings.auth, Request { provider: provider.map(str::to_string) }).await
+  }
+
+  #[tokio::test]
+  async fn it_should_be_store_the_state_of_the_authorize_url() {
+    let repo = Arc::new(InMemoryRepository::new());
+
+    let res = authorize(repo.clone(), None).await;
+
+    match res {
+      Ok(url) => {
+        let (_, state) = url.query_pairs().find(|(key, _)| key == "state").unwrap();
+        assert!(url.query_pairs().any(|(key, value)| key == "code_challenge_method" && value == "S256"));
+        assert_eq!(repo.take(&state).await.unwrap().provider, "github".to_string());
+      },
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_provider_error() {
+    let repo = Arc::new(InMemoryRepository::new());
+
+    match authorize(repo, Some("myspace")).await {
+      Err(Error::UnknownProvider) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_error_when_the_store_fails() {
+    let repo = Arc::new(InMemoryRepository::new().with_error());
+
+    match authorize(repo, Some("github")).await {
+      Err(Error::Unknown) => {},
+      _ => unreachable!(),
+    }
+  }
+}
@@ -1,36 +1,29 @@
-use std::{env, time::SystemTime, sync::Arc};
+use std::{time::SystemTime, sync::Arc};
 
-use dotenv::dotenv;
-use jsonwebtoken::{encode, Header, EncodingKey};
-use oauth2::{AuthorizationCode, basic::{BasicClient, BasicErrorResponseType, BasicTokenType}, StandardErrorResponse, StandardTokenResponse, EmptyExtraTokenFields, StandardTokenIntrospectionResponse, StandardRevocableToken, RevocationErrorResponseType, ClientId, ClientSecret, AuthUrl, TokenUrl, reqwest::{async_http_client}, TokenResponse};
-use oauth2::RequestTokenError::{ServerResponse, Request, Parse, Other};
+use chrono::{Duration, FixedOffset, TimeZone, Utc};
+use rand::RngCore;
+use sea_orm::prelude::{DateTimeWithTimeZone, Uuid};
 use serde::{Serialize, Deserialize};
-use reqwest::{header::{HeaderMap, HeaderValue, CONTENT_TYPE, USER_AGENT}, Error};
+use sha2::{Digest, Sha256};
 
-use crate::{domain::user::{create_user::{self, Request as UserRequest}, fetch_one_user::{self, Response}, update_user::{self, Request as UpdateUserRequest}}, repositories::user::PgRepository};
+use crate::{infrastructure::{keys::KeyStore, settings::JwtSettings}, repositories::refresh_token};
 
+use super::{personal_access_token::WRITE, role::Grants};
 
-#[derive(Debug, Clone)]
-pub enum OAuthProvider {
-  Github,
-}
-
-pub enum TokenError {
-  ServerReponse,
-  Request,
-  Parse,
-  Other,
-}
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Profile of the signed-in account as every provider adapter reports it.
+#[derive(Debug, Clone, PartialEq)]
 pub struct UserProfile {
-  pub id: i64,
+  /// the provider's stable account id
+  pub subject: String,
   pub login: String,
   pub name: Option<String>,
   pub avatar_url: String,
+  /// primary address the provider has verified, unverified ones are never reported
+  pub email: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all="camelCase")]
 pub struct ResUserProfile {
   pub id: i64,
@@ -39,165 +32,155 @@ pub struct ResUserProfile {
   pub avatar_url: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
+  /// unique token id, the handle used to revoke a single access token
+  pub jti: Uuid,
+  /// issued at / expires at, in milliseconds
+  pub iat: u128,
   pub exp: u128,
   pub aud: Option<String>,
   pub iss: Option<String>,
   pub user: ResUserProfile,
+  /// roles and permissions as they were when the token was issued,
+  /// changes take effect with the next refresh
+  #[serde(flatten)]
+  pub grants: Grants,
+  /// `None` for a signed-in session, the scopes for a personal access token
+  #[serde(skip)]
+  pub scopes: Option<Vec<String>>,
 }
 
-impl TryFrom<String> for OAuthProvider {
-  type Error = ();
+/// The verified caller a use case acts on behalf of.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Principal {
+  pub user_id: i64,
+  pub permissions: Vec<String>,
+}
 
-  fn try_from(t: String) -> Result<Self, Self::Error> {
-      match t.as_str() {
-          "Github" => Ok(Self::Github),
-          _ => Err(()),
-      }
+impl Principal {
+  pub fn has_permission(&self, permission: &str) -> bool {
+    self.permissions.iter().any(|p| p == permission)
   }
-}
 
-impl From<OAuthProvider> for String {
-  fn from(t: OAuthProvider) -> Self {
-      String::from(match t {
-          OAuthProvider::Github => "Github",
-      })
+  /// Users may change their own data, somebody else's takes `permission`.
+  pub fn can_act_for(&self, user_id: i64, permission: &str) -> bool {
+    self.user_id == user_id || self.has_permission(permission)
   }
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct TokenPair {
+  pub access_token: String,
+  pub refresh_token: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct RefreshTokenEntity {
+  pub id: i64,
+  pub user_id: i64,
+  pub family_id: Uuid,
+  pub expires_at: DateTimeWithTimeZone,
+  pub used_at: Option<DateTimeWithTimeZone>,
+  pub revoked_at: Option<DateTimeWithTimeZone>,
+}
+
+/// A long-lived token for scripts, see `personal_access_token`. Only the hash of the secret is stored.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct PersonalAccessTokenEntity {
+  pub id: i64,
+  #[serde(skip)]
+  pub user_id: i64,
+  pub name: String,
+  pub scopes: Vec<String>,
+  pub expires_at: Option<DateTimeWithTimeZone>,
+  pub last_used_at: Option<DateTimeWithTimeZone>,
+  pub created_at: DateTimeWithTimeZone,
+}
+
+/// An authorization started by `/authorization/code`, waiting for the provider to redirect back to `/signin`.
+#[derive(Clone, Debug)]
+pub struct PendingAuthorizationEntity {
+  pub state: String,
+  pub pkce_verifier: String,
+  /// registry name of the provider the user was sent to
+  pub provider: String,
+  pub expires_at: DateTimeWithTimeZone,
+}
+
 #[derive(Debug)]
-pub struct Authentication {
-  pub client: oauth2::Client<StandardErrorResponse<BasicErrorResponseType>, StandardTokenResponse<EmptyExtraTokenFields, BasicTokenType>, BasicTokenType, StandardTokenIntrospectionResponse<EmptyExtraTokenFields, BasicTokenType>, StandardRevocableToken, StandardErrorResponse<RevocationErrorResponseType>>,
-  pub auth_code: AuthorizationCode,
+pub enum IssueError {
+  Sign,
+  Store,
 }
 
-async fn create_user(repo: Arc<PgRepository>, req: UserRequest) -> create_user::Response {
-  create_user::execute(repo, req).await.unwrap_or_else(|err| {
-    panic!("create user failed:: {:?}", err)
-  })
+/// Refresh tokens are opaque random strings, only their sha256 is stored.
+pub fn hash_refresh_token(token: &str) -> String {
+  format!("{:x}", Sha256::digest(token.as_bytes()))
 }
 
-impl Authentication {
-  pub fn new(_provider: OAuthProvider, auth_code: AuthorizationCode) -> Self {
-    dotenv().ok();
+fn generate_refresh_token() -> String {
+  let mut bytes = [0u8; 32];
+  rand::thread_rng().fill_bytes(&mut bytes);
 
-    let client = BasicClient::new(
-      ClientId::new(env::var("GITHUB_CLIENT_ID").expect("").to_string()),
-      Some(ClientSecret::new(env::var("GITHUB_CLIENT_SECRET").expect("").to_string())),
-      AuthUrl::new(env::var("GITHUB_AUTH_URL").expect("").to_string()).expect(""),
-      Some(TokenUrl::new(env::var("GITHUB_TOKEN_URL").expect("").to_string()).expect("")),
-    );
+  base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+pub fn create_jwt(keys: &KeyStore, settings: &JwtSettings, user: ResUserProfile, grants: Grants) -> jsonwebtoken::errors::Result<String> {
+  let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).expect("error");
+
+  let my_claims = Claims {
+    jti: Uuid::new_v4(),
+    iat: now.as_millis(),
+    exp: now.as_millis() + settings.access_token_ttl as u128 * 1000,
+    aud: Some("".to_string()),
+    iss: Some("DECAFO".to_string()),
+    user,
+    grants,
+    scopes: None,
+  };
+
+  keys.encode(&my_claims)
+}
 
-    Self {
-      client,
-      auth_code,
-    }
+fn from_millis(millis: u128) -> DateTimeWithTimeZone {
+  Utc.timestamp_millis(millis as i64).with_timezone(&FixedOffset::east(9 * 3600))
+}
+
+impl Claims {
+  pub fn issued_at(&self) -> DateTimeWithTimeZone {
+    from_millis(self.iat)
+  }
+
+  pub fn expires_at(&self) -> DateTimeWithTimeZone {
+    from_millis(self.exp)
   }
 
-  pub async fn create_jwt(&self, access_token: String) -> Result<String, Error> {
-    let mut headers = HeaderMap::new();
-    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-    headers.insert(USER_AGENT, HeaderValue::from_static("DECAFO"));
-
-    let client = reqwest::Client::builder()
-      .default_headers(headers.clone())
-      .build()
-      .unwrap();
-
-    let request = client.to_owned().get("https://api.github.com/user").headers(headers).bearer_auth(access_token);
-
-
-    match request.send().await {
-      Ok(resp) => {
-        let user = resp.json::<UserProfile>().await.unwrap();
-        let exp  = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).expect("error");
-
-        let repo = Arc::new(PgRepository::try_new().await);
-        let req = UserRequest {
-          id: user.id,
-          login: user.login.clone(),
-          name: user.name.clone().expect(""),
-          avatar_url: user.avatar_url.clone(),
-        };
-        let fetch_request = fetch_one_user::Request {
-          id: user.id,
-        };
-
-        let fetch_one_user = fetch_one_user::execute(repo.clone(), fetch_request).await;
-
-        if fetch_one_user.is_err() {
-          let create_user = create_user::execute(repo, req).await;
-          if create_user.is_err() {
-            panic!("{:?}", create_user.err())
-          } else {
-            let u = create_user.unwrap();
-
-            let my_claims = Claims {
-              exp: exp.as_millis() + (60 * 1000) * 2, // 1hour
-              aud: Some("".to_string()),
-              iss: Some("DECAFO".to_string()),
-              user: ResUserProfile {
-                id: u.id,
-                name: Some(u.name),
-                login: u.login,
-                avatar_url: u.avatar_url,
-              },
-            };
-    
-            let jwt = encode(&Header::default(), &my_claims, &EncodingKey::from_secret("secret".as_ref())).expect("msg");
-    
-            Ok(jwt)
-          }
-        } else {
-          let u = fetch_one_user.unwrap();
-          let my_claims = Claims {
-            exp: exp.as_millis() + (60 * 1000) * 2, // 1hour
-            aud: Some("".to_string()),
-            iss: Some("DECAFO".to_string()),
-            user: ResUserProfile {
-              id: u.id,
-              name: Some(u.name),
-              login: u.login,
-              avatar_url: u.avatar_url,
-            },
-          };
-  
-          let jwt = encode(&Header::default(), &my_claims, &EncodingKey::from_secret("secret".as_ref())).expect("msg");
-  
-          Ok(jwt)
-        }
-      },
-      Err(e) => {
-        println!("{:?}", e);
-        Err(e)
-      },
-    }
+  /// Personal access tokens without the `write` scope may only read.
+  pub fn is_read_only(&self) -> bool {
+    self.scopes.as_ref().is_some_and(|scopes| !scopes.iter().any(|scope| scope == WRITE))
   }
+}
 
-  pub async fn get_access_token(&self) -> Result<String, TokenError> {
-    let code = self.auth_code.clone();
-
-    let token_response = self.client
-      .exchange_code(code)
-      .request_async(async_http_client).await;
-
-    match token_response {
-      Ok(token) => {
-        let token = token.access_token().secret().to_owned();
-
-        Ok(token)
-      },
-      Err(err) => {
-        match err {
-          ServerResponse(_) => {
-            Err(TokenError::ServerReponse)
-          },
-          Request(_) => Err(TokenError::Request),
-          Parse(_, _) => Err(TokenError::Parse),
-          Other(_) => Err(TokenError::Other),
-        }
-      },
-    }
+/// Signs an access token and stores a new refresh token, continuing `family_id` when rotating.
+pub async fn issue_tokens(
+  keys: &KeyStore,
+  settings: &JwtSettings,
+  repo: Arc<dyn refresh_token::Repository>,
+  user: ResUserProfile,
+  grants: Grants,
+  family_id: Option<Uuid>,
+) -> Result<TokenPair, IssueError> {
+  let user_id = user.id;
+  let access_token = create_jwt(keys, settings, user, grants).map_err(|_| IssueError::Sign)?;
+
+  let refresh_token = generate_refresh_token();
+  let expires_at = Utc::now().with_timezone(&FixedOffset::east(9 * 3600)) + Duration::seconds(settings.refresh_token_ttl);
+
+  match repo.insert(user_id, family_id.unwrap_or_else(Uuid::new_v4), hash_refresh_token(&refresh_token), expires_at).await {
+    Ok(_) => Ok(TokenPair { access_token, refresh_token }),
+    Err(_) => Err(IssueError::Store),
   }
-}
\ No newline at end of file
+}
@@ -1,65 +1,192 @@
-use oauth2::AuthorizationCode;
-use serde::Deserialize;
+use std::sync::Arc;
 
-use crate::domain::auth::entity::Authentication;
+use chrono::Utc;
+use oauth2::{AuthorizationCode, PkceCodeVerifier};
 
-use super::entity::OAuthProvider;
+use crate::{
+  domain::user::sign_in,
+  infrastructure::{keys::KeyStore, settings::Settings},
+  repositories::{user::Repository, refresh_token, identity, role, pending_authorization::{self, TakeError}},
+};
 
-#[derive(Debug, Deserialize)]
+use super::{entity::{TokenPair, issue_tokens}, provider::Providers};
+
+#[derive(Debug)]
 pub struct Request {
   pub provider: String,
   pub auth_code: String,
+  /// `state` the provider echoed back, issued by `/authorization/code`
+  pub state: String,
 }
 
 #[derive(Debug)]
 pub enum Error {
   BadRequest,
+  InvalidState,
+  /// the database is unreachable, signing in may be retried
+  Unavailable,
+  Unknown,
 }
 
-pub async fn execute(req: Request) -> Result<String, Error> {
-  match (
-    OAuthProvider::try_from(req.provider),
-    AuthorizationCode::try_from(AuthorizationCode::new(req.auth_code)),
-  ) {
-    (Ok(provider), Ok(auth_code)) => {
-      let auth = Authentication::new(provider, auth_code);
-
-      match auth.get_access_token().await {
-        Ok(access_token) => {
-          match auth.create_jwt(access_token).await {
-            Ok(jwt) => Ok(jwt),
-            Err(_) => Err(Error::BadRequest),
-          }
-        },
-        Err(_) => todo!(),
-      }
-    },
-    _ => Err(Error::BadRequest),
+pub struct Repositories {
+  pub users: Arc<dyn Repository>,
+  pub identities: Arc<dyn identity::Repository>,
+  pub roles: Arc<dyn role::Repository>,
+  pub tokens: Arc<dyn refresh_token::Repository>,
+  pub authorizations: Arc<dyn pending_authorization::Repository>,
+}
+
+pub async fn execute(
+  repos: Repositories,
+  providers: &Providers,
+  settings: &Settings,
+  keys: Arc<KeyStore>,
+  req: Request,
+) -> Result<TokenPair, Error> {
+  let authorization = match repos.authorizations.take(&req.state).await {
+    Ok(authorization) if authorization.expires_at > Utc::now() => authorization,
+    Ok(_) | Err(TakeError::NotFound) => return Err(Error::InvalidState),
+    Err(TakeError::Unknown) => return Err(Error::Unknown),
+  };
+
+  // the code has to be redeemed at the provider the state was issued for
+  if !authorization.provider.eq_ignore_ascii_case(&req.provider) {
+    return Err(Error::InvalidState);
   }
+
+  let provider = match providers.get(&authorization.provider) {
+    Some(provider) => provider,
+    None => return Err(Error::BadRequest),
+  };
+
+  let access_token = provider
+    .exchange_code(AuthorizationCode::new(req.auth_code), PkceCodeVerifier::new(authorization.pkce_verifier))
+    .await
+    .map_err(|_| Error::BadRequest)?;
+  let profile = provider.fetch_profile(&access_token).await.map_err(|_| Error::BadRequest)?;
+
+  let user_id = repos.identities.resolve(provider.name(), &profile.subject).await.map_err(|_| Error::Unknown)?;
+  let req = sign_in::Request {
+    id: user_id,
+    provider: provider.name().to_string(),
+    login: profile.login,
+    name: profile.name,
+    avatar_url: profile.avatar_url,
+    email: profile.email,
+  };
+  let user = sign_in::execute(repos.users, req).await.map_err(|e| match e {
+    sign_in::Error::BadRequest => Error::BadRequest,
+    sign_in::Error::Unavailable => Error::Unavailable,
+    sign_in::Error::Unknown => Error::Unknown,
+  })?;
+
+  let grants = repos.roles.grants(user.id).await.map_err(|_| Error::Unknown)?;
+
+  issue_tokens(&keys, &settings.jwt, repos.tokens, user, grants, None).await.map_err(|_| Error::Unknown)
 }
 
 #[cfg(test)]
 mod tests {
+  use chrono::{Duration, FixedOffset};
+
+  use crate::{
+    domain::auth::entity::PendingAuthorizationEntity,
+    repositories::{
+      user::InMemoryRepository,
+      identity::InMemoryRepository as InMemoryIdentities,
+      role::InMemoryRepository as InMemoryRoles,
+      refresh_token::InMemoryRepository as InMemoryTokenRepository,
+      pending_authorization::{InMemoryRepository as InMemoryAuthorizations, Repository as _},
+    },
+  };
   use super::*;
 
+  async fn authorizations(provider: &str, expires_in: Duration) -> Arc<InMemoryAuthorizations> {
+    let authorizations = Arc::new(InMemoryAuthorizations::new());
+    let _ = authorizations.insert(PendingAuthorizationEntity {
+      state: "state".to_string(),
+      pkce_verifier: "verifier".to_string(),
+      provider: provider.to_string(),
+      expires_at: Utc::now().with_timezone(&FixedOffset::east(9 * 3600)) + expires_in,
+    }).await;
+
+    authorizations
+  }
+
+  async fn sign_in(authorizations: Arc<InMemoryAuthorizations>, req: Request) -> Result<TokenPair, Error> {
+    let settings = Settings::test();
+    let keys = Arc::new(KeyStore::new(&settings.jwt).unwrap());
+    let repos = Repositories {
+      users: Arc::new(InMemoryRepository::_new()),
+      identities: Arc::new(InMemoryIdentities::new()),
+      roles: Arc::new(InMemoryRoles::new()),
+      tokens: Arc::new(InMemoryTokenRepository::new()),
+      authorizations,
+    };
+
+    execute(repos, &Providers::new(&settings), &settings, keys, req).await
+  }
+
   #[tokio::test]
   async fn it_should_be_return_a_bad_request_when_provider_valid() {
-    let req = Request::new("google".to_string(), "test".to_string());
-
-    let res = execute(req);
+    let req = Request::new("google".to_string(), "test".to_string(), "state".to_string());
 
-    match res.await {
+    match sign_in(authorizations("google", Duration::minutes(10)).await, req).await {
       Err(Error::BadRequest) => {},
       _ => unreachable!(),
     }
   }
 
+  #[tokio::test]
+  async fn it_should_be_reject_an_unknown_state() {
+    let req = Request::new("Github".to_string(), "test".to_string(), "forged".to_string());
+
+    match sign_in(authorizations("github", Duration::minutes(10)).await, req).await {
+      Err(Error::InvalidState) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_reject_an_expired_state() {
+    let req = Request::new("Github".to_string(), "test".to_string(), "state".to_string());
+
+    match sign_in(authorizations("github", Duration::seconds(-1)).await, req).await {
+      Err(Error::InvalidState) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_reject_a_state_issued_for_another_provider() {
+    let req = Request::new("gitlab".to_string(), "test".to_string(), "state".to_string());
+
+    match sign_in(authorizations("github", Duration::minutes(10)).await, req).await {
+      Err(Error::InvalidState) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_accept_a_state_only_once() {
+    let authorizations = authorizations("google", Duration::minutes(10)).await;
+    let _ = sign_in(authorizations.clone(), Request::new("google".to_string(), "test".to_string(), "state".to_string())).await;
+
+    let req = Request::new("google".to_string(), "test".to_string(), "state".to_string());
+
+    match sign_in(authorizations, req).await {
+      Err(Error::InvalidState) => {},
+      _ => unreachable!(),
+    }
+  }
+
   impl Request {
-    fn new(provider: String, auth_code: String) -> Self {
+    fn new(provider: String, auth_code: String, state: String) -> Self {
       Self {
         provider,
         auth_code,
+        state,
       }
     }
   }
-}
\ No newline at end of file
+}
@@ -1,2 +1,10 @@
 pub mod entity;
-pub mod fetch_access_token;
\ No newline at end of file
+pub mod fetch_access_token;
+pub mod refresh_token;
+pub mod authorization_code;
+pub mod provider;
+pub mod sign_out;
+pub mod revoke_sessions;
+pub mod role;
+pub mod assign_role;
+pub mod personal_access_token;
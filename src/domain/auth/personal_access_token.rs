@@ -0,0 +1,291 @@
+use std::sync::Arc;
+
+use chrono::{Duration, FixedOffset, Utc};
+use rand::RngCore;
+use sea_orm::prelude::Uuid;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+  domain::user::fetch_one_user,
+  middleware::trace_id,
+  repositories::{user, role, personal_access_token::{Repository, InsertError, FetchOneError}},
+};
+
+use super::{entity::{Claims, Principal, PersonalAccessTokenEntity, ResUserProfile}, role::Grants};
+
+/// Tells personal access tokens apart from JWTs in the `Authorization` header.
+pub const TOKEN_PREFIX: &str = "dcf_pat_";
+
+/// GET, HEAD and OPTIONS requests only
+pub const READ: &str = "read";
+/// anything the user may do with their own data
+pub const WRITE: &str = "write";
+
+const MAX_NAME_LENGTH: usize = 100;
+const MAX_EXPIRES_IN_DAYS: i64 = 366;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct CreateRequest {
+  pub name: String,
+  /// `read`, `write` and any permission the user holds, e.g. `career:write`
+  pub scopes: Vec<String>,
+  /// never expires when missing
+  pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct Created {
+  #[serde(flatten)]
+  pub entity: PersonalAccessTokenEntity,
+  /// the secret, shown this one time only
+  pub token: String,
+}
+
+#[derive(Debug)]
+pub enum Error {
+  InvalidName,
+  InvalidScope(String),
+  InvalidExpiry,
+  /// the user already has a token with that name
+  Conflict,
+  NotFound,
+  Unknown,
+}
+
+#[derive(Debug)]
+pub enum VerifyError {
+  /// unknown, expired, or its user is gone
+  Invalid,
+  Unknown,
+}
+
+fn hash_token(token: &str) -> String {
+  format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn generate_token() -> String {
+  let mut bytes = [0u8; 32];
+  rand::thread_rng().fill_bytes(&mut bytes);
+
+  format!("{}{}", TOKEN_PREFIX, base64::encode_config(bytes, base64::URL_SAFE_NO_PAD))
+}
+
+fn validate_scopes(principal: &Principal, scopes: Vec<String>) -> Result<Vec<String>, Error> {
+  if scopes.is_empty() {
+    return Err(Error::InvalidScope(String::new()));
+  }
+
+  let mut valid = vec![];
+  for scope in scopes {
+    if scope != READ && scope != WRITE && !principal.has_permission(&scope) {
+      return Err(Error::InvalidScope(scope));
+    }
+    if !valid.contains(&scope) {
+      valid.push(scope);
+    }
+  }
+
+  Ok(valid)
+}
+
+pub async fn create(repo: Arc<dyn Repository>, principal: &Principal, req: CreateRequest) -> Result<Created, Error> {
+  let name = req.name.trim().to_string();
+  if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+    return Err(Error::InvalidName);
+  }
+
+  let scopes = validate_scopes(principal, req.scopes)?;
+
+  let expires_at = match req.expires_in_days {
+    Some(days) if (1..=MAX_EXPIRES_IN_DAYS).contains(&days) => Some(Utc::now().with_timezone(&FixedOffset::east(9 * 3600)) + Duration::days(days)),
+    Some(_) => return Err(Error::InvalidExpiry),
+    None => None,
+  };
+
+  let token = generate_token();
+  match repo.insert(principal.user_id, name, hash_token(&token), scopes, expires_at).await {
+    Ok(entity) => Ok(Created { entity, token }),
+    Err(InsertError::Conflict) => Err(Error::Conflict),
+    Err(InsertError::Unknown) => Err(Error::Unknown),
+  }
+}
+
+pub async fn list(repo: Arc<dyn Repository>, principal: &Principal) -> Result<Vec<PersonalAccessTokenEntity>, Error> {
+  repo.list_by_user(principal.user_id).await.map_err(|_| Error::Unknown)
+}
+
+/// Deletes one of the caller's own tokens; it stops working immediately.
+pub async fn revoke(repo: Arc<dyn Repository>, principal: &Principal, id: i64) -> Result<(), Error> {
+  match repo.delete(id, principal.user_id).await {
+    Ok(true) => Ok(()),
+    Ok(false) => Err(Error::NotFound),
+    Err(_) => Err(Error::Unknown),
+  }
+}
+
+/// Resolves a presented token to claims as if the user had signed in, with the
+/// user's current permissions narrowed down to the token's scopes.
+pub async fn verify(
+  repo: Arc<dyn Repository>,
+  users: Arc<dyn user::Repository>,
+  roles: Arc<dyn role::Repository>,
+  token: &str,
+) -> Result<Claims, VerifyError> {
+  let entity = match repo.find_by_hash(&hash_token(token)).await {
+    Ok(entity) => entity,
+    Err(FetchOneError::NotFound) => return Err(VerifyError::Invalid),
+    Err(FetchOneError::Unknown) => return Err(VerifyError::Unknown),
+  };
+
+  if entity.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+    return Err(VerifyError::Invalid);
+  }
+
+  let user = match fetch_one_user::execute(users, fetch_one_user::Request { id: entity.user_id }).await {
+    Ok(user) => ResUserProfile {
+      id: user.id,
+      login: user.login,
+      name: Some(user.name),
+      avatar_url: user.avatar_url,
+    },
+    Err(fetch_one_user::Error::NotFound) => return Err(VerifyError::Invalid),
+    Err(_) => return Err(VerifyError::Unknown),
+  };
+
+  let grants = roles.grants(user.id).await.map_err(|_| VerifyError::Unknown)?;
+  let permissions = grants.permissions.into_iter().filter(|p| entity.scopes.contains(p)).collect();
+
+  // a failed bookkeeping write does not fail the request
+  if let Err(e) = repo.touch(entity.id).await {
+    log::warn!("[{}] {:?}", trace_id::current(), e);
+  }
+
+  Ok(Claims {
+    // never revoked one by one, the token itself is deleted instead
+    jti: Uuid::nil(),
+    iat: entity.created_at.timestamp_millis() as u128,
+    exp: entity.expires_at.map_or(i64::MAX, |expires_at| expires_at.timestamp_millis()) as u128,
+    aud: None,
+    iss: None,
+    user,
+    grants: Grants { roles: grants.roles, permissions },
+    scopes: Some(entity.scopes),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    domain::{auth::role::{CAREER_WRITE, MODERATOR, ROLE_ASSIGN}, user::entity::{UserId, UserLogin, UserName, UserAvatar}},
+    repositories::{
+      personal_access_token::InMemoryRepository,
+      role::{InMemoryRepository as InMemoryRoles, Repository as _},
+      user::{InMemoryRepository as InMemoryUserRepository, Repository as _},
+    },
+  };
+
+  use super::*;
+
+  async fn users() -> Arc<InMemoryUserRepository> {
+    let users = Arc::new(InMemoryUserRepository::_new());
+    let _ = users.upsert_from_provider(UserId::one(), UserLogin::kent_back(), UserName::kent_back(), UserAvatar::user(), None).await;
+
+    users
+  }
+
+  fn principal(permissions: &[&str]) -> Principal {
+    Principal { user_id: i64::from(UserId::one()), permissions: permissions.iter().map(|p| p.to_string()).collect() }
+  }
+
+  fn request(name: &str, scopes: &[&str], expires_in_days: Option<i64>) -> CreateRequest {
+    CreateRequest { name: name.to_string(), scopes: scopes.iter().map(|s| s.to_string()).collect(), expires_in_days }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_create_a_token_that_verifies_as_its_user() {
+    let repo = Arc::new(InMemoryRepository::new());
+
+    let created = create(repo.clone(), &principal(&[]), request("ci", &[READ, WRITE], Some(30))).await.unwrap();
+
+    assert!(created.token.starts_with(TOKEN_PREFIX));
+    let claims = verify(repo.clone(), users().await, Arc::new(InMemoryRoles::new()), &created.token).await.unwrap();
+    assert_eq!(claims.user.id, i64::from(UserId::one()));
+    assert!(!claims.is_read_only());
+    assert!(repo.list_by_user(claims.user.id).await.unwrap()[0].last_used_at.is_some());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_narrow_permissions_down_to_the_scopes() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let roles = Arc::new(InMemoryRoles::new());
+    let _ = roles.assign(i64::from(UserId::one()), MODERATOR).await;
+    let moderator = principal(&[CAREER_WRITE, "user:write"]);
+
+    let created = create(repo.clone(), &moderator, request("import", &[READ, CAREER_WRITE], None)).await.unwrap();
+
+    let claims = verify(repo, users().await, roles, &created.token).await.unwrap();
+    assert_eq!(claims.grants.permissions, vec![CAREER_WRITE.to_string()]);
+    assert!(claims.is_read_only());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_reject_a_scope_the_user_does_not_hold() {
+    match create(Arc::new(InMemoryRepository::new()), &principal(&[]), request("ci", &[READ, ROLE_ASSIGN], None)).await {
+      Err(Error::InvalidScope(scope)) => assert_eq!(scope, ROLE_ASSIGN),
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_reject_bad_names_and_expiries() {
+    let repo = Arc::new(InMemoryRepository::new());
+
+    assert!(matches!(create(repo.clone(), &principal(&[]), request("  ", &[READ], None)).await, Err(Error::InvalidName)));
+    assert!(matches!(create(repo.clone(), &principal(&[]), request("ci", &[READ], Some(0))).await, Err(Error::InvalidExpiry)));
+    assert!(matches!(create(repo, &principal(&[]), request("ci", &[], None)).await, Err(Error::InvalidScope(_))));
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_a_conflict_for_a_duplicate_name() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let _ = create(repo.clone(), &principal(&[]), request("ci", &[READ], None)).await;
+
+    match create(repo, &principal(&[]), request("ci", &[WRITE], None)).await {
+      Err(Error::Conflict) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_stop_working_once_revoked() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let created = create(repo.clone(), &principal(&[]), request("ci", &[READ], None)).await.unwrap();
+
+    assert!(matches!(revoke(repo.clone(), &Principal { user_id: 2, permissions: vec![] }, created.entity.id).await, Err(Error::NotFound)));
+    assert!(revoke(repo.clone(), &principal(&[]), created.entity.id).await.is_ok());
+
+    assert!(matches!(verify(repo.clone(), users().await, Arc::new(InMemoryRoles::new()), &created.token).await, Err(VerifyError::Invalid)));
+    assert!(list(repo, &principal(&[])).await.unwrap().is_empty());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_reject_an_expired_token() {
+    let (repo, users, roles) = (Arc::new(InMemoryRepository::new()), users().await, Arc::new(InMemoryRoles::new()));
+    let expired = Utc::now().with_timezone(&FixedOffset::east(9 * 3600)) - Duration::seconds(1);
+    let _ = repo.insert(i64::from(UserId::one()), "old".to_string(), hash_token("dcf_pat_old"), vec![READ.to_string()], Some(expired)).await;
+
+    assert!(matches!(verify(repo.clone(), users.clone(), roles.clone(), "dcf_pat_old").await, Err(VerifyError::Invalid)));
+    assert!(matches!(verify(repo, users, roles, "dcf_pat_unknown").await, Err(VerifyError::Invalid)));
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_error_when_the_repo_fails() {
+    match verify(Arc::new(InMemoryRepository::new().with_error()), users().await, Arc::new(InMemoryRoles::new()), "dcf_pat_x").await {
+      Err(VerifyError::Unknown) => {},
+      _ => unreachable!(),
+    }
+  }
+}
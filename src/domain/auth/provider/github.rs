@@ -0,0 +1,134 @@
+use async_trait::async_trait;
+use oauth2::{AuthorizationCode, basic::BasicClient, CsrfToken, PkceCodeChallenge, PkceCodeVerifier, Scope, url::Url};
+use serde::Deserialize;
+
+use crate::{domain::auth::entity::UserProfile, infrastructure::settings::GithubSettings, middleware::trace_id};
+
+use super::{OAuthProviderAdapter, ProviderError, client, exchange, get_json};
+
+#[derive(Debug, Deserialize)]
+struct GithubUser {
+  id: i64,
+  login: String,
+  name: Option<String>,
+  avatar_url: String,
+}
+
+/// One entry of `/user/emails`, readable with the `user:email` scope.
+#[derive(Debug, Deserialize)]
+struct GithubEmail {
+  email: String,
+  primary: bool,
+  verified: bool,
+}
+
+impl From<GithubUser> for UserProfile {
+  fn from(user: GithubUser) -> Self {
+    Self {
+      subject: user.id.to_string(),
+      login: user.login,
+      name: user.name,
+      avatar_url: user.avatar_url,
+      email: None,
+    }
+  }
+}
+
+fn primary_verified(emails: Vec<GithubEmail>) -> Option<String> {
+  emails.into_iter().find(|e| e.primary && e.verified).map(|e| e.email)
+}
+
+pub struct GithubAdapter {
+  client: BasicClient,
+  api_url: String,
+}
+
+impl GithubAdapter {
+  pub fn new(settings: &GithubSettings) -> Self {
+    Self {
+      client: client(&settings.client_id, &settings.client_secret, settings.auth_url.clone(), settings.token_url.clone(), &settings.redirect_url),
+      api_url: settings.api_url.trim_end_matches('/').to_string(),
+    }
+  }
+}
+
+#[async_trait]
+impl OAuthProviderAdapter for GithubAdapter {
+  fn name(&self) -> &str {
+    "github"
+  }
+
+  async fn authorize_url(&self, state: CsrfToken, pkce_challenge: PkceCodeChallenge) -> Result<Url, ProviderError> {
+    let (url, _) = self.client
+      .authorize_url(|| state)
+      .add_scope(Scope::new("read:user".to_string()))
+      .add_scope(Scope::new("user:email".to_string()))
+      .set_pkce_challenge(pkce_challenge)
+      .url();
+
+    Ok(url)
+  }
+
+  async fn exchange_code(&self, code: AuthorizationCode, pkce_verifier: PkceCodeVerifier) -> Result<String, ProviderError> {
+    exchange(&self.client, code, pkce_verifier).await
+  }
+
+  async fn fetch_profile(&self, access_token: &str) -> Result<UserProfile, ProviderError> {
+    let mut profile = match get_json::<GithubUser>(&format!("{}/user", self.api_url), Some(access_token)).await {
+      Ok(user) => UserProfile::from(user),
+      Err(e) => {
+        log::warn!("[{}] github profile: {:?}", trace_id::current(), e);
+        return Err(ProviderError::Profile);
+      },
+    };
+
+    // `/user` only shows the public address; a user who declined `user:email` signs in without one
+    match get_json::<Vec<GithubEmail>>(&format!("{}/user/emails", self.api_url), Some(access_token)).await {
+      Ok(emails) => profile.email = primary_verified(emails),
+      Err(e) => log::warn!("[{}] github emails: {:?}", trace_id::current(), e),
+    }
+
+    Ok(profile)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::infrastructure::settings::Settings;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn it_should_be_build_the_authorize_url() {
+    let adapter = GithubAdapter::new(&Settings::test().github);
+    let (challenge, _) = PkceCodeChallenge::new_random_sha256();
+
+    let url = adapter.authorize_url(CsrfToken::new("state".to_string()), challenge).await.unwrap();
+
+    assert!(url.as_str().starts_with("https://github.com/login/oauth/authorize"));
+    assert!(url.query_pairs().any(|(key, value)| key == "state" && value == "state"));
+    assert!(url.query_pairs().any(|(key, value)| key == "scope" && value == "read:user user:email"));
+  }
+
+  #[test]
+  fn it_should_be_use_the_numeric_id_as_subject() {
+    let user = GithubUser { id: 443, login: "kent-back".to_string(), name: None, avatar_url: "avatar_url".to_string() };
+
+    let profile = UserProfile::from(user);
+
+    assert_eq!(profile.subject, "443".to_string());
+    assert_eq!(profile.name, None);
+  }
+
+  #[test]
+  fn it_should_be_pick_the_primary_verified_email() {
+    let email = |email: &str, primary: bool, verified: bool| GithubEmail { email: email.to_string(), primary, verified };
+
+    assert_eq!(
+      primary_verified(vec![email("old@gmail.com", false, true), email("kent@gmail.com", true, true)]),
+      Some("kent@gmail.com".to_string()),
+    );
+    assert_eq!(primary_verified(vec![email("kent@gmail.com", true, false), email("old@gmail.com", false, true)]), None);
+    assert_eq!(primary_verified(vec![]), None);
+  }
+}
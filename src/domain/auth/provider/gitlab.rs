@@ -0,0 +1,82 @@
+use async_trait::async_trait;
+use oauth2::{AuthorizationCode, basic::BasicClient, CsrfToken, PkceCodeChallenge, PkceCodeVerifier, Scope, url::Url};
+use serde::Deserialize;
+
+use crate::{domain::auth::entity::UserProfile, infrastructure::settings::ProviderSettings, middleware::trace_id};
+
+use super::{OAuthProviderAdapter, ProviderError, client, exchange, get_json};
+
+#[derive(Debug, Deserialize)]
+struct GitlabUser {
+  id: i64,
+  username: String,
+  name: Option<String>,
+  avatar_url: Option<String>,
+  /// the primary address, GitLab only lets confirmed addresses become primary
+  email: Option<String>,
+}
+
+impl From<GitlabUser> for UserProfile {
+  fn from(user: GitlabUser) -> Self {
+    Self {
+      subject: user.id.to_string(),
+      login: user.username,
+      name: user.name.filter(|name| !name.is_empty()),
+      avatar_url: user.avatar_url.unwrap_or_default(),
+      email: user.email.filter(|email| !email.is_empty()),
+    }
+  }
+}
+
+pub struct GitlabAdapter {
+  name: String,
+  client: BasicClient,
+  base_url: String,
+}
+
+impl GitlabAdapter {
+  pub fn new(settings: &ProviderSettings) -> Self {
+    Self {
+      name: settings.name.clone(),
+      client: client(
+        &settings.client_id,
+        &settings.client_secret,
+        format!("{}/oauth/authorize", settings.base_url),
+        format!("{}/oauth/token", settings.base_url),
+        &settings.redirect_url,
+      ),
+      base_url: settings.base_url.clone(),
+    }
+  }
+}
+
+#[async_trait]
+impl OAuthProviderAdapter for GitlabAdapter {
+  fn name(&self) -> &str {
+    &self.name
+  }
+
+  async fn authorize_url(&self, state: CsrfToken, pkce_challenge: PkceCodeChallenge) -> Result<Url, ProviderError> {
+    let (url, _) = self.client
+      .authorize_url(|| state)
+      .add_scope(Scope::new("read_user".to_string()))
+      .set_pkce_challenge(pkce_challenge)
+      .url();
+
+    Ok(url)
+  }
+
+  async fn exchange_code(&self, code: AuthorizationCode, pkce_verifier: PkceCodeVerifier) -> Result<String, ProviderError> {
+    exchange(&self.client, code, pkce_verifier).await
+  }
+
+  async fn fetch_profile(&self, access_token: &str) -> Result<UserProfile, ProviderError> {
+    match get_json::<GitlabUser>(&format!("{}/api/v4/user", self.base_url), Some(access_token)).await {
+      Ok(user) => Ok(UserProfile::from(user)),
+      Err(e) => {
+        log::warn!("[{}] gitlab profile: {:?}", trace_id::current(), e);
+        Err(ProviderError::Profile)
+      },
+    }
+  }
+}
@@ -0,0 +1,133 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use oauth2::{AuthorizationCode, AuthUrl, basic::BasicClient, ClientId, ClientSecret, CsrfToken, PkceCodeChallenge, PkceCodeVerifier, RedirectUrl, reqwest::async_http_client, TokenResponse, TokenUrl, url::Url};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, USER_AGENT};
+
+use crate::{infrastructure::settings::{ProviderKind, Settings}, middleware::trace_id};
+
+use super::entity::UserProfile;
+
+pub mod github;
+pub mod gitlab;
+pub mod oidc;
+
+#[derive(Debug)]
+pub enum ProviderError {
+  /// OIDC discovery document could not be loaded
+  Discovery,
+  /// the provider refused the authorization code
+  Exchange,
+  /// the profile could not be fetched or read
+  Profile,
+}
+
+/// Everything the sign-in flow needs from one identity provider.
+#[async_trait]
+pub trait OAuthProviderAdapter: Send + Sync {
+  /// Registry name, also what `/authorization/code?provider=` selects.
+  fn name(&self) -> &str;
+
+  async fn authorize_url(&self, state: CsrfToken, pkce_challenge: PkceCodeChallenge) -> Result<Url, ProviderError>;
+
+  /// Trades the authorization code for a provider access token.
+  async fn exchange_code(&self, code: AuthorizationCode, pkce_verifier: PkceCodeVerifier) -> Result<String, ProviderError>;
+
+  async fn fetch_profile(&self, access_token: &str) -> Result<UserProfile, ProviderError>;
+}
+
+/// Configured providers by name; GitHub is always present.
+pub struct Providers {
+  adapters: HashMap<String, Arc<dyn OAuthProviderAdapter>>,
+}
+
+impl Providers {
+  pub fn new(settings: &Settings) -> Self {
+    let mut providers = Self { adapters: HashMap::new() };
+    providers.register(Arc::new(github::GithubAdapter::new(&settings.github)));
+
+    for provider in &settings.oauth {
+      match provider.kind {
+        ProviderKind::Gitlab => providers.register(Arc::new(gitlab::GitlabAdapter::new(provider))),
+        ProviderKind::Google | ProviderKind::Oidc => providers.register(Arc::new(oidc::OidcAdapter::new(provider))),
+      }
+    }
+
+    providers
+  }
+
+  pub fn register(&mut self, adapter: Arc<dyn OAuthProviderAdapter>) {
+    self.adapters.insert(adapter.name().to_lowercase(), adapter);
+  }
+
+  /// Names are matched case-insensitively, so the historical `Github` keeps working.
+  pub fn get(&self, name: &str) -> Option<Arc<dyn OAuthProviderAdapter>> {
+    self.adapters.get(&name.to_lowercase()).cloned()
+  }
+}
+
+// urls are validated when the settings load
+fn client(client_id: &str, client_secret: &str, auth_url: String, token_url: String, redirect_url: &str) -> BasicClient {
+  BasicClient::new(
+    ClientId::new(client_id.to_string()),
+    Some(ClientSecret::new(client_secret.to_string())),
+    AuthUrl::new(auth_url).expect("valid auth url"),
+    Some(TokenUrl::new(token_url).expect("valid token url")),
+  ).set_redirect_uri(RedirectUrl::new(redirect_url.to_string()).expect("valid redirect url"))
+}
+
+async fn exchange(client: &BasicClient, code: AuthorizationCode, pkce_verifier: PkceCodeVerifier) -> Result<String, ProviderError> {
+  match client.exchange_code(code).set_pkce_verifier(pkce_verifier).request_async(async_http_client).await {
+    Ok(token) => Ok(token.access_token().secret().to_owned()),
+    Err(e) => {
+      log::warn!("[{}] code exchange: {:?}", trace_id::current(), e);
+      Err(ProviderError::Exchange)
+    },
+  }
+}
+
+fn http_client() -> reqwest::Client {
+  let mut headers = HeaderMap::new();
+  headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+  headers.insert(USER_AGENT, HeaderValue::from_static("DECAFO"));
+
+  reqwest::Client::builder()
+    .default_headers(headers)
+    .build()
+    .expect("tls backend available")
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(url: &str, access_token: Option<&str>) -> Result<T, reqwest::Error> {
+  let mut request = http_client().get(url);
+  if let Some(access_token) = access_token {
+    request = request.bearer_auth(access_token);
+  }
+
+  request.send().await?.error_for_status()?.json::<T>().await
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::infrastructure::settings::ProviderSettings;
+
+  use super::*;
+
+  #[test]
+  fn it_should_be_find_providers_by_name_ignoring_case() {
+    let mut settings = Settings::test();
+    settings.oauth.push(ProviderSettings {
+      name: "google".to_string(),
+      kind: ProviderKind::Google,
+      client_id: "id".to_string(),
+      client_secret: "secret".to_string(),
+      redirect_url: "http://localhost:3000/signin".to_string(),
+      base_url: "https://accounts.google.com".to_string(),
+    });
+
+    let providers = Providers::new(&settings);
+
+    assert_eq!(providers.get("Github").map(|p| p.name().to_string()), Some("github".to_string()));
+    assert!(providers.get("google").is_some());
+    assert!(providers.get("gitlab").is_none());
+  }
+}
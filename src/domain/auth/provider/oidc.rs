@@ -0,0 +1,171 @@
+use async_trait::async_trait;
+use oauth2::{AuthorizationCode, basic::BasicClient, CsrfToken, PkceCodeChallenge, PkceCodeVerifier, Scope, url::Url};
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+
+use crate::{domain::auth::entity::UserProfile, infrastructure::settings::ProviderSettings, middleware::trace_id};
+
+use super::{OAuthProviderAdapter, ProviderError, client, exchange, get_json};
+
+/// The parts of `/.well-known/openid-configuration` the code flow uses.
+#[derive(Debug, Deserialize)]
+struct Discovery {
+  authorization_endpoint: String,
+  token_endpoint: String,
+  userinfo_endpoint: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserInfo {
+  sub: String,
+  preferred_username: Option<String>,
+  name: Option<String>,
+  email: Option<String>,
+  #[serde(default)]
+  email_verified: bool,
+  picture: Option<String>,
+}
+
+impl From<UserInfo> for UserProfile {
+  // Google has no username claim, so the login falls back to the email's local part
+  fn from(info: UserInfo) -> Self {
+    let login = info.preferred_username
+      .or_else(|| info.email.as_deref().and_then(|email| email.split_once('@')).map(|(local, _)| local.to_string()))
+      .unwrap_or_else(|| info.sub.clone());
+
+    Self {
+      subject: info.sub,
+      login,
+      name: info.name,
+      avatar_url: info.picture.unwrap_or_default(),
+      email: info.email.filter(|_| info.email_verified),
+    }
+  }
+}
+
+/// Google and any other OpenID Connect issuer, endpoints are discovered on first use.
+pub struct OidcAdapter {
+  name: String,
+  client_id: String,
+  client_secret: String,
+  redirect_url: String,
+  issuer: String,
+  discovered: OnceCell<(BasicClient, String)>,
+}
+
+impl OidcAdapter {
+  pub fn new(settings: &ProviderSettings) -> Self {
+    Self {
+      name: settings.name.clone(),
+      client_id: settings.client_id.clone(),
+      client_secret: settings.client_secret.clone(),
+      redirect_url: settings.redirect_url.clone(),
+      issuer: settings.base_url.clone(),
+      discovered: OnceCell::new(),
+    }
+  }
+
+  async fn discover(&self) -> Result<&(BasicClient, String), ProviderError> {
+    self.discovered.get_or_try_init(|| async {
+      let url = format!("{}/.well-known/openid-configuration", self.issuer);
+      let discovery = get_json::<Discovery>(&url, None).await.map_err(|e| {
+        log::error!("[{}] {} discovery: {:?}", trace_id::current(), self.name, e);
+        ProviderError::Discovery
+      })?;
+
+      for endpoint in [&discovery.authorization_endpoint, &discovery.token_endpoint] {
+        if Url::parse(endpoint).is_err() {
+          return Err(ProviderError::Discovery);
+        }
+      }
+
+      let client = client(&self.client_id, &self.client_secret, discovery.authorization_endpoint, discovery.token_endpoint, &self.redirect_url);
+      Ok((client, discovery.userinfo_endpoint))
+    }).await
+  }
+}
+
+#[async_trait]
+impl OAuthProviderAdapter for OidcAdapter {
+  fn name(&self) -> &str {
+    &self.name
+  }
+
+  async fn authorize_url(&self, state: CsrfToken, pkce_challenge: PkceCodeChallenge) -> Result<Url, ProviderError> {
+    let (client, _) = self.discover().await?;
+    let (url, _) = client
+      .authorize_url(|| state)
+      .add_scope(Scope::new("openid".to_string()))
+      .add_scope(Scope::new("profile".to_string()))
+      .add_scope(Scope::new("email".to_string()))
+      .set_pkce_challenge(pkce_challenge)
+      .url();
+
+    Ok(url)
+  }
+
+  async fn exchange_code(&self, code: AuthorizationCode, pkce_verifier: PkceCodeVerifier) -> Result<String, ProviderError> {
+    let (client, _) = self.discover().await?;
+
+    exchange(client, code, pkce_verifier).await
+  }
+
+  async fn fetch_profile(&self, access_token: &str) -> Result<UserProfile, ProviderError> {
+    let (_, userinfo_endpoint) = self.discover().await?;
+
+    match get_json::<UserInfo>(userinfo_endpoint, Some(access_token)).await {
+      Ok(info) => Ok(UserProfile::from(info)),
+      Err(e) => {
+        log::warn!("[{}] {} userinfo: {:?}", trace_id::current(), self.name, e);
+        Err(ProviderError::Profile)
+      },
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn info(preferred_username: Option<&str>, email: Option<&str>) -> UserInfo {
+    UserInfo {
+      sub: "109876543210987654321".to_string(),
+      preferred_username: preferred_username.map(str::to_string),
+      name: Some("kent back".to_string()),
+      email: email.map(str::to_string),
+      email_verified: true,
+      picture: Some("avatar_url".to_string()),
+    }
+  }
+
+  #[test]
+  fn it_should_be_prefer_the_preferred_username() {
+    let profile = UserProfile::from(info(Some("kent-back"), Some("kent@gmail.com")));
+
+    assert_eq!(profile.login, "kent-back".to_string());
+    assert_eq!(profile.subject, "109876543210987654321".to_string());
+  }
+
+  #[test]
+  fn it_should_be_fall_back_to_the_email_local_part() {
+    let profile = UserProfile::from(info(None, Some("kent@gmail.com")));
+
+    assert_eq!(profile.login, "kent".to_string());
+  }
+
+  #[test]
+  fn it_should_be_fall_back_to_the_subject() {
+    let profile = UserProfile::from(info(None, None));
+
+    assert_eq!(profile.login, "109876543210987654321".to_string());
+  }
+
+  #[test]
+  fn it_should_be_drop_an_unverified_email() {
+    let verified = UserProfile::from(info(None, Some("kent@gmail.com")));
+    let unverified = UserProfile::from(UserInfo { email_verified: false, ..info(None, Some("kent@gmail.com")) });
+
+    assert_eq!(verified.email, Some("kent@gmail.com".to_string()));
+    assert_eq!(unverified.email, None);
+  }
+}
@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::{domain::user::fetch_one_user, infrastructure::{keys::KeyStore, settings::JwtSettings}, repositories::{user, role, refresh_token::{Repository, FetchOneError}}};
+
+use super::entity::{ResUserProfile, TokenPair, hash_refresh_token, issue_tokens};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct Request {
+  pub refresh_token: String,
+}
+
+#[derive(Debug)]
+pub enum Error {
+  Invalid,
+  Expired,
+  Reused,
+  Unknown,
+}
+
+pub async fn execute(
+  user_repo: Arc<dyn user::Repository>,
+  roles: Arc<dyn role::Repository>,
+  repo: Arc<dyn Repository>,
+  keys: &KeyStore,
+  settings: &JwtSettings,
+  req: Request,
+) -> Result<TokenPair, Error> {
+  let token = match repo.find_by_hash(&hash_refresh_token(&req.refresh_token)).await {
+    Ok(token) => token,
+    Err(FetchOneError::NotFound) => return Err(Error::Invalid),
+    Err(FetchOneError::Unknown) => return Err(Error::Unknown),
+  };
+
+  if token.revoked_at.is_some() {
+    return Err(Error::Invalid);
+  }
+
+  if token.used_at.is_none() && token.expires_at < Utc::now() {
+    return Err(Error::Expired);
+  }
+
+  // a used token showing up again means it leaked, so the whole family goes
+  if token.used_at.is_some() || !repo.mark_used(token.id).await.map_err(|_| Error::Unknown)? {
+    repo.revoke_family(token.family_id).await.map_err(|_| Error::Unknown)?;
+    return Err(Error::Reused);
+  }
+
+  let user = match fetch_one_user::execute(user_repo, fetch_one_user::Request { id: token.user_id }).await {
+    Ok(user) => ResUserProfile {
+      id: user.id,
+      login: user.login,
+      name: Some(user.name),
+      avatar_url: user.avatar_url,
+    },
+    Err(fetch_one_user::Error::NotFound) => return Err(Error::Invalid),
+    Err(_) => return Err(Error::Unknown),
+  };
+
+  // grants are read again so role changes apply from the next refresh
+  let grants = roles.grants(user.id).await.map_err(|_| Error::Unknown)?;
+
+  issue_tokens(keys, settings, repo, user, grants, Some(token.family_id)).await.map_err(|_| Error::Unknown)
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{Duration, FixedOffset};
+  use sea_orm::prelude::Uuid;
+
+  use crate::{
+    domain::{auth::{entity::Claims, role::{Grants, MODERATOR, CAREER_WRITE}}, user::entity::{UserId, UserLogin, UserName, UserAvatar}},
+    infrastructure::settings::Settings,
+    repositories::{user::{InMemoryRepository as InMemoryUserRepository, Repository as _}, role::{InMemoryRepository as InMemoryRoles, Repository as _}, refresh_token::InMemoryRepository},
+  };
+
+  use super::*;
+
+  async fn user_repo() -> Arc<InMemoryUserRepository> {
+    let user_repo = Arc::new(InMemoryUserRepository::_new());
+    let _ = user_repo.upsert_from_provider(UserId::one(), UserLogin::kent_back(), UserName::kent_back(), UserAvatar::user(), None).await;
+
+    user_repo
+  }
+
+  async fn sign_in(keys: &KeyStore, settings: &JwtSettings, repo: Arc<InMemoryRepository>) -> TokenPair {
+    let user = ResUserProfile {
+      id: i64::from(UserId::one()),
+      login: String::from(UserLogin::kent_back()),
+      name: Some(String::from(UserName::kent_back())),
+      avatar_url: String::from(UserAvatar::user()),
+    };
+
+    issue_tokens(keys, settings, repo, user, Grants::default(), None).await.unwrap()
+  }
+
+  #[tokio::test]
+  async fn it_should_be_rotate_the_refresh_token() {
+    let settings = Settings::test().jwt;
+    let keys = KeyStore::new(&settings).unwrap();
+    let (user_repo, roles, repo) = (user_repo().await, Arc::new(InMemoryRoles::new()), Arc::new(InMemoryRepository::new()));
+    let pair = sign_in(&keys, &settings, repo.clone()).await;
+
+    let res = execute(user_repo.clone(), roles.clone(), repo.clone(), &keys, &settings, Request::new(&pair.refresh_token)).await;
+
+    match res {
+      Ok(res) => {
+        assert_ne!(res.refresh_token, pair.refresh_token);
+        assert!(execute(user_repo, roles, repo, &keys, &settings, Request::new(&res.refresh_token)).await.is_ok());
+      },
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_revoke_the_family_when_a_used_token_is_replayed() {
+    let settings = Settings::test().jwt;
+    let keys = KeyStore::new(&settings).unwrap();
+    let (user_repo, roles, repo) = (user_repo().await, Arc::new(InMemoryRoles::new()), Arc::new(InMemoryRepository::new()));
+    let pair = sign_in(&keys, &settings, repo.clone()).await;
+    let rotated = execute(user_repo.clone(), roles.clone(), repo.clone(), &keys, &settings, Request::new(&pair.refresh_token)).await.unwrap();
+
+    let res = execute(user_repo.clone(), roles.clone(), repo.clone(), &keys, &settings, Request::new(&pair.refresh_token)).await;
+
+    match res {
+      Err(Error::Reused) => {},
+      _ => unreachable!(),
+    }
+    match execute(user_repo, roles, repo, &keys, &settings, Request::new(&rotated.refresh_token)).await {
+      Err(Error::Invalid) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_pick_up_roles_granted_since_the_sign_in() {
+    let settings = Settings::test().jwt;
+    let keys = KeyStore::new(&settings).unwrap();
+    let (user_repo, roles, repo) = (user_repo().await, Arc::new(InMemoryRoles::new()), Arc::new(InMemoryRepository::new()));
+    let pair = sign_in(&keys, &settings, repo.clone()).await;
+    let _ = roles.assign(i64::from(UserId::one()), MODERATOR).await;
+
+    let res = execute(user_repo, roles, repo, &keys, &settings, Request::new(&pair.refresh_token)).await.unwrap();
+
+    let claims = keys.decode::<Claims>(&res.access_token).unwrap().claims;
+    assert_eq!(claims.grants.roles, vec![MODERATOR.to_string()]);
+    assert!(claims.grants.permissions.contains(&CAREER_WRITE.to_string()));
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_invalid_error_for_an_unknown_token() {
+    let settings = Settings::test().jwt;
+    let keys = KeyStore::new(&settings).unwrap();
+
+    match execute(user_repo().await, Arc::new(InMemoryRoles::new()), Arc::new(InMemoryRepository::new()), &keys, &settings, Request::new("unknown")).await {
+      Err(Error::Invalid) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_expired_error_after_the_ttl() {
+    let settings = Settings::test().jwt;
+    let keys = KeyStore::new(&settings).unwrap();
+    let repo = Arc::new(InMemoryRepository::new());
+    let expires_at = Utc::now().with_timezone(&FixedOffset::east(9 * 3600)) - Duration::seconds(1);
+    let _ = repo.insert(i64::from(UserId::one()), Uuid::new_v4(), hash_refresh_token("expired"), expires_at).await;
+
+    match execute(user_repo().await, Arc::new(InMemoryRoles::new()), repo, &keys, &settings, Request::new("expired")).await {
+      Err(Error::Expired) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_error_when_the_repo_fails() {
+    let settings = Settings::test().jwt;
+    let keys = KeyStore::new(&settings).unwrap();
+    let repo = Arc::new(InMemoryRepository::new().with_error());
+
+    let res = execute(user_repo().await, Arc::new(InMemoryRoles::new()), repo, &keys, &settings, Request::new("token")).await;
+
+    match res {
+      Err(Error::Unknown) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  impl Request {
+    fn new(refresh_token: &str) -> Self {
+      Self {
+        refresh_token: refresh_token.to_string(),
+      }
+    }
+  }
+}
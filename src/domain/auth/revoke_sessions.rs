@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use chrono::{FixedOffset, Utc};
+
+use crate::repositories::{revocation, refresh_token, personal_access_token};
+
+use super::{entity::Principal, role::SESSION_REVOKE};
+
+pub struct Request {
+  /// whose sessions are revoked
+  pub user_id: i64,
+}
+
+#[derive(Debug)]
+pub enum Error {
+  Forbidden,
+  Unknown,
+}
+
+/// Signs a user out everywhere: every access token issued so far stops working,
+/// every refresh token is revoked and every personal access token is deleted, so
+/// the user has to sign in again and scripts need new tokens.
+pub async fn execute(
+  revocations: Arc<dyn revocation::Repository>,
+  token_repo: Arc<dyn refresh_token::Repository>,
+  personal_tokens: Arc<dyn personal_access_token::Repository>,
+  principal: Principal,
+  req: Request,
+) -> Result<(), Error> {
+  if !principal.has_permission(SESSION_REVOKE) {
+    return Err(Error::Forbidden);
+  }
+
+  let now = Utc::now().with_timezone(&FixedOffset::east(9 * 3600));
+
+  revocations.revoke_user(req.user_id, now).await.map_err(|_| Error::Unknown)?;
+  token_repo.revoke_by_user(req.user_id).await.map_err(|_| Error::Unknown)?;
+  personal_tokens.delete_by_user(req.user_id).await.map_err(|_| Error::Unknown)?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::Duration;
+  use sea_orm::prelude::Uuid;
+
+  use crate::{
+    domain::auth::entity::hash_refresh_token,
+    repositories::{
+      personal_access_token::{InMemoryRepository as InMemoryPersonalTokens, Repository as _},
+      refresh_token::{InMemoryRepository, Repository as _},
+      revocation::{InMemoryRepository as InMemoryRevocations, Repository as _},
+    },
+  };
+
+  use super::*;
+
+  fn admin() -> Principal {
+    Principal { user_id: 1, permissions: vec![SESSION_REVOKE.to_string()] }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_revoke_every_session_of_the_user() {
+    let revocations = Arc::new(InMemoryRevocations::new());
+    let repo = Arc::new(InMemoryRepository::new());
+    let issued_at = Utc::now().with_timezone(&FixedOffset::east(9 * 3600)) - Duration::seconds(1);
+    let personal_tokens = Arc::new(InMemoryPersonalTokens::new());
+    let _ = repo.insert(2, Uuid::new_v4(), hash_refresh_token("token"), issued_at + Duration::days(1)).await;
+    let _ = personal_tokens.insert(2, "ci".to_string(), "hash-2".to_string(), vec!["read".to_string()], None).await;
+    let _ = personal_tokens.insert(3, "ci".to_string(), "hash-3".to_string(), vec!["read".to_string()], None).await;
+
+    let res = execute(revocations.clone(), repo.clone(), personal_tokens.clone(), admin(), Request { user_id: 2 }).await;
+
+    assert!(res.is_ok());
+    assert!(revocations.is_revoked(Uuid::new_v4(), 2, issued_at).await.unwrap());
+    assert!(!revocations.is_revoked(Uuid::new_v4(), 3, issued_at).await.unwrap());
+    assert!(repo.find_by_hash(&hash_refresh_token("token")).await.unwrap().revoked_at.is_some());
+    assert!(personal_tokens.list_by_user(2).await.unwrap().is_empty());
+    assert_eq!(personal_tokens.list_by_user(3).await.unwrap().len(), 1);
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_a_forbidden_error_without_the_permission() {
+    let res = execute(Arc::new(InMemoryRevocations::new()), Arc::new(InMemoryRepository::new()), Arc::new(InMemoryPersonalTokens::new()), Principal { user_id: 2, permissions: vec![] }, Request { user_id: 3 }).await;
+
+    match res {
+      Err(Error::Forbidden) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_error_when_the_store_fails() {
+    let res = execute(Arc::new(InMemoryRevocations::new().with_error()), Arc::new(InMemoryRepository::new()), Arc::new(InMemoryPersonalTokens::new()), admin(), Request { user_id: 2 }).await;
+
+    match res {
+      Err(Error::Unknown) => {},
+      _ => unreachable!(),
+    }
+  }
+}
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+pub const ADMIN: &str = "admin";
+/// edits and hides what other users put up, see `DEFAULT_ROLES`
+pub const MODERATOR: &str = "moderator";
+
+/// edit the profile of any user
+pub const USER_WRITE: &str = "user:write";
+/// add, edit and remove careers of any user
+pub const CAREER_WRITE: &str = "career:write";
+/// hide and show careers of any user
+pub const CAREER_HIDE: &str = "career:hide";
+/// sign any user out everywhere
+pub const SESSION_REVOKE: &str = "session:revoke";
+/// grant and take away roles
+pub const ROLE_ASSIGN: &str = "role:assign";
+
+/// Roles and what they grant, as seeded by the `create_role_tables` and `add_career_hidden` migrations;
+/// the in-memory repository serves them from here.
+#[cfg(any(test, feature = "test-support"))]
+pub const DEFAULT_ROLES: [(&str, &[&str]); 2] = [
+  (ADMIN, &[USER_WRITE, CAREER_WRITE, CAREER_HIDE, SESSION_REVOKE, ROLE_ASSIGN]),
+  (MODERATOR, &[USER_WRITE, CAREER_WRITE, CAREER_HIDE]),
+];
+
+/// What a user was granted, embedded in the access token when it is issued.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Grants {
+  pub roles: Vec<String>,
+  pub permissions: Vec<String>,
+}
@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use crate::repositories::{revocation, refresh_token::{self, FetchOneError}};
+
+use super::entity::{Claims, hash_refresh_token};
+
+pub struct Request {
+  /// claims of the access token used to call `/signout`
+  pub claims: Claims,
+  pub refresh_token: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+  /// personal access tokens are deleted instead, see `personal_access_token::revoke`
+  NotASession,
+  Unknown,
+}
+
+/// Revokes the presented access token and, when given, the refresh token family it belongs to.
+pub async fn execute(
+  revocations: Arc<dyn revocation::Repository>,
+  token_repo: Arc<dyn refresh_token::Repository>,
+  req: Request,
+) -> Result<(), Error> {
+  let claims = req.claims;
+  if claims.scopes.is_some() {
+    return Err(Error::NotASession);
+  }
+
+  revocations.revoke(claims.jti, claims.user.id, claims.expires_at()).await.map_err(|_| Error::Unknown)?;
+
+  if let Some(refresh_token) = req.refresh_token {
+    match token_repo.find_by_hash(&hash_refresh_token(&refresh_token)).await {
+      // a refresh token of somebody else is left alone
+      Ok(token) if token.user_id == claims.user.id => {
+        token_repo.revoke_family(token.family_id).await.map_err(|_| Error::Unknown)?;
+      },
+      Ok(_) | Err(FetchOneError::NotFound) => {},
+      Err(FetchOneError::Unknown) => return Err(Error::Unknown),
+    }
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    domain::{auth::{entity::{ResUserProfile, TokenPair, issue_tokens}, refresh_token, role::Grants}, user::entity::{UserId, UserLogin, UserName, UserAvatar}},
+    infrastructure::{keys::KeyStore, settings::{JwtSettings, Settings}},
+    repositories::{refresh_token::{InMemoryRepository, Repository as _}, revocation::{InMemoryRepository as InMemoryRevocations, Repository as _}, user::{InMemoryRepository as InMemoryUserRepository, Repository as _}, role::InMemoryRepository as InMemoryRoles},
+  };
+
+  use super::*;
+
+  async fn sign_in(keys: &KeyStore, settings: &JwtSettings, repo: Arc<InMemoryRepository>, id: i64) -> (Claims, TokenPair) {
+    let user = ResUserProfile {
+      id,
+      login: String::from(UserLogin::kent_back()),
+      name: Some(String::from(UserName::kent_back())),
+      avatar_url: String::from(UserAvatar::user()),
+    };
+    let pair = issue_tokens(keys, settings, repo, user, Grants::default(), None).await.unwrap();
+    let claims = keys.decode::<Claims>(&pair.access_token).unwrap().claims;
+
+    (claims, pair)
+  }
+
+  #[tokio::test]
+  async fn it_should_be_revoke_the_access_token() {
+    let settings = Settings::test().jwt;
+    let keys = KeyStore::new(&settings).unwrap();
+    let (revocations, repo) = (Arc::new(InMemoryRevocations::new()), Arc::new(InMemoryRepository::new()));
+    let (claims, _) = sign_in(&keys, &settings, repo.clone(), i64::from(UserId::one())).await;
+
+    let res = execute(revocations.clone(), repo, Request { claims: claims.clone(), refresh_token: None }).await;
+
+    assert!(res.is_ok());
+    assert!(revocations.is_revoked(claims.jti, claims.user.id, claims.issued_at()).await.unwrap());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_revoke_the_refresh_token_family() {
+    let settings = Settings::test().jwt;
+    let keys = KeyStore::new(&settings).unwrap();
+    let repo = Arc::new(InMemoryRepository::new());
+    let (claims, pair) = sign_in(&keys, &settings, repo.clone(), i64::from(UserId::one())).await;
+    let user_repo = Arc::new(InMemoryUserRepository::_new());
+    let _ = user_repo.upsert_from_provider(UserId::one(), UserLogin::kent_back(), UserName::kent_back(), UserAvatar::user(), None).await;
+
+    let _ = execute(Arc::new(InMemoryRevocations::new()), repo.clone(), Request { claims, refresh_token: Some(pair.refresh_token.clone()) }).await;
+
+    let res = refresh_token::execute(user_repo, Arc::new(InMemoryRoles::new()), repo, &keys, &settings, refresh_token::Request { refresh_token: pair.refresh_token }).await;
+    match res {
+      Err(refresh_token::Error::Invalid) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_ignore_a_refresh_token_of_another_user() {
+    let settings = Settings::test().jwt;
+    let keys = KeyStore::new(&settings).unwrap();
+    let repo = Arc::new(InMemoryRepository::new());
+    let (claims, _) = sign_in(&keys, &settings, repo.clone(), 1).await;
+    let (_, other) = sign_in(&keys, &settings, repo.clone(), 2).await;
+
+    let _ = execute(Arc::new(InMemoryRevocations::new()), repo.clone(), Request { claims, refresh_token: Some(other.refresh_token.clone()) }).await;
+
+    let token = repo.find_by_hash(&hash_refresh_token(&other.refresh_token)).await.unwrap();
+    assert!(token.revoked_at.is_none());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_refuse_a_personal_access_token() {
+    let settings = Settings::test().jwt;
+    let keys = KeyStore::new(&settings).unwrap();
+    let repo = Arc::new(InMemoryRepository::new());
+    let (mut claims, _) = sign_in(&keys, &settings, repo.clone(), 1).await;
+    claims.scopes = Some(vec!["read".to_string()]);
+
+    let res = execute(Arc::new(InMemoryRevocations::new()), repo, Request { claims, refresh_token: None }).await;
+
+    match res {
+      Err(Error::NotASession) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_error_when_the_store_fails() {
+    let settings = Settings::test().jwt;
+    let keys = KeyStore::new(&settings).unwrap();
+    let repo = Arc::new(InMemoryRepository::new());
+    let (claims, _) = sign_in(&keys, &settings, repo.clone(), 1).await;
+
+    let res = execute(Arc::new(InMemoryRevocations::new().with_error()), repo, Request { claims, refresh_token: None }).await;
+
+    match res {
+      Err(Error::Unknown) => {},
+      _ => unreachable!(),
+    }
+  }
+}
@@ -1,69 +1,236 @@
 use std::sync::Arc;
 
 use chrono::{NaiveDate};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::repositories::career::Repository;
+use crate::{domain::{auth::{entity::Principal, role::CAREER_WRITE}, career::entity::{self, CareerDto, CareerPeriod, FieldError, ValidationError}}, infrastructure::settings::{CareerSettings, OverlapPolicy}, repositories::career::{Repository, FetchError, InsertError}};
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all="camelCase")]
 pub struct Request {
-  pub user_id: i64,
+  /// defaults to the caller, somebody else takes `career:write`
+  pub user_id: Option<i64>,
   pub company: String,
   pub job: String,
   pub in_at: NaiveDate,
   pub out_at: Option<NaiveDate>,
+  #[serde(default="entity::default_full_time")]
+  pub full_time: bool,
 }
 
+/// What creating or updating a career answers with.
+#[derive(Debug, Serialize)]
+#[serde(rename_all="camelCase")]
 pub struct Response {
-  pub user_id: i64,
-  pub company: String,
-  pub job: String,
+  #[serde(flatten)]
+  pub career: CareerDto,
+  /// overlaps let through by the `warn` policy
+  #[serde(skip_serializing_if="Vec::is_empty")]
+  pub warnings: Vec<FieldError>,
 }
 
+#[derive(Debug)]
 pub enum Error {
+  Validation(Vec<ValidationError>),
+  /// `userId` names nobody
+  UnknownUser,
+  Forbidden,
+  /// the database is unreachable, the request may be retried
+  Unavailable,
   Unknown,
 }
 
-pub async fn execute(repo: Arc<dyn Repository>, req: Request) -> Result<Response, Error>  {
-  match repo.insert(req.user_id, req.company, req.job, req.in_at, req.out_at).await {
+/// Overlaps of a full-time `period` with the user's other full-time careers, `except` the one being edited.
+/// Empty when the policy allows overlaps.
+pub(super) async fn find_overlaps(
+  repo: &Arc<dyn Repository>,
+  settings: &CareerSettings,
+  user_id: i64,
+  except: Option<i64>,
+  period: &CareerPeriod,
+  full_time: bool,
+) -> Result<Vec<ValidationError>, FetchError> {
+  if settings.overlap_policy == OverlapPolicy::Allow || !full_time {
+    return Ok(vec![]);
+  }
+
+  let others = repo.find_by_user_id(user_id).await?.into_iter().filter(|c| Some(c.id) != except).collect::<Vec<_>>();
+
+  Ok(entity::overlaps(period, &others))
+}
+
+pub async fn execute(repo: Arc<dyn Repository>, settings: &CareerSettings, principal: Principal, req: Request) -> Result<Response, Error>  {
+  let user_id = req.user_id.unwrap_or(principal.user_id);
+  if !principal.can_act_for(user_id, CAREER_WRITE) {
+    return Err(Error::Forbidden);
+  }
+
+  let (company, job, period) = entity::parse(req.company, req.job, req.in_at, req.out_at).map_err(Error::Validation)?;
+
+  let warnings = find_overlaps(&repo, settings, user_id, None, &period, req.full_time).await.map_err(|e| match e {
+    FetchError::Unavailable => Error::Unavailable,
+    FetchError::Unknown(_) => Error::Unknown,
+  })?;
+  if !warnings.is_empty() && settings.overlap_policy == OverlapPolicy::Reject {
+    return Err(Error::Validation(warnings));
+  }
+
+  match repo.insert(user_id, company, job, period, req.full_time).await {
     Ok(res) => Ok(Response {
-      user_id: res.user_id,
-      company: res.company,
-      job: res.job
+      career: CareerDto::from(res),
+      warnings: warnings.iter().map(FieldError::from).collect(),
     }),
-    Err(_) => Err(Error::Unknown),
+    Err(InsertError::InvalidReference) => Err(Error::UnknownUser),
+    Err(InsertError::Unavailable) => Err(Error::Unavailable),
+    Err(InsertError::Conflict | InsertError::Unknown(_)) => Err(Error::Unknown),
   }
 }
 
 #[cfg(test)]
 mod tests {
-  use crate::repositories::career::InMemoryRepository;
+  use crate::{domain::career::entity::{CompanyName, JobTitle}, repositories::career::InMemoryRepository};
   use super::*;
 
+  fn settings(overlap_policy: OverlapPolicy) -> CareerSettings {
+    CareerSettings { overlap_policy }
+  }
+
+  async fn employed_since_2020() -> Arc<InMemoryRepository> {
+    let repo = Arc::new(InMemoryRepository::new());
+    let _ = repo.insert(1, CompanyName::pine_apple(), JobTitle::server_engineer(), CareerPeriod::since(NaiveDate::from_ymd(2020, 1, 1)), true).await;
+
+    repo
+  }
+
+  fn owner() -> Principal {
+    Principal { user_id: 1, permissions: vec![] }
+  }
+
   #[tokio::test]
   async fn it_should_be_return_ok() {
     let repo = Arc::new(InMemoryRepository::new());
-    let req = Request::new(1, "PineApple".to_string(), "Server Engineer".to_string(), NaiveDate::from_ymd(2022, 1, 1), None);
+    let req = Request::new(Some(1), "PineApple".to_string(), "Server Engineer".to_string(), NaiveDate::from_ymd(2022, 1, 1), None);
 
-    let res = execute(repo, req).await;
+    let res = execute(repo, &settings(OverlapPolicy::Warn), owner(), req).await;
 
     match res {
       Ok(res) => {
-        assert_eq!(res.company, "PineApple".to_string());
+        assert_eq!(res.career.company, "PineApple".to_string());
       },
       _ => unreachable!(),
     }
   }
 
+  #[tokio::test]
+  async fn it_should_be_add_the_career_to_the_caller_by_default() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let req = Request::new(None, "PineApple".to_string(), "Server Engineer".to_string(), NaiveDate::from_ymd(2022, 1, 1), None);
+
+    match execute(repo, &settings(OverlapPolicy::Warn), owner(), req).await {
+      Ok(res) => assert_eq!(res.career.user_id, 1),
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_a_forbidden_error_for_another_user() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let req = Request::new(Some(2), "PineApple".to_string(), "Server Engineer".to_string(), NaiveDate::from_ymd(2022, 1, 1), None);
+
+    match execute(repo, &settings(OverlapPolicy::Warn), owner(), req).await {
+      Err(Error::Forbidden) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_let_a_moderator_add_a_career_to_another_user() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let req = Request::new(Some(2), "PineApple".to_string(), "Server Engineer".to_string(), NaiveDate::from_ymd(2022, 1, 1), None);
+
+    match execute(repo, &settings(OverlapPolicy::Warn), Principal { user_id: 1, permissions: vec![CAREER_WRITE.to_string()] }, req).await {
+      Ok(res) => assert_eq!(res.career.user_id, 2),
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_user_error_for_a_made_up_user() {
+    let repo = Arc::new(InMemoryRepository::new().with_users(vec![1, 2]));
+    let req = Request::new(Some(404), "PineApple".to_string(), "Server Engineer".to_string(), NaiveDate::from_ymd(2022, 1, 1), None);
+
+    match execute(repo.clone(), &settings(OverlapPolicy::Warn), Principal { user_id: 1, permissions: vec![CAREER_WRITE.to_string()] }, req).await {
+      Err(Error::UnknownUser) => {},
+      _ => unreachable!(),
+    }
+    assert!(repo.find_by_user_id(404).await.unwrap().is_empty());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_every_validation_error() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let req = Request::new(None, " ".to_string(), "Server Engineer".to_string(), NaiveDate::from_ymd(2999, 1, 1), None);
+
+    match execute(repo, &settings(OverlapPolicy::Warn), owner(), req).await {
+      Err(Error::Validation(errors)) => assert_eq!(errors, vec![ValidationError::EmptyCompanyName, ValidationError::InAtInFuture]),
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_warn_about_an_overlapping_full_time_career() {
+    let repo = employed_since_2020().await;
+    let req = Request::new(None, "Micro Hard".to_string(), "Designer".to_string(), NaiveDate::from_ymd(2021, 1, 1), None);
+
+    match execute(repo, &settings(OverlapPolicy::Warn), owner(), req).await {
+      Ok(res) => assert_eq!(res.warnings, vec![FieldError::from(&ValidationError::Overlap(1))]),
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_reject_an_overlapping_full_time_career() {
+    let repo = employed_since_2020().await;
+    let req = Request::new(None, "Micro Hard".to_string(), "Designer".to_string(), NaiveDate::from_ymd(2021, 1, 1), None);
+
+    match execute(repo.clone(), &settings(OverlapPolicy::Reject), owner(), req).await {
+      Err(Error::Validation(errors)) => assert_eq!(errors, vec![ValidationError::Overlap(1)]),
+      _ => unreachable!(),
+    }
+    assert_eq!(repo.find_by_user_id(1).await.ok().map(|c| c.len()), Some(1));
+  }
+
+  #[tokio::test]
+  async fn it_should_be_accept_an_overlapping_part_time_career() {
+    let repo = employed_since_2020().await;
+    let req = Request { full_time: false, ..Request::new(None, "Micro Hard".to_string(), "Designer".to_string(), NaiveDate::from_ymd(2021, 1, 1), None) };
+
+    match execute(repo, &settings(OverlapPolicy::Reject), owner(), req).await {
+      Ok(res) => assert!(res.warnings.is_empty()),
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_ignore_overlaps_when_allowed() {
+    let repo = employed_since_2020().await;
+    let req = Request::new(None, "Micro Hard".to_string(), "Designer".to_string(), NaiveDate::from_ymd(2021, 1, 1), None);
+
+    match execute(repo, &settings(OverlapPolicy::Allow), owner(), req).await {
+      Ok(res) => assert!(res.warnings.is_empty()),
+      _ => unreachable!(),
+    }
+  }
+
   impl Request {
-    fn new(user_id: i64, company: String, job: String, in_at: NaiveDate, out_at: Option<NaiveDate>) -> Self {
+    fn new(user_id: Option<i64>, company: String, job: String, in_at: NaiveDate, out_at: Option<NaiveDate>) -> Self {
       Self {
         user_id,
         company,
         job,
         in_at,
         out_at,
+        full_time: true,
       }
     }
   }
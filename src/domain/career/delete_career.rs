@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use crate::{domain::auth::{entity::Principal, role::CAREER_WRITE}, repositories::career::{Repository, FetchOneError, UpdateError}};
+
+pub struct Request {
+  pub id: i64,
+}
+
+#[derive(Debug)]
+pub enum Error {
+  NotFound,
+  Forbidden,
+  /// the database is unreachable, the request may be retried
+  Unavailable,
+  Unknown,
+}
+
+/// Owners delete their own careers, `career:write` anybody's.
+pub async fn execute(repo: Arc<dyn Repository>, principal: Principal, req: Request) -> Result<(), Error> {
+  let career = match repo.find_by_id(req.id).await {
+    Ok(career) => career,
+    Err(FetchOneError::NotFound) => return Err(Error::NotFound),
+    Err(FetchOneError::Unavailable) => return Err(Error::Unavailable),
+    Err(FetchOneError::Unknown(_)) => return Err(Error::Unknown),
+  };
+
+  if !principal.can_act_for(career.user_id, CAREER_WRITE) {
+    return Err(Error::Forbidden);
+  }
+
+  match repo.delete(req.id).await {
+    Ok(_) => Ok(()),
+    Err(UpdateError::NotFound) => Err(Error::NotFound),
+    Err(UpdateError::Unavailable) => Err(Error::Unavailable),
+    Err(UpdateError::Unknown(_)) => Err(Error::Unknown),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::NaiveDate;
+
+  use crate::{domain::career::entity::{CareerPeriod, CompanyName, JobTitle}, repositories::career::InMemoryRepository};
+
+  use super::*;
+
+  async fn repo() -> Arc<InMemoryRepository> {
+    let repo = Arc::new(InMemoryRepository::new());
+    let _ = repo.insert(1, CompanyName::pine_apple(), JobTitle::server_engineer(), CareerPeriod::since(NaiveDate::from_ymd(2022, 1, 1)), true).await;
+
+    repo
+  }
+
+  #[tokio::test]
+  async fn it_should_be_delete_an_own_career() {
+    let repo = repo().await;
+
+    let res = execute(repo.clone(), Principal { user_id: 1, permissions: vec![] }, Request { id: 1 }).await;
+
+    assert!(res.is_ok());
+    assert!(repo.find_by_user_id(1).await.ok().unwrap().is_empty());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_a_forbidden_error_for_another_users_career() {
+    let repo = repo().await;
+
+    match execute(repo.clone(), Principal { user_id: 2, permissions: vec![] }, Request { id: 1 }).await {
+      Err(Error::Forbidden) => {},
+      _ => unreachable!(),
+    }
+    assert!(repo.find_by_id(1).await.is_ok());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_let_a_moderator_delete_another_users_career() {
+    let res = execute(repo().await, Principal { user_id: 2, permissions: vec![CAREER_WRITE.to_string()] }, Request { id: 1 }).await;
+
+    assert!(res.is_ok());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_a_not_found_error_for_an_unknown_career() {
+    match execute(repo().await, Principal { user_id: 1, permissions: vec![] }, Request { id: 2 }).await {
+      Err(Error::NotFound) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_error_when_the_repo_fails() {
+    match execute(Arc::new(InMemoryRepository::new().with_error()), Principal { user_id: 1, permissions: vec![] }, Request { id: 1 }).await {
+      Err(Error::Unknown) => {},
+      _ => unreachable!(),
+    }
+  }
+}
@@ -1,22 +1,327 @@
-use chrono::{NaiveDate};
+use std::fmt::{Display, Formatter};
 
-#[derive(Clone)]
+use chrono::{FixedOffset, NaiveDate, Utc};
+use serde::Serialize;
+
+const COMPANY_NAME_MAX_LENGTH: usize = 100;
+const JOB_TITLE_MAX_LENGTH: usize = 100;
+
+/// A rule a career breaks. Every variant belongs to one request field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+  EmptyCompanyName,
+  CompanyNameTooLong,
+  EmptyJobTitle,
+  JobTitleTooLong,
+  OutBeforeIn,
+  InAtInFuture,
+  /// overlaps the full-time career with this id
+  Overlap(i64),
+}
+
+impl std::error::Error for ValidationError {}
+
+impl Display for ValidationError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ValidationError::EmptyCompanyName => write!(f, "Company name is empty."),
+      ValidationError::CompanyNameTooLong => write!(f, "Company name is too long. Length limit: {}", COMPANY_NAME_MAX_LENGTH),
+      ValidationError::EmptyJobTitle => write!(f, "Job title is empty."),
+      ValidationError::JobTitleTooLong => write!(f, "Job title is too long. Length limit: {}", JOB_TITLE_MAX_LENGTH),
+      ValidationError::OutBeforeIn => write!(f, "The end date is before the start date."),
+      ValidationError::InAtInFuture => write!(f, "The start date is in the future."),
+      ValidationError::Overlap(id) => write!(f, "Overlaps the full-time career {}.", id),
+    }
+  }
+}
+
+impl ValidationError {
+  /// Request field the error is reported on.
+  pub fn field(&self) -> &'static str {
+    match self {
+      ValidationError::EmptyCompanyName | ValidationError::CompanyNameTooLong => "company",
+      ValidationError::EmptyJobTitle | ValidationError::JobTitleTooLong => "job",
+      ValidationError::OutBeforeIn => "outAt",
+      ValidationError::InAtInFuture | ValidationError::Overlap(_) => "inAt",
+    }
+  }
+}
+
+/// `{ "field": "company", "message": "..." }` as sent to clients.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+  pub field: &'static str,
+  pub message: String,
+}
+
+impl From<&ValidationError> for FieldError {
+  fn from(e: &ValidationError) -> Self {
+    Self {
+      field: e.field(),
+      message: e.to_string(),
+    }
+  }
+}
+
+fn non_empty(value: String, max_length: usize, empty: ValidationError, too_long: ValidationError) -> Result<String, ValidationError> {
+  let value = value.trim().to_string();
+  if value.is_empty() {
+    Err(empty)
+  } else if value.chars().count() > max_length {
+    Err(too_long)
+  } else {
+    Ok(value)
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompanyName(String);
+
+impl TryFrom<String> for CompanyName {
+  type Error = ValidationError;
+
+  fn try_from(n: String) -> Result<Self, Self::Error> {
+    non_empty(n, COMPANY_NAME_MAX_LENGTH, ValidationError::EmptyCompanyName, ValidationError::CompanyNameTooLong).map(Self)
+  }
+}
+
+impl From<CompanyName> for String {
+  fn from(n: CompanyName) -> Self {
+    n.0
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobTitle(String);
+
+impl TryFrom<String> for JobTitle {
+  type Error = ValidationError;
+
+  fn try_from(n: String) -> Result<Self, Self::Error> {
+    non_empty(n, JOB_TITLE_MAX_LENGTH, ValidationError::EmptyJobTitle, ValidationError::JobTitleTooLong).map(Self)
+  }
+}
+
+impl From<JobTitle> for String {
+  fn from(n: JobTitle) -> Self {
+    n.0
+  }
+}
+
+/// Start and, once left, end of a career. It has started by today and does not end before it starts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CareerPeriod {
+  in_at: NaiveDate,
+  out_at: Option<NaiveDate>,
+}
+
+impl CareerPeriod {
+  pub fn new(in_at: NaiveDate, out_at: Option<NaiveDate>, today: NaiveDate) -> Result<Self, ValidationError> {
+    if in_at > today {
+      return Err(ValidationError::InAtInFuture);
+    }
+    if out_at.is_some_and(|out_at| out_at < in_at) {
+      return Err(ValidationError::OutBeforeIn);
+    }
+
+    Ok(Self { in_at, out_at })
+  }
+
+  pub fn in_at(&self) -> NaiveDate {
+    self.in_at
+  }
+
+  pub fn out_at(&self) -> Option<NaiveDate> {
+    self.out_at
+  }
+
+  /// Periods that only touch, one ending the day the other starts, do not overlap.
+  pub fn overlaps(&self, other: &CareerPeriod) -> bool {
+    let ends_after = |period: &CareerPeriod, day: NaiveDate| period.out_at.is_none_or(|out_at| out_at > day);
+
+    ends_after(self, other.in_at) && ends_after(other, self.in_at)
+  }
+}
+
+impl TryFrom<(NaiveDate, Option<NaiveDate>)> for CareerPeriod {
+  type Error = ValidationError;
+
+  fn try_from((in_at, out_at): (NaiveDate, Option<NaiveDate>)) -> Result<Self, Self::Error> {
+    Self::new(in_at, out_at, Utc::now().with_timezone(&FixedOffset::east(9 * 3600)).date_naive())
+  }
+}
+
+/// Parses the editable fields of a career, reporting every violation rather than the first.
+pub fn parse(company: String, job: String, in_at: NaiveDate, out_at: Option<NaiveDate>) -> Result<(CompanyName, JobTitle, CareerPeriod), Vec<ValidationError>> {
+  match (CompanyName::try_from(company), JobTitle::try_from(job), CareerPeriod::try_from((in_at, out_at))) {
+    (Ok(company), Ok(job), Ok(period)) => Ok((company, job, period)),
+    (company, job, period) => Err([company.err(), job.err(), period.err()].into_iter().flatten().collect()),
+  }
+}
+
+/// One `Overlap` per full-time career in `others` that `period` overlaps.
+pub fn overlaps(period: &CareerPeriod, others: &[CareerEntity]) -> Vec<ValidationError> {
+  others.iter()
+    .filter(|c| c.full_time && c.period().overlaps(period))
+    .map(|c| ValidationError::Overlap(c.id))
+    .collect()
+}
+
+#[cfg(test)]
+impl CareerPeriod {
+  pub fn since(in_at: NaiveDate) -> Self {
+    Self { in_at, out_at: None }
+  }
+
+  pub fn between(in_at: NaiveDate, out_at: NaiveDate) -> Self {
+    Self { in_at, out_at: Some(out_at) }
+  }
+}
+
+#[cfg(test)]
+impl CompanyName {
+  pub fn pine_apple() -> Self {
+    Self(String::from("PineApple"))
+  }
+
+  pub fn micro_hard() -> Self {
+    Self(String::from("Micro Hard"))
+  }
+}
+
+#[cfg(test)]
+impl JobTitle {
+  pub fn server_engineer() -> Self {
+    Self(String::from("Server Engineer"))
+  }
+}
+
+#[derive(Debug, Clone)]
 pub struct CareerEntity {
+  pub id: i64,
   pub user_id: i64,
   pub company: String,
   pub job: String,
   pub in_at: NaiveDate,
   pub out_at: Option<NaiveDate>,
+  pub full_time: bool,
+  /// taken out of sight by a moderator; only the owner and `career:hide` still see it
+  pub hidden: bool,
 }
 
 impl CareerEntity {
-  pub fn new(user_id: i64, company: String, job: String, in_at: NaiveDate, out_at: Option<NaiveDate>) -> Self {
+  #[cfg(any(test, feature = "test-support"))]
+  pub fn new(id: i64, user_id: i64, company: CompanyName, job: JobTitle, period: CareerPeriod, full_time: bool) -> Self {
     Self {
+      id,
       user_id,
-      company,
-      job,
-      in_at,
-      out_at,
+      company: String::from(company),
+      job: String::from(job),
+      in_at: period.in_at,
+      out_at: period.out_at,
+      full_time,
+      hidden: false,
     }
   }
-}
\ No newline at end of file
+
+  pub fn period(&self) -> CareerPeriod {
+    CareerPeriod { in_at: self.in_at, out_at: self.out_at }
+  }
+}
+
+/// `fullTime` of a request that leaves it out
+pub fn default_full_time() -> bool {
+  true
+}
+
+/// A career as every endpoint hands it out, whether just created, updated or listed.
+#[derive(Debug, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct CareerDto {
+  pub id: i64,
+  pub user_id: i64,
+  pub company: String,
+  pub job: String,
+  pub in_at: NaiveDate,
+  pub out_at: Option<NaiveDate>,
+  pub full_time: bool,
+  pub hidden: bool,
+}
+
+impl From<CareerEntity> for CareerDto {
+  fn from(career: CareerEntity) -> Self {
+    Self {
+      id: career.id,
+      user_id: career.user_id,
+      company: career.company,
+      job: career.job,
+      in_at: career.in_at,
+      out_at: career.out_at,
+      full_time: career.full_time,
+      hidden: career.hidden,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd(y, m, d)
+  }
+
+  #[test]
+  fn it_should_be_reject_empty_and_oversized_names() {
+    assert_eq!(CompanyName::try_from("  ".to_string()), Err(ValidationError::EmptyCompanyName));
+    assert_eq!(CompanyName::try_from("a".repeat(101)), Err(ValidationError::CompanyNameTooLong));
+    assert_eq!(JobTitle::try_from(String::new()), Err(ValidationError::EmptyJobTitle));
+    assert_eq!(JobTitle::try_from("a".repeat(101)), Err(ValidationError::JobTitleTooLong));
+    assert_eq!(String::from(CompanyName::try_from(" PineApple ".to_string()).unwrap()), "PineApple".to_string());
+  }
+
+  #[test]
+  fn it_should_be_reject_a_period_ending_before_it_starts() {
+    assert_eq!(CareerPeriod::new(date(2022, 5, 1), Some(date(2022, 4, 30)), date(2023, 1, 1)), Err(ValidationError::OutBeforeIn));
+    assert!(CareerPeriod::new(date(2022, 5, 1), Some(date(2022, 5, 1)), date(2023, 1, 1)).is_ok());
+  }
+
+  #[test]
+  fn it_should_be_reject_a_period_starting_in_the_future() {
+    assert_eq!(CareerPeriod::new(date(2023, 1, 2), None, date(2023, 1, 1)), Err(ValidationError::InAtInFuture));
+    assert!(CareerPeriod::new(date(2023, 1, 1), None, date(2023, 1, 1)).is_ok());
+  }
+
+  #[test]
+  fn it_should_be_detect_overlapping_periods() {
+    let past = CareerPeriod::between(date(2016, 5, 1), date(2018, 3, 31));
+
+    assert!(past.overlaps(&CareerPeriod::between(date(2018, 1, 1), date(2019, 1, 1))));
+    assert!(past.overlaps(&CareerPeriod::since(date(2017, 1, 1))));
+    assert!(CareerPeriod::since(date(2017, 1, 1)).overlaps(&CareerPeriod::since(date(2020, 1, 1))));
+    assert!(!past.overlaps(&CareerPeriod::since(date(2018, 3, 31))));
+    assert!(!past.overlaps(&CareerPeriod::between(date(2015, 1, 1), date(2016, 5, 1))));
+  }
+
+  #[test]
+  fn it_should_be_collect_every_violation() {
+    match parse(String::new(), "a".repeat(101), date(2022, 5, 1), Some(date(2022, 4, 1))) {
+      Err(errors) => assert_eq!(errors, vec![ValidationError::EmptyCompanyName, ValidationError::JobTitleTooLong, ValidationError::OutBeforeIn]),
+      Ok(_) => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn it_should_be_ignore_part_time_careers_when_checking_overlaps() {
+    let full_time = CareerEntity::new(1, 1, CompanyName::pine_apple(), JobTitle::server_engineer(), CareerPeriod::since(date(2020, 1, 1)), true);
+    let part_time = CareerEntity::new(2, 1, CompanyName::micro_hard(), JobTitle::server_engineer(), CareerPeriod::since(date(2020, 1, 1)), false);
+
+    assert_eq!(overlaps(&CareerPeriod::since(date(2021, 1, 1)), &[full_time, part_time]), vec![ValidationError::Overlap(1)]);
+  }
+
+  #[test]
+  fn it_should_be_report_errors_on_their_fields() {
+    assert_eq!(FieldError::from(&ValidationError::OutBeforeIn), FieldError { field: "outAt", message: "The end date is before the start date.".to_string() });
+    assert_eq!(ValidationError::Overlap(3).field(), "inAt");
+  }
+}
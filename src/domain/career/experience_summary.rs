@@ -0,0 +1,286 @@
+use std::sync::Arc;
+
+use chrono::{FixedOffset, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{domain::{career::entity::CareerEntity, user::entity::UserId}, repositories::career::{Repository, FetchError}};
+
+// the average month, 365.25 / 12 days
+const DAYS_PER_MONTH: f64 = 30.4375;
+/// Breaks between careers shorter than this are moves, not gaps.
+const MIN_GAP_DAYS: i64 = 30;
+
+#[derive(Deserialize)]
+pub struct Request {
+  pub id: i64,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct CompanyTenure {
+  pub company: String,
+  pub months: u32,
+  pub current: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct CurrentPosition {
+  pub company: String,
+  pub job: String,
+  pub since: NaiveDate,
+}
+
+/// Time without any career, from the day the previous one ended to the day the next started.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct Gap {
+  pub from: NaiveDate,
+  pub to: NaiveDate,
+  pub months: u32,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct ExperienceSummary {
+  /// overlapping careers counted once
+  pub total_months: u32,
+  /// full-time careers only, side jobs left out
+  pub relevant_months: u32,
+  /// most recent first
+  pub companies: Vec<CompanyTenure>,
+  pub average_tenure_months: Option<f64>,
+  pub current: Option<CurrentPosition>,
+  pub gaps: Vec<Gap>,
+}
+
+pub enum Error {
+  BadRequest,
+  /// the database is unreachable, the request may be retried
+  Unavailable,
+  Unknown,
+}
+
+// [start, end) like `CareerPeriod::overlaps`: a career left on the day the next one starts does not overlap it
+type Span = (NaiveDate, NaiveDate);
+
+fn span(career: &CareerEntity, today: NaiveDate) -> Span {
+  let end = career.out_at.unwrap_or(today).min(today);
+
+  (career.in_at, end.max(career.in_at))
+}
+
+/// Sorted, with overlapping and touching spans joined.
+fn merge(mut spans: Vec<Span>) -> Vec<Span> {
+  spans.sort();
+
+  spans.into_iter().fold(Vec::new(), |mut merged: Vec<Span>, (start, end)| {
+    match merged.last_mut() {
+      Some(last) if start <= last.1 => last.1 = last.1.max(end),
+      _ => merged.push((start, end)),
+    }
+    merged
+  })
+}
+
+fn days(spans: &[Span]) -> i64 {
+  spans.iter().map(|(start, end)| (*end - *start).num_days()).sum()
+}
+
+fn months(days: i64) -> u32 {
+  (days as f64 / DAYS_PER_MONTH).round() as u32
+}
+
+/// Summarizes the careers as of `today`; careers without `out_at` run until then.
+pub fn summarize(careers: &[CareerEntity], today: NaiveDate) -> ExperienceSummary {
+  let all = merge(careers.iter().map(|c| span(c, today)).collect());
+  let full_time = merge(careers.iter().filter(|c| c.full_time).map(|c| span(c, today)).collect());
+
+  // one entry per company however it was spelled, named after its most recent career
+  let mut by_recency = careers.iter().collect::<Vec<_>>();
+  by_recency.sort_by_key(|c| std::cmp::Reverse((span(c, today).1, c.in_at)));
+  let mut companies: Vec<(String, Vec<&CareerEntity>)> = vec![];
+  for career in by_recency {
+    match companies.iter_mut().find(|(name, _)| name.to_lowercase() == career.company.to_lowercase()) {
+      Some((_, careers)) => careers.push(career),
+      None => companies.push((career.company.clone(), vec![career])),
+    }
+  }
+  let companies = companies.into_iter().map(|(company, careers)| CompanyTenure {
+    company,
+    months: months(days(&merge(careers.iter().map(|c| span(c, today)).collect()))),
+    current: careers.iter().any(|c| c.out_at.is_none_or(|out_at| out_at > today)),
+  }).collect::<Vec<_>>();
+  let average_tenure_months = (!companies.is_empty()).then(|| {
+    let average = companies.iter().map(|c| c.months as f64).sum::<f64>() / companies.len() as f64;
+    (average * 10.0).round() / 10.0
+  });
+
+  // the full-time one when holding several, then the latest started
+  let current = careers.iter()
+    .filter(|c| c.out_at.is_none_or(|out_at| out_at > today))
+    .max_by_key(|c| (c.full_time, c.in_at, c.id))
+    .map(|c| CurrentPosition { company: c.company.clone(), job: c.job.clone(), since: c.in_at });
+
+  let gaps = all.windows(2)
+    .map(|pair| (pair[0].1, pair[1].0))
+    .filter(|(from, to)| (*to - *from).num_days() >= MIN_GAP_DAYS)
+    .map(|(from, to)| Gap { from, to, months: months((to - from).num_days()) })
+    .collect();
+
+  ExperienceSummary {
+    total_months: months(days(&all)),
+    relevant_months: months(days(&full_time)),
+    companies,
+    average_tenure_months,
+    current,
+    gaps,
+  }
+}
+
+pub async fn execute(repo: Arc<dyn Repository>, req: Request) -> Result<ExperienceSummary, Error> {
+  let user_id = UserId::try_from(req.id).map_err(|_| Error::BadRequest)?;
+  let today = Utc::now().with_timezone(&FixedOffset::east(9 * 3600)).date_naive();
+
+  match repo.find_by_user_id(i64::from(user_id)).await {
+    // what a moderator hid counts for nobody
+    Ok(careers) => Ok(summarize(&careers.into_iter().filter(|c| !c.hidden).collect::<Vec<_>>(), today)),
+    Err(FetchError::Unavailable) => Err(Error::Unavailable),
+    Err(FetchError::Unknown(_)) => Err(Error::Unknown),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{domain::career::entity::{CareerPeriod, CompanyName, JobTitle}, repositories::career::InMemoryRepository};
+
+  use super::*;
+
+  fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd(y, m, d)
+  }
+
+  fn career(id: i64, company: &str, in_at: NaiveDate, out_at: Option<NaiveDate>, full_time: bool) -> CareerEntity {
+    let period = match out_at {
+      Some(out_at) => CareerPeriod::between(in_at, out_at),
+      None => CareerPeriod::since(in_at),
+    };
+
+    CareerEntity::new(id, 1, CompanyName::try_from(company.to_string()).unwrap(), JobTitle::server_engineer(), period, full_time)
+  }
+
+  fn today() -> NaiveDate {
+    date(2023, 1, 1)
+  }
+
+  #[test]
+  fn it_should_be_run_an_open_ended_career_until_today() {
+    let summary = summarize(&[career(1, "Wercel", date(2021, 1, 1), None, true)], today());
+
+    assert_eq!(summary.total_months, 24);
+    assert_eq!(summary.current, Some(CurrentPosition { company: "Wercel".to_string(), job: "Server Engineer".to_string(), since: date(2021, 1, 1) }));
+    assert_eq!(summary.companies, vec![CompanyTenure { company: "Wercel".to_string(), months: 24, current: true }]);
+  }
+
+  #[test]
+  fn it_should_be_count_overlapping_careers_once() {
+    let careers = [
+      career(1, "Wercel", date(2020, 1, 1), Some(date(2021, 1, 1)), true),
+      // a side job during the last half year
+      career(2, "Micro Hard", date(2020, 7, 1), Some(date(2021, 7, 1)), false),
+    ];
+
+    let summary = summarize(&careers, today());
+
+    assert_eq!(summary.total_months, 18);
+    assert_eq!(summary.relevant_months, 12);
+    assert_eq!(summary.average_tenure_months, Some(12.0));
+    assert_eq!(summary.current, None);
+  }
+
+  #[test]
+  fn it_should_be_not_see_a_gap_in_a_same_month_move() {
+    let careers = [
+      career(1, "Wercel", date(2019, 1, 1), Some(date(2020, 3, 13)), true),
+      career(2, "Micro Hard", date(2020, 3, 16), Some(date(2021, 3, 16)), true),
+      // left and joined the same day
+      career(3, "PineApple", date(2021, 3, 16), None, true),
+    ];
+
+    let summary = summarize(&careers, today());
+
+    assert!(summary.gaps.is_empty());
+    assert_eq!(summary.total_months, 48);
+    assert_eq!(summary.companies.iter().map(|c| c.company.as_str()).collect::<Vec<_>>(), vec!["PineApple", "Micro Hard", "Wercel"]);
+  }
+
+  #[test]
+  fn it_should_be_report_gaps_between_careers() {
+    let careers = [
+      career(1, "Wercel", date(2018, 1, 1), Some(date(2019, 1, 1)), true),
+      career(2, "Micro Hard", date(2019, 7, 1), Some(date(2020, 1, 1)), true),
+    ];
+
+    let summary = summarize(&careers, today());
+
+    assert_eq!(summary.gaps, vec![Gap { from: date(2019, 1, 1), to: date(2019, 7, 1), months: 6 }]);
+    assert_eq!(summary.total_months, 18);
+    assert_eq!(summary.average_tenure_months, Some(9.0));
+  }
+
+  #[test]
+  fn it_should_be_add_up_a_return_to_the_same_company() {
+    let careers = [
+      career(1, "Wercel", date(2018, 1, 1), Some(date(2019, 1, 1)), true),
+      career(2, "Micro Hard", date(2019, 1, 1), Some(date(2020, 1, 1)), true),
+      career(3, "wercel", date(2020, 1, 1), Some(date(2020, 7, 1)), true),
+    ];
+
+    let summary = summarize(&careers, today());
+
+    assert_eq!(summary.companies, vec![
+      CompanyTenure { company: "wercel".to_string(), months: 18, current: false },
+      CompanyTenure { company: "Micro Hard".to_string(), months: 12, current: false },
+    ]);
+    assert_eq!(summary.average_tenure_months, Some(15.0));
+  }
+
+  #[test]
+  fn it_should_be_prefer_the_full_time_career_as_the_current_position() {
+    let careers = [
+      career(1, "Wercel", date(2020, 1, 1), None, true),
+      career(2, "Micro Hard", date(2022, 1, 1), None, false),
+    ];
+
+    assert_eq!(summarize(&careers, today()).current.map(|c| c.company), Some("Wercel".to_string()));
+  }
+
+  #[test]
+  fn it_should_be_summarize_no_careers_as_empty() {
+    let summary = summarize(&[], today());
+
+    assert_eq!(summary, ExperienceSummary { total_months: 0, relevant_months: 0, companies: vec![], average_tenure_months: None, current: None, gaps: vec![] });
+  }
+
+  #[tokio::test]
+  async fn it_should_be_leave_out_hidden_careers() {
+    let repo = Arc::new(InMemoryRepository::new());
+    let _ = repo.insert(i64::from(UserId::one()), CompanyName::pine_apple(), JobTitle::server_engineer(), CareerPeriod::since(date(2020, 1, 1)), true).await;
+    let _ = repo.set_hidden(1, true).await;
+
+    match execute(repo, Request { id: i64::from(UserId::one()) }).await {
+      Ok(summary) => assert_eq!((summary.total_months, summary.current), (0, None)),
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_error_when_the_repo_fails() {
+    let repo = Arc::new(InMemoryRepository::new().with_error());
+
+    match execute(repo, Request { id: 1 }).await {
+      Err(Error::Unknown) => {},
+      _ => unreachable!(),
+    }
+  }
+}
@@ -1,59 +1,141 @@
-use std::{sync::Arc, convert::TryFrom};
+use std::sync::Arc;
 
-use chrono::NaiveDate;
-use serde::Serialize;
+use serde::Deserialize;
 
-use crate::repositories::career::Repository;
+use crate::{domain::{auth::{entity::Principal, role::CAREER_HIDE}, career::entity::CareerDto, cursor::CursorToken, user::entity::UserId}, repositories::career::{CareerSort, Cursor, Direction, ListQuery, Repository, FetchError}};
 
-pub struct Request {
-  pub user_id: i64,
+const DEFAULT_LIMIT: u64 = 50;
+const MAX_LIMIT: u64 = 100;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub enum Sort {
+  #[default]
+  InAt,
+  OutAt,
+  Company,
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
 #[serde(rename_all="camelCase")]
-pub struct FetchCareerDto {
-  company: String,
-  job: String,
-  in_at: NaiveDate,
-  out_at: Option<NaiveDate>
+pub enum Order {
+  Asc,
+  Desc,
 }
 
-impl FetchCareerDto {
-  fn new(company: String, job: String, in_at: NaiveDate, out_at: Option<NaiveDate>) -> Self {
-    Self {
-      company,
-      job,
-      in_at,
-      out_at
-    }
-  }
+/// Query string of a career listing.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct Options {
+  #[serde(default)]
+  pub sort: Sort,
+  /// newest first for dates and a-z for companies when left out
+  pub direction: Option<Order>,
+  #[serde(default)]
+  pub current_only: bool,
+  /// `nextCursor` of the previous page
+  pub cursor: Option<String>,
+  pub limit: Option<u64>,
+}
+
+pub struct Request {
+  pub user_id: i64,
+  /// who is looking; hidden careers are only listed to their owner and `career:hide`
+  pub viewer: Principal,
+  pub options: Options,
 }
 
 pub struct Response {
-  pub careers: Vec<FetchCareerDto>
+  pub careers: Vec<CareerDto>,
+  /// careers matching the filter across every page
+  pub total: u64,
+  /// `None` on the last page
+  pub next_cursor: Option<String>,
 }
 
 pub enum Error {
   BadRequest,
+  /// not between 1 and 100
+  InvalidLimit,
+  /// not issued by this endpoint, or for another sort
+  InvalidCursor,
+  /// the database is unreachable, the request may be retried
+  Unavailable,
   Unknown,
 }
 
+fn encode(cursor: Cursor) -> String {
+  match cursor {
+    Cursor::InAt(at, id) => CursorToken::InAt { at, id },
+    Cursor::OutAt(at, id) => CursorToken::OutAt { at, id },
+    Cursor::Company(company, id) => CursorToken::Company { company, id },
+  }.encode()
+}
+
+fn decode(cursor: &str, sort: Sort) -> Result<Cursor, Error> {
+  match (CursorToken::decode(cursor), sort) {
+    (Some(CursorToken::InAt { at, id }), Sort::InAt) => Ok(Cursor::InAt(at, id)),
+    (Some(CursorToken::OutAt { at, id }), Sort::OutAt) => Ok(Cursor::OutAt(at, id)),
+    (Some(CursorToken::Company { company, id }), Sort::Company) => Ok(Cursor::Company(company, id)),
+    _ => Err(Error::InvalidCursor),
+  }
+}
+
 pub async fn execute(repo: Arc<dyn Repository>, req: Request) -> Result<Response, Error> {
-  match i64::try_from(req.user_id) {
-    Ok(user_id) => match repo.find_by_user_id(user_id).await {
-      Ok(res) => Ok(Response {
-        careers: res.iter().map(|career| FetchCareerDto::new(career.company.clone(), career.job.clone(), career.in_at, career.out_at)).collect::<Vec<FetchCareerDto>>(),
-      }),
-      Err(_) => Err(Error::Unknown),
-    },
-    _ => Err(Error::BadRequest)
+  let user_id = UserId::try_from(req.user_id).map_err(|_| Error::BadRequest)?;
+  let Options { sort, direction, current_only, cursor, limit } = req.options;
+
+  let limit = limit.unwrap_or(DEFAULT_LIMIT);
+  if !(1..=MAX_LIMIT).contains(&limit) {
+    return Err(Error::InvalidLimit);
   }
+  let after = cursor.as_deref().map(|cursor| decode(cursor, sort)).transpose()?;
+
+  let query = ListQuery {
+    sort: match sort {
+      Sort::InAt => CareerSort::InAt,
+      Sort::OutAt => CareerSort::OutAt,
+      Sort::Company => CareerSort::Company,
+    },
+    direction: match (direction, sort) {
+      (Some(Order::Asc), _) | (None, Sort::Company) => Direction::Asc,
+      (Some(Order::Desc), _) | (None, _) => Direction::Desc,
+    },
+    current_only,
+    include_hidden: req.viewer.user_id == i64::from(user_id) || req.viewer.has_permission(CAREER_HIDE),
+    after,
+    // one more than asked tells whether another page follows
+    limit: limit + 1,
+  };
+
+  let mut page = match repo.list_by_user_id(i64::from(user_id), query).await {
+    Ok(page) => page,
+    Err(FetchError::Unavailable) => return Err(Error::Unavailable),
+    Err(FetchError::Unknown(_)) => return Err(Error::Unknown),
+  };
+
+  let next_cursor = if page.careers.len() as u64 > limit {
+    page.careers.truncate(limit as usize);
+    page.careers.last().map(|career| encode(match sort {
+      Sort::InAt => Cursor::InAt(career.in_at, career.id),
+      Sort::OutAt => Cursor::OutAt(career.out_at, career.id),
+      Sort::Company => Cursor::Company(career.company.clone(), career.id),
+    }))
+  } else {
+    None
+  };
+
+  Ok(Response {
+    careers: page.careers.into_iter().map(CareerDto::from).collect(),
+    total: page.total,
+    next_cursor,
+  })
 }
 
 #[cfg(test)]
 mod tests {
   use chrono::{NaiveDate};
-  use crate::repositories::career::InMemoryRepository;
+  use crate::{domain::career::entity::{CareerPeriod, CompanyName, JobTitle}, repositories::career::InMemoryRepository};
   use super::*;
 
   #[tokio::test]
@@ -62,26 +144,26 @@ mod tests {
 
     let _ = repo.insert(
       1,
-      "Micro Hard".to_string(),
-      "Designer".to_string(),
-      NaiveDate::from_ymd(2016, 5, 1),
-      Some(NaiveDate::from_ymd(2018, 3, 31)),
+      CompanyName::micro_hard(),
+      JobTitle::try_from("Designer".to_string()).unwrap(),
+      CareerPeriod::between(NaiveDate::from_ymd(2016, 5, 1), NaiveDate::from_ymd(2018, 3, 31)),
+      true,
     ).await;
 
     let _ =  repo.insert(
       2,
-      "Micro Hard".to_string(),
-      "Designer".to_string(),
-      NaiveDate::from_ymd(2016, 7, 1),
-      Some(NaiveDate::from_ymd(2018, 1, 31)),
+      CompanyName::micro_hard(),
+      JobTitle::try_from("Designer".to_string()).unwrap(),
+      CareerPeriod::between(NaiveDate::from_ymd(2016, 7, 1), NaiveDate::from_ymd(2018, 1, 31)),
+      true,
     ).await;
 
     let _ = repo.insert(
       1,
-      "Wercel".to_string(),
-      "Server Engieneer".to_string(),
-      NaiveDate::from_ymd(2018, 4, 1),
-      None,
+      CompanyName::try_from("Wercel".to_string()).unwrap(),
+      JobTitle::try_from("Server Engieneer".to_string()).unwrap(),
+      CareerPeriod::since(NaiveDate::from_ymd(2018, 4, 1)),
+      true,
     ).await;
 
     let req = Request::new(1);
@@ -89,16 +171,121 @@ mod tests {
 
     match res {
       Ok(res) => {
-        assert_eq!(res.careers.len(), 2)
+        assert_eq!(res.careers.len(), 2);
+        assert_eq!(res.careers.iter().map(|c| c.id).collect::<Vec<i64>>(), vec![3, 1]);
+        assert_eq!(res.total, 2);
+        assert!(res.next_cursor.is_none());
       },
       Err(_) => unreachable!(),
     }
   }
 
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_error_when_the_repo_fails() {
+    let repo = Arc::new(InMemoryRepository::new().with_error());
+
+    let req = Request::new(1);
+    let res = execute(repo, req).await;
+
+    match res {
+      Err(Error::Unknown) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  async fn repo() -> Arc<InMemoryRepository> {
+    let repo = Arc::new(InMemoryRepository::new());
+    for (company, in_at, out_at) in [
+      ("Wercel", (2014, 1, 1), Some((2015, 12, 31))),
+      ("Amazing", (2016, 1, 1), None),
+      ("Micro Hard", (2018, 1, 1), Some((2019, 12, 31))),
+      ("Banana", (2020, 1, 1), None),
+    ] {
+      let period = match out_at {
+        Some((y, m, d)) => CareerPeriod::between(NaiveDate::from_ymd(in_at.0, in_at.1, in_at.2), NaiveDate::from_ymd(y, m, d)),
+        None => CareerPeriod::since(NaiveDate::from_ymd(in_at.0, in_at.1, in_at.2)),
+      };
+      let _ = repo.insert(1, CompanyName::try_from(company.to_string()).unwrap(), JobTitle::server_engineer(), period, false).await;
+    }
+
+    repo
+  }
+
+  fn ids(res: &Response) -> Vec<i64> {
+    res.careers.iter().map(|c| c.id).collect()
+  }
+
+  #[tokio::test]
+  async fn it_should_be_page_through_the_careers_with_the_total() {
+    let repo = repo().await;
+    let options = || Options { sort: Sort::OutAt, limit: Some(3), ..Options::default() };
+
+    let first = execute(repo.clone(), Request { options: options(), ..Request::new(1) }).await.ok().unwrap();
+    let second = execute(repo, Request { options: Options { cursor: first.next_cursor.clone(), ..options() }, ..Request::new(1) }).await.ok().unwrap();
+
+    // current careers count as ending last
+    assert_eq!(ids(&first), vec![4, 2, 3]);
+    assert_eq!(ids(&second), vec![1]);
+    assert_eq!((first.total, second.total), (4, 4));
+    assert!(second.next_cursor.is_none());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_sort_by_company_and_direction() {
+    let repo = repo().await;
+
+    let az = execute(repo.clone(), Request { options: Options { sort: Sort::Company, ..Options::default() }, ..Request::new(1) }).await.ok().unwrap();
+    let oldest = execute(repo, Request { options: Options { direction: Some(Order::Asc), ..Options::default() }, ..Request::new(1) }).await.ok().unwrap();
+
+    assert_eq!(ids(&az), vec![2, 4, 3, 1]);
+    assert_eq!(ids(&oldest), vec![1, 2, 3, 4]);
+  }
+
+  #[tokio::test]
+  async fn it_should_be_list_only_current_careers() {
+    let res = execute(repo().await, Request { options: Options { current_only: true, ..Options::default() }, ..Request::new(1) }).await.ok().unwrap();
+
+    assert_eq!(ids(&res), vec![4, 2]);
+    assert_eq!(res.total, 2);
+  }
+
+  #[tokio::test]
+  async fn it_should_be_reject_a_cursor_of_another_sort() {
+    let repo = repo().await;
+    let first = execute(repo.clone(), Request { options: Options { limit: Some(1), ..Options::default() }, ..Request::new(1) }).await.ok().unwrap();
+
+    let res = execute(repo, Request { options: Options { sort: Sort::Company, cursor: first.next_cursor, ..Options::default() }, ..Request::new(1) }).await;
+
+    match res {
+      Err(Error::InvalidCursor) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_list_hidden_careers_only_to_the_owner_and_moderators() {
+    let repo = repo().await;
+    let _ = repo.set_hidden(2, true).await;
+    let viewer = |user_id: i64, permissions: &[&str]| Request {
+      viewer: Principal { user_id, permissions: permissions.iter().map(|p| p.to_string()).collect() },
+      ..Request::new(1)
+    };
+
+    let stranger = execute(repo.clone(), viewer(2, &[])).await.ok().unwrap();
+    let owner = execute(repo.clone(), viewer(1, &[])).await.ok().unwrap();
+    let moderator = execute(repo, viewer(2, &[CAREER_HIDE])).await.ok().unwrap();
+
+    assert_eq!((ids(&stranger), stranger.total), (vec![4, 3, 1], 3));
+    assert_eq!(ids(&owner), vec![4, 3, 2, 1]);
+    assert_eq!(ids(&moderator), vec![4, 3, 2, 1]);
+  }
+
   impl Request {
     fn new(user_id: i64) -> Self {
       Self {
         user_id,
+        viewer: Principal { user_id, permissions: vec![] },
+        options: Options::default(),
       }
     }
   }
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use crate::{domain::auth::{entity::Principal, role::CAREER_HIDE}, repositories::career::{Repository, UpdateError}};
+
+pub struct Request {
+  pub id: i64,
+  /// `false` shows a hidden career again
+  pub hidden: bool,
+}
+
+#[derive(Debug)]
+pub enum Error {
+  NotFound,
+  Forbidden,
+  /// the database is unreachable, the request may be retried
+  Unavailable,
+  Unknown,
+}
+
+/// Takes a career out of sight of everybody but its owner, or brings it back. Moderation only:
+/// owners cannot show what a moderator hid, so it takes `career:hide` even for an own career.
+pub async fn execute(repo: Arc<dyn Repository>, principal: Principal, req: Request) -> Result<(), Error> {
+  if !principal.has_permission(CAREER_HIDE) {
+    return Err(Error::Forbidden);
+  }
+
+  match repo.set_hidden(req.id, req.hidden).await {
+    Ok(_) => Ok(()),
+    Err(UpdateError::NotFound) => Err(Error::NotFound),
+    Err(UpdateError::Unavailable) => Err(Error::Unavailable),
+    Err(UpdateError::Unknown(_)) => Err(Error::Unknown),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::NaiveDate;
+
+  use crate::{domain::career::entity::{CareerPeriod, CompanyName, JobTitle}, repositories::career::InMemoryRepository};
+
+  use super::*;
+
+  async fn repo() -> Arc<InMemoryRepository> {
+    let repo = Arc::new(InMemoryRepository::new());
+    let _ = repo.insert(1, CompanyName::pine_apple(), JobTitle::server_engineer(), CareerPeriod::since(NaiveDate::from_ymd(2022, 1, 1)), true).await;
+
+    repo
+  }
+
+  fn moderator() -> Principal {
+    Principal { user_id: 2, permissions: vec![CAREER_HIDE.to_string()] }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_let_a_moderator_hide_and_show_a_career() {
+    let repo = repo().await;
+
+    let hidden = execute(repo.clone(), moderator(), Request { id: 1, hidden: true }).await;
+    assert!(hidden.is_ok());
+    assert!(repo.find_by_id(1).await.unwrap().hidden);
+
+    let shown = execute(repo.clone(), moderator(), Request { id: 1, hidden: false }).await;
+    assert!(shown.is_ok());
+    assert!(!repo.find_by_id(1).await.unwrap().hidden);
+  }
+
+  #[tokio::test]
+  async fn it_should_be_not_let_the_owner_show_a_hidden_career() {
+    let repo = repo().await;
+    let _ = execute(repo.clone(), moderator(), Request { id: 1, hidden: true }).await;
+
+    match execute(repo.clone(), Principal { user_id: 1, permissions: vec![] }, Request { id: 1, hidden: false }).await {
+      Err(Error::Forbidden) => {},
+      _ => unreachable!(),
+    }
+    assert!(repo.find_by_id(1).await.unwrap().hidden);
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_a_not_found_error() {
+    match execute(repo().await, moderator(), Request { id: 2, hidden: true }).await {
+      Err(Error::NotFound) => {},
+      _ => unreachable!(),
+    }
+  }
+}
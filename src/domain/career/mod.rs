@@ -1,3 +1,7 @@
 pub mod entity;
 pub mod create_career;
-pub mod find_by_user_id;
\ No newline at end of file
+pub mod find_by_user_id;
+pub mod update_career;
+pub mod delete_career;
+pub mod hide_career;
+pub mod experience_summary;
@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::{domain::{auth::{entity::Principal, role::CAREER_WRITE}, career::{create_career::{find_overlaps, Response}, entity::{self, CareerDto, FieldError, ValidationError}}}, infrastructure::settings::{CareerSettings, OverlapPolicy}, repositories::career::{Repository, FetchError, FetchOneError, UpdateError}};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct Request {
+  #[serde(skip)]
+  pub id: i64,
+  pub company: String,
+  pub job: String,
+  pub in_at: NaiveDate,
+  pub out_at: Option<NaiveDate>,
+  #[serde(default="entity::default_full_time")]
+  pub full_time: bool,
+}
+
+#[derive(Debug)]
+pub enum Error {
+  Validation(Vec<ValidationError>),
+  NotFound,
+  Forbidden,
+  /// the database is unreachable, the request may be retried
+  Unavailable,
+  Unknown,
+}
+
+/// Replaces the editable fields of a career. Owners edit their own, `career:write` anybody's.
+pub async fn execute(repo: Arc<dyn Repository>, settings: &CareerSettings, principal: Principal, req: Request) -> Result<Response, Error> {
+  let career = match repo.find_by_id(req.id).await {
+    Ok(career) => career,
+    Err(FetchOneError::NotFound) => return Err(Error::NotFound),
+    Err(FetchOneError::Unavailable) => return Err(Error::Unavailable),
+    Err(FetchOneError::Unknown(_)) => return Err(Error::Unknown),
+  };
+
+  if !principal.can_act_for(career.user_id, CAREER_WRITE) {
+    return Err(Error::Forbidden);
+  }
+
+  let (company, job, period) = entity::parse(req.company, req.job, req.in_at, req.out_at).map_err(Error::Validation)?;
+
+  let warnings = find_overlaps(&repo, settings, career.user_id, Some(career.id), &period, req.full_time).await.map_err(|e| match e {
+    FetchError::Unavailable => Error::Unavailable,
+    FetchError::Unknown(_) => Error::Unknown,
+  })?;
+  if !warnings.is_empty() && settings.overlap_policy == OverlapPolicy::Reject {
+    return Err(Error::Validation(warnings));
+  }
+
+  match repo.update(req.id, company, job, period, req.full_time).await {
+    Ok(res) => Ok(Response {
+      career: CareerDto::from(res),
+      warnings: warnings.iter().map(FieldError::from).collect(),
+    }),
+    // deleted in between
+    Err(UpdateError::NotFound) => Err(Error::NotFound),
+    Err(UpdateError::Unavailable) => Err(Error::Unavailable),
+    Err(UpdateError::Unknown(_)) => Err(Error::Unknown),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{domain::career::entity::{CareerPeriod, CompanyName, JobTitle}, repositories::career::InMemoryRepository};
+
+  use super::*;
+
+  async fn repo() -> Arc<InMemoryRepository> {
+    let repo = Arc::new(InMemoryRepository::new());
+    let _ = repo.insert(1, CompanyName::pine_apple(), JobTitle::server_engineer(), CareerPeriod::since(NaiveDate::from_ymd(2022, 1, 1)), true).await;
+
+    repo
+  }
+
+  fn settings() -> CareerSettings {
+    CareerSettings { overlap_policy: OverlapPolicy::Reject }
+  }
+
+  fn principal(user_id: i64, permissions: &[&str]) -> Principal {
+    Principal { user_id, permissions: permissions.iter().map(|p| p.to_string()).collect() }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_update_an_own_career() {
+    let repo = repo().await;
+
+    let res = execute(repo.clone(), &settings(), principal(1, &[]), Request::new(1, "Wercel", Some(NaiveDate::from_ymd(2022, 12, 31)))).await;
+
+    match res {
+      Ok(res) => {
+        assert_eq!(res.career.company, "Wercel".to_string());
+        assert_eq!(res.career.out_at, Some(NaiveDate::from_ymd(2022, 12, 31)));
+      },
+      _ => unreachable!(),
+    }
+    assert_eq!(repo.find_by_id(1).await.unwrap().company, "Wercel".to_string());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_a_forbidden_error_for_another_users_career() {
+    let repo = repo().await;
+
+    match execute(repo.clone(), &settings(), principal(2, &[]), Request::new(1, "Wercel", None)).await {
+      Err(Error::Forbidden) => {},
+      _ => unreachable!(),
+    }
+    assert_eq!(repo.find_by_id(1).await.unwrap().company, "PineApple".to_string());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_let_a_moderator_update_another_users_career() {
+    let res = execute(repo().await, &settings(), principal(2, &[CAREER_WRITE]), Request::new(1, "Wercel", None)).await;
+
+    assert!(res.is_ok());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_a_not_found_error_for_an_unknown_career() {
+    match execute(repo().await, &settings(), principal(1, &[]), Request::new(2, "Wercel", None)).await {
+      Err(Error::NotFound) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_error_when_the_repo_fails() {
+    match execute(Arc::new(InMemoryRepository::new().with_error()), &settings(), principal(1, &[]), Request::new(1, "Wercel", None)).await {
+      Err(Error::Unknown) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_not_count_the_career_itself_as_an_overlap() {
+    let res = execute(repo().await, &settings(), principal(1, &[]), Request::new(1, "Wercel", None)).await;
+
+    match res {
+      Ok(res) => assert!(res.warnings.is_empty()),
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_reject_a_period_overlapping_another_full_time_career() {
+    let repo = repo().await;
+    let _ = repo.insert(1, CompanyName::micro_hard(), JobTitle::server_engineer(), CareerPeriod::between(NaiveDate::from_ymd(2020, 1, 1), NaiveDate::from_ymd(2021, 12, 31)), true).await;
+    let req = Request { in_at: NaiveDate::from_ymd(2020, 6, 1), ..Request::new(2, "Micro Hard", None) };
+
+    match execute(repo, &settings(), principal(1, &[]), req).await {
+      Err(Error::Validation(errors)) => assert_eq!(errors, vec![ValidationError::Overlap(1)]),
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_reject_an_end_before_the_start() {
+    let req = Request::new(1, "Wercel", Some(NaiveDate::from_ymd(2021, 12, 31)));
+
+    match execute(repo().await, &settings(), principal(1, &[]), req).await {
+      Err(Error::Validation(errors)) => assert_eq!(errors, vec![ValidationError::OutBeforeIn]),
+      _ => unreachable!(),
+    }
+  }
+
+  impl Request {
+    fn new(id: i64, company: &str, out_at: Option<NaiveDate>) -> Self {
+      Self {
+        id,
+        company: company.to_string(),
+        job: "Server Engineer".to_string(),
+        in_at: NaiveDate::from_ymd(2022, 1, 1),
+        out_at,
+        full_time: true,
+      }
+    }
+  }
+}
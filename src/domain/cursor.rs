@@ -0,0 +1,47 @@
+use chrono::NaiveDate;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use serde::{Deserialize, Serialize};
+
+/// What the opaque `cursor` of a paged listing carries: the sort key and id of the last row
+/// handed out. A listing only accepts the variants of its own sorts.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "sort", rename_all="camelCase")]
+pub enum CursorToken {
+  CreatedAt { at: DateTimeWithTimeZone, id: i64 },
+  Name { name: String, id: i64 },
+  InAt { at: NaiveDate, id: i64 },
+  OutAt { at: Option<NaiveDate>, id: i64 },
+  Company { company: String, id: i64 },
+}
+
+impl CursorToken {
+  /// base64 encoded json, safe to put in a query string as is
+  pub fn encode(&self) -> String {
+    base64::encode_config(serde_json::to_vec(self).unwrap_or_default(), base64::URL_SAFE_NO_PAD)
+  }
+
+  /// `None` for anything `encode` did not produce
+  pub fn decode(cursor: &str) -> Option<Self> {
+    base64::decode_config(cursor, base64::URL_SAFE_NO_PAD)
+      .ok()
+      .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_should_be_decode_what_it_encodes() {
+    let token = CursorToken::OutAt { at: None, id: 3 };
+
+    assert_eq!(CursorToken::decode(&token.encode()), Some(token));
+  }
+
+  #[test]
+  fn it_should_be_reject_a_made_up_cursor() {
+    assert_eq!(CursorToken::decode("not-a-cursor"), None);
+    assert_eq!(CursorToken::decode(&base64::encode_config(b"{\"sort\":\"unknown\"}", base64::URL_SAFE_NO_PAD)), None);
+  }
+}
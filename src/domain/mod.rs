@@ -1,3 +1,6 @@
 pub mod user;
 pub mod auth;
-pub mod career;
\ No newline at end of file
+pub mod career;
+pub mod search;
+pub mod resume;
+pub mod cursor;
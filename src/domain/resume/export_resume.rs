@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use chrono::{FixedOffset, Utc};
+use serde::Deserialize;
+
+use crate::{
+  domain::{resume::json_resume::{self, JsonResume}, user::entity::UserId},
+  repositories::{career, user},
+};
+
+#[derive(Deserialize)]
+pub struct Request {
+  pub id: i64,
+}
+
+#[derive(Debug)]
+pub enum Error {
+  BadRequest,
+  NotFound,
+  /// the database is unreachable, the request may be retried
+  Unavailable,
+  Unknown,
+}
+
+/// The user's resume as `viewer` may see it: the account email, hidden channels and careers a
+/// moderator hid only go to its owner.
+pub async fn execute(
+  users: Arc<dyn user::Repository>,
+  careers: Arc<dyn career::Repository>,
+  viewer: i64,
+  req: Request,
+) -> Result<JsonResume, Error> {
+  let id = UserId::try_from(req.id).map_err(|_| Error::BadRequest)?;
+
+  let mut user = match users.fetch_one(id).await {
+    Ok(user) => user,
+    Err(user::FetchOneError::NotFound) => return Err(Error::NotFound),
+    Err(user::FetchOneError::Unavailable) => return Err(Error::Unavailable),
+    Err(user::FetchOneError::Unknown(_)) => return Err(Error::Unknown),
+  };
+  let owner = user.id == viewer;
+  if !owner {
+    user.email = None;
+    user.channels = user.channels.visible();
+  }
+
+  let careers = match careers.find_by_user_id(user.id).await {
+    Ok(careers) => careers.into_iter().filter(|c| owner || !c.hidden).collect::<Vec<_>>(),
+    Err(career::FetchError::Unavailable) => return Err(Error::Unavailable),
+    Err(career::FetchError::Unknown(_)) => return Err(Error::Unknown),
+  };
+
+  Ok(json_resume::export(&user, &careers, Utc::now().with_timezone(&FixedOffset::east(9 * 3600)).date_naive()))
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::NaiveDate;
+
+  use crate::{
+    domain::{career::entity::{CareerPeriod, CompanyName, JobTitle}, user::entity::{ChannelKind, ContactChannel, ContactChannels, UserAvatar, UserEmail, UserLogin, UserName}},
+    repositories::{career::{InMemoryRepository as InMemoryCareers, Repository as _}, user::{InMemoryRepository, Repository as _}},
+  };
+
+  use super::*;
+
+  async fn users() -> Arc<InMemoryRepository> {
+    let users = Arc::new(InMemoryRepository::_new());
+    let _ = users.upsert_from_provider(UserId::one(), UserLogin::kent_back(), UserName::kent_back(), UserAvatar::user(), Some(UserEmail::gmail())).await;
+    let channels = ContactChannels::try_from(vec![
+      ContactChannel { kind: ChannelKind::Twitter, value: "kent".to_string(), visible: true },
+      ContactChannel { kind: ChannelKind::Phone, value: "010-1234-5678".to_string(), visible: false },
+    ]).unwrap();
+    let _ = users.update_channels(UserId::one(), channels).await;
+
+    users
+  }
+
+  #[tokio::test]
+  async fn it_should_be_show_everything_to_the_owner() {
+    let resume = execute(users().await, Arc::new(InMemoryCareers::new()), i64::from(UserId::one()), Request { id: i64::from(UserId::one()) }).await.unwrap();
+
+    assert_eq!(resume.basics.email, Some(String::from(UserEmail::gmail())));
+    assert_eq!(resume.basics.phone, Some("010-1234-5678".to_string()));
+  }
+
+  #[tokio::test]
+  async fn it_should_be_hide_the_email_and_hidden_channels_from_others() {
+    let resume = execute(users().await, Arc::new(InMemoryCareers::new()), i64::from(UserId::two()), Request { id: i64::from(UserId::one()) }).await.unwrap();
+
+    assert_eq!(resume.basics.email, None);
+    assert_eq!(resume.basics.phone, None);
+    assert_eq!(resume.basics.profiles.len(), 1);
+  }
+
+  #[tokio::test]
+  async fn it_should_be_leave_out_hidden_careers_for_others() {
+    let careers = Arc::new(InMemoryCareers::new());
+    let _ = careers.insert(i64::from(UserId::one()), CompanyName::pine_apple(), JobTitle::server_engineer(), CareerPeriod::since(NaiveDate::from_ymd(2020, 1, 1)), true).await;
+    let _ = careers.set_hidden(1, true).await;
+
+    let owner = execute(users().await, careers.clone(), i64::from(UserId::one()), Request { id: i64::from(UserId::one()) }).await.unwrap();
+    let other = execute(users().await, careers, i64::from(UserId::two()), Request { id: i64::from(UserId::one()) }).await.unwrap();
+
+    assert_eq!(owner.work.len(), 1);
+    assert!(other.work.is_empty());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_a_not_found_error_for_an_unknown_user() {
+    match execute(users().await, Arc::new(InMemoryCareers::new()), i64::from(UserId::one()), Request { id: i64::from(UserId::two()) }).await {
+      Err(Error::NotFound) => {},
+      _ => unreachable!(),
+    }
+  }
+}
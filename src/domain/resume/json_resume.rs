@@ -0,0 +1,255 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::domain::{
+  career::{entity::CareerEntity, experience_summary},
+  user::entity::{ChannelKind, UserEntity},
+};
+
+// https://jsonresume.org/schema/
+pub const SCHEMA: &str = "https://raw.githubusercontent.com/jsonresume/resume-schema/v1.0.0/schema.json";
+const VERSION: &str = "v1.0.0";
+
+/// A jsonresume.org document. Every field the schema knows is a string when present,
+/// so empty values are left out rather than written as `null`.
+#[derive(Debug, Serialize)]
+pub struct JsonResume {
+  #[serde(rename="$schema")]
+  pub schema: &'static str,
+  pub basics: Basics,
+  pub work: Vec<Work>,
+  pub meta: Meta,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Basics {
+  pub name: String,
+  /// the current job title
+  #[serde(skip_serializing_if="Option::is_none")]
+  pub label: Option<String>,
+  pub image: String,
+  #[serde(skip_serializing_if="Option::is_none")]
+  pub email: Option<String>,
+  #[serde(skip_serializing_if="Option::is_none")]
+  pub phone: Option<String>,
+  /// the blog
+  #[serde(skip_serializing_if="Option::is_none")]
+  pub url: Option<String>,
+  pub profiles: Vec<Profile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Profile {
+  pub network: String,
+  #[serde(skip_serializing_if="Option::is_none")]
+  pub username: Option<String>,
+  pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct Work {
+  /// the company
+  pub name: String,
+  pub position: String,
+  pub start_date: String,
+  /// left out while the user still works there
+  #[serde(skip_serializing_if="Option::is_none")]
+  pub end_date: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct Meta {
+  pub version: &'static str,
+  pub last_modified: String,
+}
+
+fn date(date: NaiveDate) -> String {
+  date.format("%Y-%m-%d").to_string()
+}
+
+/// Renders what the caller lets through of `user`: pass the email and hidden channels only to their owner.
+pub fn export(user: &UserEntity, careers: &[CareerEntity], today: NaiveDate) -> JsonResume {
+  let channel = |kind: ChannelKind| user.channels.get(kind).map(|c| c.value.clone());
+
+  let mut profiles = vec![];
+  if let Some(handle) = channel(ChannelKind::Twitter) {
+    profiles.push(Profile { network: "Twitter".to_string(), url: format!("https://twitter.com/{}", handle), username: Some(handle) });
+  }
+  if let Some(url) = channel(ChannelKind::Linkedin) {
+    profiles.push(Profile { network: "LinkedIn".to_string(), username: None, url });
+  }
+
+  let label = experience_summary::summarize(careers, today).current.map(|c| c.job);
+  let mut careers = careers.iter().collect::<Vec<_>>();
+  careers.sort_by_key(|c| std::cmp::Reverse((c.in_at, c.id)));
+  let work = careers.into_iter().map(|c| Work {
+    name: c.company.clone(),
+    position: c.job.clone(),
+    start_date: date(c.in_at),
+    end_date: c.out_at.map(date),
+  }).collect();
+
+  JsonResume {
+    schema: SCHEMA,
+    basics: Basics {
+      name: user.name.clone(),
+      label,
+      image: user.avatar_url.clone(),
+      // a public contact address before the account one
+      email: channel(ChannelKind::Email).or_else(|| user.email.clone()),
+      phone: channel(ChannelKind::Phone),
+      url: channel(ChannelKind::Blog),
+      profiles,
+    },
+    work,
+    meta: Meta {
+      version: VERSION,
+      last_modified: user.updated_at.format("%Y-%m-%dT%H:%M:%S").to_string(),
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use oauth2::url::Url;
+  use serde_json::{json, Map, Value};
+
+  use crate::domain::{
+    career::entity::{CareerPeriod, CompanyName, JobTitle},
+    user::entity::{ContactChannel, ContactChannels, UserAvatar, UserId, UserLogin, UserName},
+  };
+
+  use super::*;
+
+  // the properties resume-schema v1.0.0 defines per object, anything else is a typo
+  const ROOT: &[&str] = &["$schema", "basics", "work", "volunteer", "education", "awards", "certificates", "publications", "skills", "languages", "interests", "references", "projects", "meta"];
+  const BASICS: &[&str] = &["name", "label", "image", "email", "phone", "url", "summary", "location", "profiles"];
+  const PROFILE: &[&str] = &["network", "username", "url"];
+  const WORK: &[&str] = &["name", "location", "description", "position", "url", "startDate", "endDate", "summary", "highlights"];
+  const META: &[&str] = &["canonical", "version", "lastModified"];
+
+  fn object<'a>(value: &'a Value, allowed: &[&str]) -> &'a Map<String, Value> {
+    let object = value.as_object().expect("an object");
+    for key in object.keys() {
+      assert!(allowed.contains(&key.as_str()), "unknown property {}", key);
+    }
+
+    object
+  }
+
+  fn strings(object: &Map<String, Value>) {
+    for (key, value) in object {
+      assert!(value.is_string(), "{} is not a string: {}", key, value);
+    }
+  }
+
+  // the schema's `iso8601` definition: YYYY-MM-DD, YYYY-MM or YYYY
+  fn iso8601(value: &Value) -> bool {
+    let value = value.as_str().unwrap_or_default();
+    let parts = value.split('-').collect::<Vec<_>>();
+    let digits = |part: &str, len: usize, first: std::ops::RangeInclusive<char>| {
+      part.len() == len && part.chars().all(|c| c.is_ascii_digit()) && part.chars().next().is_some_and(|c| first.contains(&c))
+    };
+
+    !parts.is_empty() && parts.len() <= 3
+      && digits(parts[0], 4, '1'..='2')
+      && parts.get(1).is_none_or(|month| digits(month, 2, '0'..='1'))
+      && parts.get(2).is_none_or(|day| digits(day, 2, '0'..='3'))
+  }
+
+  fn assert_conforms(resume: &Value) {
+    let root = object(resume, ROOT);
+    assert_eq!(root["$schema"], json!(SCHEMA));
+
+    let basics = object(&root["basics"], BASICS);
+    for (key, value) in basics.iter().filter(|(key, _)| *key != "profiles") {
+      assert!(value.is_string(), "{} is not a string: {}", key, value);
+    }
+    if let Some(email) = basics.get("email") {
+      assert!(email.as_str().unwrap().contains('@'));
+    }
+    if let Some(url) = basics.get("url") {
+      assert!(Url::parse(url.as_str().unwrap()).is_ok());
+    }
+    for profile in basics["profiles"].as_array().expect("profiles") {
+      strings(object(profile, PROFILE));
+      assert!(Url::parse(profile["url"].as_str().unwrap()).is_ok());
+    }
+
+    for work in root["work"].as_array().expect("work") {
+      let work = object(work, WORK);
+      strings(work);
+      assert!(iso8601(&work["startDate"]), "startDate {}", work["startDate"]);
+      if let Some(end_date) = work.get("endDate") {
+        assert!(iso8601(end_date), "endDate {}", end_date);
+      }
+    }
+
+    strings(object(&root["meta"], META));
+  }
+
+  fn user(channels: Vec<ContactChannel>) -> UserEntity {
+    UserEntity {
+      email: Some("kent@gmail.com".to_string()),
+      channels: ContactChannels::try_from(channels).unwrap(),
+      ..UserEntity::new(UserId::one(), UserLogin::kent_back(), UserName::kent_back(), UserAvatar::user())
+    }
+  }
+
+  fn channel(kind: ChannelKind, value: &str) -> ContactChannel {
+    ContactChannel { kind, value: value.to_string(), visible: true }
+  }
+
+  fn careers() -> Vec<CareerEntity> {
+    vec![
+      CareerEntity::new(1, 1, CompanyName::micro_hard(), JobTitle::try_from("Designer".to_string()).unwrap(), CareerPeriod::between(NaiveDate::from_ymd(2016, 5, 1), NaiveDate::from_ymd(2018, 3, 31)), true),
+      CareerEntity::new(2, 1, CompanyName::pine_apple(), JobTitle::server_engineer(), CareerPeriod::since(NaiveDate::from_ymd(2018, 4, 1)), true),
+    ]
+  }
+
+  fn today() -> NaiveDate {
+    NaiveDate::from_ymd(2023, 1, 1)
+  }
+
+  #[test]
+  fn it_should_be_conform_to_the_json_resume_schema() {
+    let user = user(vec![
+      channel(ChannelKind::Email, "hello@kent.dev"),
+      channel(ChannelKind::Blog, "https://kent.dev"),
+      channel(ChannelKind::Twitter, "@kent"),
+      channel(ChannelKind::Linkedin, "https://www.linkedin.com/in/kent"),
+      channel(ChannelKind::Phone, "+82 10-1234-5678"),
+    ]);
+
+    assert_conforms(&serde_json::to_value(export(&user, &careers(), today())).unwrap());
+  }
+
+  #[test]
+  fn it_should_be_leave_out_what_the_user_does_not_have() {
+    let user = UserEntity { email: None, ..user(vec![]) };
+
+    let resume = serde_json::to_value(export(&user, &[], today())).unwrap();
+
+    assert_conforms(&resume);
+    assert_eq!(resume["basics"], json!({ "name": String::from(UserName::kent_back()), "image": "avatar_url", "profiles": [] }));
+    assert_eq!(resume["work"], json!([]));
+  }
+
+  #[test]
+  fn it_should_be_map_channels_and_careers() {
+    let user = user(vec![channel(ChannelKind::Twitter, "kent"), channel(ChannelKind::Blog, "https://kent.dev")]);
+
+    let resume = serde_json::to_value(export(&user, &careers(), today())).unwrap();
+
+    assert_eq!(resume["basics"]["label"], json!("Server Engineer"));
+    assert_eq!(resume["basics"]["email"], json!("kent@gmail.com"));
+    assert_eq!(resume["basics"]["url"], json!("https://kent.dev/"));
+    assert_eq!(resume["basics"]["profiles"], json!([{ "network": "Twitter", "username": "kent", "url": "https://twitter.com/kent" }]));
+    assert_eq!(resume["work"], json!([
+      { "name": "PineApple", "position": "Server Engineer", "startDate": "2018-04-01" },
+      { "name": "Micro Hard", "position": "Designer", "startDate": "2016-05-01", "endDate": "2018-03-31" },
+    ]));
+  }
+}
@@ -0,0 +1,2 @@
+pub mod json_resume;
+pub mod export_resume;
@@ -0,0 +1,79 @@
+use std::collections::HashSet;
+
+/// More terms than this are ignored, they rarely narrow the result further.
+const MAX_TERMS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+  /// nothing searchable was left after splitting the query into words
+  Empty,
+}
+
+/// Lowercased words of a query, each matched as a prefix of a word in a profile or career.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchTerms(Vec<String>);
+
+/// Splits on anything but letters and digits, the way the search columns are indexed.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+  text.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).map(str::to_lowercase)
+}
+
+impl TryFrom<String> for SearchTerms {
+  type Error = Error;
+
+  fn try_from(q: String) -> Result<Self, Self::Error> {
+    let mut seen = HashSet::new();
+    let terms = tokenize(&q).filter(|term| seen.insert(term.clone())).take(MAX_TERMS).collect::<Vec<_>>();
+
+    if terms.is_empty() {
+      Err(Error::Empty)
+    } else {
+      Ok(Self(terms))
+    }
+  }
+}
+
+impl SearchTerms {
+  /// A postgres `tsquery` matching any of the terms by prefix; safe to bind, terms are letters and digits only.
+  pub fn to_tsquery(&self) -> String {
+    self.0.iter().map(|term| format!("{}:*", term)).collect::<Vec<_>>().join(" | ")
+  }
+}
+
+// token matching for the in-memory repository
+#[cfg(any(test, feature = "test-support"))]
+impl SearchTerms {
+  pub fn terms(&self) -> &[String] {
+    &self.0
+  }
+
+  /// Whether the term is the beginning of any word of `text`.
+  pub fn matches(term: &str, text: &str) -> bool {
+    tokenize(text).any(|word| word.starts_with(term))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_should_be_split_a_query_into_distinct_words() {
+    let terms = SearchTerms::try_from("Rust engineer at Wercel, rust!".to_string()).unwrap();
+
+    assert_eq!(terms.terms(), ["rust", "engineer", "at", "wercel"]);
+    assert_eq!(terms.to_tsquery(), "rust:* | engineer:* | at:* | wercel:*");
+  }
+
+  #[test]
+  fn it_should_be_reject_a_query_without_words() {
+    assert_eq!(SearchTerms::try_from(" & | ! ".to_string()), Err(Error::Empty));
+  }
+
+  #[test]
+  fn it_should_be_match_the_beginning_of_a_word() {
+    assert!(SearchTerms::matches("eng", "Server Engineer"));
+    assert!(SearchTerms::matches("back", "kent-back"));
+    assert!(!SearchTerms::matches("gineer", "Server Engineer"));
+  }
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::domain::{search::entity::SearchTerms, user::entity::UserEntity};
+use crate::repositories::search::{Repository, SearchError};
+
+const DEFAULT_LIMIT: u64 = 20;
+const MAX_LIMIT: u64 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+  /// free text, e.g. `rust engineer at Wercel`
+  pub q: String,
+  pub limit: Option<u64>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+  /// no words in `q`, or the limit is not between 1 and 50
+  BadRequest,
+  /// the database is unreachable, the request may be retried
+  Unavailable,
+  Unknown,
+}
+
+pub async fn execute(repo: Arc<dyn Repository>, req: Request) -> Result<Vec<UserEntity>, Error> {
+  let limit = req.limit.unwrap_or(DEFAULT_LIMIT);
+  if !(1..=MAX_LIMIT).contains(&limit) {
+    return Err(Error::BadRequest);
+  }
+  let terms = SearchTerms::try_from(req.q).map_err(|_| Error::BadRequest)?;
+
+  match repo.search(terms, limit).await {
+    Ok(users) => Ok(users),
+    Err(SearchError::Unavailable) => Err(Error::Unavailable),
+    Err(SearchError::Unknown(_)) => Err(Error::Unknown),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::NaiveDate;
+
+  use crate::{
+    domain::{career::entity::{CareerEntity, CareerPeriod, CompanyName, JobTitle}, user::entity::{UserAvatar, UserId, UserLogin, UserName}},
+    repositories::search::InMemoryRepository,
+  };
+
+  use super::*;
+
+  fn user(id: i64, login: &str, name: &str) -> UserEntity {
+    UserEntity::new(
+      UserId::try_from(id).unwrap(),
+      UserLogin::try_from(login.to_string()).unwrap(),
+      UserName::try_from(name.to_string()).unwrap(),
+      UserAvatar::user(),
+    )
+  }
+
+  fn repo() -> Arc<InMemoryRepository> {
+    let users = vec![user(1, "kent-back", "Kent Back"), user(2, "rusty", "Rusty Ryan"), user(3, "linus", "Linus Caldwell")];
+    let careers = vec![
+      CareerEntity::new(1, 1, CompanyName::pine_apple(), JobTitle::server_engineer(), CareerPeriod::since(NaiveDate::from_ymd(2020, 1, 1)), true),
+      CareerEntity::new(2, 3, CompanyName::micro_hard(), JobTitle::server_engineer(), CareerPeriod::since(NaiveDate::from_ymd(2021, 1, 1)), true),
+    ];
+
+    Arc::new(InMemoryRepository::new(users, careers))
+  }
+
+  fn request(q: &str) -> Request {
+    Request { q: q.to_string(), limit: None }
+  }
+
+  fn ids(users: Vec<UserEntity>) -> Vec<i64> {
+    users.into_iter().map(|user| user.id).collect()
+  }
+
+  #[tokio::test]
+  async fn it_should_be_rank_profile_matches_above_career_matches() {
+    let res = execute(repo(), request("rust engineer")).await.unwrap();
+
+    assert_eq!(ids(res), vec![2, 1, 3]);
+  }
+
+  #[tokio::test]
+  async fn it_should_be_match_companies_and_logins_by_prefix() {
+    assert_eq!(ids(execute(repo(), request("micro")).await.unwrap()), vec![3]);
+    assert_eq!(ids(execute(repo(), request("BACK")).await.unwrap()), vec![1]);
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_a_bad_request_for_an_empty_query() {
+    match execute(repo(), request(" ?! ")).await {
+      Err(Error::BadRequest) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_error_when_the_repo_fails() {
+    let repo = Arc::new(InMemoryRepository::new(vec![], vec![]).with_error());
+
+    match execute(repo, request("rust")).await {
+      Err(Error::Unknown) => {},
+      _ => unreachable!(),
+    }
+  }
+}
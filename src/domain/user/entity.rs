@@ -1,7 +1,9 @@
 use std::{fmt::{Display, Formatter}};
+#[cfg(any(test, feature = "test-support"))]
 use chrono::{FixedOffset, Utc};
-use sea_orm::{FromQueryResult, prelude::DateTimeWithTimeZone};
-use serde::Deserialize;
+use oauth2::url::Url;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Error {
@@ -17,7 +19,7 @@ impl Display for Error {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
     match self {
       Error::EmptyString => write!(f, "Empty String"),
-      Error::MissingSeparator => write!(f, "Missing separator character '{}'.", '@'),
+      Error::MissingSeparator => write!(f, "Missing separator character '@'."),
       Error::LocalPartTooLong => write!(
         f,
         "Local part is too long. Length limit: {}",
@@ -117,7 +119,7 @@ impl TryFrom<i64> for UserId {
   type Error = ();
 
   fn try_from(n: i64) -> Result<Self, Self::Error> {
-    if n > USER_ID_MAX_LENGTH || n < 0 {
+    if !(0..=USER_ID_MAX_LENGTH).contains(&n) {
       Err(())
     } else {
       Ok(Self(n))
@@ -169,6 +171,10 @@ impl UserLogin {
     Self(String::from("kent-back"))
   }
 
+  pub fn no_name() -> Self {
+    Self(String::from("no-name"))
+  }
+
   pub fn bad() -> Self {
     Self(String::from(""))
   }
@@ -234,7 +240,7 @@ impl UserAvatar {
   }
 }
 
-#[derive(Clone, Debug, Deserialize, FromQueryResult)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct UserEntity {
   pub id: i64,
   pub login: String,
@@ -243,9 +249,12 @@ pub struct UserEntity {
   pub email: Option<String>,
   pub created_at: DateTimeWithTimeZone,
   pub updated_at: DateTimeWithTimeZone,
+  pub last_signed_in_at: Option<DateTimeWithTimeZone>,
+  pub channels: ContactChannels,
 }
 
 impl UserEntity {
+  #[cfg(any(test, feature = "test-support"))]
   pub fn new(id: UserId, login: UserLogin, name: UserName, avatar_url: UserAvatar) -> Self {
     let now = Utc::now().with_timezone(&FixedOffset::east(9 * 3600));
     Self {
@@ -256,6 +265,225 @@ impl UserEntity {
       email: None,
       created_at: now,
       updated_at: now,
+      last_signed_in_at: None,
+      channels: ContactChannels::default(),
+    }
+  }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub enum ChannelKind {
+  Email,
+  Blog,
+  /// Twitter / X
+  Twitter,
+  Linkedin,
+  Phone,
+}
+
+impl ChannelKind {
+  /// Where a problem with the entry is reported.
+  pub fn field(&self) -> &'static str {
+    match self {
+      ChannelKind::Email => "channels.email",
+      ChannelKind::Blog => "channels.blog",
+      ChannelKind::Twitter => "channels.twitter",
+      ChannelKind::Linkedin => "channels.linkedin",
+      ChannelKind::Phone => "channels.phone",
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelError {
+  /// every kind may appear once
+  Duplicate(ChannelKind),
+  Invalid(ChannelKind, String),
+}
+
+impl Display for ChannelError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ChannelError::Duplicate(_) => write!(f, "only one entry per channel"),
+      ChannelError::Invalid(_, reason) => write!(f, "{}", reason),
+    }
+  }
+}
+
+impl ChannelError {
+  pub fn kind(&self) -> ChannelKind {
+    match self {
+      ChannelError::Duplicate(kind) | ChannelError::Invalid(kind, _) => *kind,
+    }
+  }
+}
+
+const TWITTER_HANDLE_MAX_LENGTH: usize = 15;
+const PHONE_MIN_DIGITS: usize = 7;
+// https://www.itu.int/rec/T-REC-E.164
+const PHONE_MAX_DIGITS: usize = 15;
+
+/// One way to reach the user. `visible` entries are shown to everybody, the rest only to the user.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContactChannel {
+  pub kind: ChannelKind,
+  pub value: String,
+  pub visible: bool,
+}
+
+impl ContactChannel {
+  /// Validates the value for its kind and brings it into the stored form.
+  fn validate(self) -> Result<Self, ChannelError> {
+    let kind = self.kind;
+    let invalid = |reason: &str| ChannelError::Invalid(kind, reason.to_string());
+    let value = self.value.trim();
+
+    let value = match kind {
+      ChannelKind::Email => UserEmail::try_from(value.to_string()).map(String::from).map_err(|e| invalid(&e.to_string()))?,
+      ChannelKind::Blog => match Url::parse(value) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.host_str().is_some() => url.to_string(),
+        _ => return Err(invalid("must be an http(s) url")),
+      },
+      ChannelKind::Linkedin => match Url::parse(value) {
+        Ok(url) if url.scheme() == "https" && url.host_str().is_some_and(|host| host == "linkedin.com" || host.ends_with(".linkedin.com")) => url.to_string(),
+        _ => return Err(invalid("must be an https://linkedin.com url")),
+      },
+      ChannelKind::Twitter => {
+        let handle = value.strip_prefix('@').unwrap_or(value);
+        if handle.is_empty() || handle.len() > TWITTER_HANDLE_MAX_LENGTH || !handle.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+          return Err(invalid("must be a handle of up to 15 letters, digits and underscores"));
+        }
+        handle.to_string()
+      },
+      ChannelKind::Phone => {
+        let digits = value.chars().filter(char::is_ascii_digit).count();
+        let allowed = value.strip_prefix('+').unwrap_or(value).chars().all(|c| c.is_ascii_digit() || matches!(c, ' ' | '-' | '(' | ')'));
+        if !allowed || !(PHONE_MIN_DIGITS..=PHONE_MAX_DIGITS).contains(&digits) {
+          return Err(invalid("must be a phone number of 7 to 15 digits"));
+        }
+        value.to_string()
+      },
+    };
+
+    Ok(Self { kind, value, visible: self.visible })
+  }
+}
+
+/// The contact channels of a user, kept in the `channel` json column.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ContactChannels(Vec<ContactChannel>);
+
+impl TryFrom<Vec<ContactChannel>> for ContactChannels {
+  type Error = Vec<ChannelError>;
+
+  /// Reports every invalid entry at once.
+  fn try_from(channels: Vec<ContactChannel>) -> Result<Self, Self::Error> {
+    let mut valid: Vec<ContactChannel> = vec![];
+    let mut errors = vec![];
+    for channel in channels {
+      if valid.iter().any(|c| c.kind == channel.kind) || errors.iter().any(|e: &ChannelError| e.kind() == channel.kind) {
+        errors.push(ChannelError::Duplicate(channel.kind));
+        continue;
+      }
+      match channel.validate() {
+        Ok(channel) => valid.push(channel),
+        Err(e) => errors.push(e),
+      }
+    }
+
+    if errors.is_empty() {
+      Ok(Self(valid))
+    } else {
+      Err(errors)
     }
   }
-}
\ No newline at end of file
+}
+
+impl ContactChannels {
+  /// What somebody other than the user gets to see.
+  pub fn visible(self) -> Self {
+    Self(self.0.into_iter().filter(|c| c.visible).collect())
+  }
+
+  /// The channel of a kind, there is at most one.
+  pub fn get(&self, kind: ChannelKind) -> Option<&ContactChannel> {
+    self.0.iter().find(|c| c.kind == kind)
+  }
+
+  #[cfg(test)]
+  pub fn into_inner(self) -> Vec<ContactChannel> {
+    self.0
+  }
+}
+
+/// A requested email change waiting for the link mailed to the new address, see `verify_email`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmailVerificationEntity {
+  pub user_id: i64,
+  pub email: String,
+  pub expires_at: DateTimeWithTimeZone,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn channel(kind: ChannelKind, value: &str) -> ContactChannel {
+    ContactChannel { kind, value: value.to_string(), visible: true }
+  }
+
+  #[test]
+  fn it_should_be_accept_valid_channels() {
+    let channels = ContactChannels::try_from(vec![
+      channel(ChannelKind::Email, " kent@gmail.com "),
+      channel(ChannelKind::Blog, "https://kent.dev/posts"),
+      channel(ChannelKind::Twitter, "@kent_back"),
+      channel(ChannelKind::Linkedin, "https://www.linkedin.com/in/kent-back"),
+      channel(ChannelKind::Phone, "+82 10-1234-5678"),
+    ]).unwrap();
+
+    let values = channels.into_inner().into_iter().map(|c| c.value).collect::<Vec<_>>();
+    assert_eq!(values, vec!["kent@gmail.com", "https://kent.dev/posts", "kent_back", "https://www.linkedin.com/in/kent-back", "+82 10-1234-5678"]);
+  }
+
+  #[test]
+  fn it_should_be_report_every_invalid_channel() {
+    let res = ContactChannels::try_from(vec![
+      channel(ChannelKind::Email, "kent"),
+      channel(ChannelKind::Blog, "ftp://kent.dev"),
+      channel(ChannelKind::Twitter, "kent back"),
+      channel(ChannelKind::Linkedin, "https://linkedin.com.evil.io/in/kent"),
+      channel(ChannelKind::Phone, "123"),
+    ]);
+
+    let kinds = res.unwrap_err().iter().map(ChannelError::kind).collect::<Vec<_>>();
+    assert_eq!(kinds, vec![ChannelKind::Email, ChannelKind::Blog, ChannelKind::Twitter, ChannelKind::Linkedin, ChannelKind::Phone]);
+  }
+
+  #[test]
+  fn it_should_be_reject_a_second_entry_of_the_same_kind() {
+    let res = ContactChannels::try_from(vec![channel(ChannelKind::Twitter, "kent"), channel(ChannelKind::Twitter, "back")]);
+
+    assert_eq!(res, Err(vec![ChannelError::Duplicate(ChannelKind::Twitter)]));
+  }
+
+  #[test]
+  fn it_should_be_hide_channels_the_user_did_not_make_visible() {
+    let channels = ContactChannels::try_from(vec![
+      channel(ChannelKind::Twitter, "kent"),
+      ContactChannel { visible: false, ..channel(ChannelKind::Phone, "010-1234-5678") },
+    ]).unwrap();
+
+    assert_eq!(channels.visible().into_inner(), vec![channel(ChannelKind::Twitter, "kent")]);
+  }
+
+  #[test]
+  fn it_should_be_read_channels_as_stored() {
+    let stored = serde_json::json!([{ "kind": "blog", "value": "https://kent.dev/", "visible": false }]);
+
+    let channels: ContactChannels = serde_json::from_value(stored.clone()).unwrap();
+
+    assert_eq!(serde_json::to_value(&channels).unwrap(), stored);
+  }
+}
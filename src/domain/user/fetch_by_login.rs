@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::domain::user::entity::{UserEntity, UserLogin};
+use crate::repositories::user::{Repository, FetchOneError};
+
+#[derive(Deserialize)]
+pub struct Request {
+  /// matched ignoring case
+  pub login: String,
+}
+
+#[derive(Debug)]
+pub enum Response {
+  Found(UserEntity),
+  /// the login is an old handle of the user; redirect temporarily, someone else may claim it later
+  Moved { login: String },
+}
+
+#[derive(Debug)]
+pub enum Error {
+  BadRequest,
+  NotFound,
+  /// the database is unreachable, the request may be retried
+  Unavailable,
+  Unknown,
+}
+
+pub async fn execute(repo: Arc<dyn Repository>, req: Request) -> Result<Response, Error> {
+  let login = UserLogin::try_from(req.login.clone()).map_err(|_| Error::BadRequest)?;
+
+  match repo.fetch_by_login(login).await {
+    Ok(user) if user.login.eq_ignore_ascii_case(&req.login) => Ok(Response::Found(user)),
+    Ok(user) => Ok(Response::Moved { login: user.login }),
+    Err(FetchOneError::NotFound) => Err(Error::NotFound),
+    Err(FetchOneError::Unavailable) => Err(Error::Unavailable),
+    Err(FetchOneError::Unknown(_)) => Err(Error::Unknown),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{repositories::user::InMemoryRepository, domain::user::entity::{UserAvatar, UserId, UserName}};
+
+  use super::*;
+
+  async fn repo() -> Arc<InMemoryRepository> {
+    let repo = Arc::new(InMemoryRepository::_new());
+    let _ = repo.upsert_from_provider(UserId::one(), UserLogin::kent_back(), UserName::kent_back(), UserAvatar::user(), None).await;
+
+    repo
+  }
+
+  fn request(login: &str) -> Request {
+    Request { login: login.to_string() }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_find_the_user_ignoring_case() {
+    match execute(repo().await, request("Kent-Back")).await {
+      Ok(Response::Found(user)) => assert_eq!(user.id, i64::from(UserId::one())),
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_move_an_old_handle_to_the_current_one() {
+    let repo = repo().await;
+    let login = UserLogin::try_from("kent".to_string()).unwrap();
+    let _ = repo.upsert_from_provider(UserId::one(), login, UserName::kent_back(), UserAvatar::user(), None).await;
+
+    match execute(repo, request("KENT-BACK")).await {
+      Ok(Response::Moved { login }) => assert_eq!(login, "kent".to_string()),
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_a_not_found_error_for_an_unknown_login() {
+    match execute(repo().await, request("nobody")).await {
+      Err(Error::NotFound) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_a_bad_request_for_an_empty_login() {
+    match execute(repo().await, request("")).await {
+      Err(Error::BadRequest) => {},
+      _ => unreachable!(),
+    }
+  }
+}
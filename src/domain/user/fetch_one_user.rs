@@ -1,8 +1,9 @@
 use std::sync::Arc;
 
+use sea_orm::prelude::DateTimeWithTimeZone;
 use serde::{Deserialize, Serialize};
 
-use crate::domain::user::entity::{UserId};
+use crate::domain::user::entity::{ContactChannels, UserEntity, UserId};
 use crate::repositories::user::{Repository, FetchOneError};
 
 #[derive(Deserialize)]
@@ -18,27 +19,45 @@ pub struct Response {
   pub name: String,
   pub avatar_url: String,
   pub email: Option<String>,
+  pub created_at: DateTimeWithTimeZone,
+  pub updated_at: DateTimeWithTimeZone,
+  #[serde(skip_serializing_if="Option::is_none")]
+  pub last_signed_in_at: Option<DateTimeWithTimeZone>,
+  pub channels: ContactChannels,
+}
+
+impl From<UserEntity> for Response {
+  fn from(user: UserEntity) -> Self {
+    Self {
+      id: user.id,
+      login: user.login,
+      name: user.name,
+      avatar_url: user.avatar_url,
+      email: user.email,
+      created_at: user.created_at,
+      updated_at: user.updated_at,
+      last_signed_in_at: user.last_signed_in_at,
+      channels: user.channels,
+    }
+  }
 }
 
 #[derive(Debug)]
 pub enum Error {
   BadRequest,
   NotFound,
+  /// the database is unreachable, the request may be retried
+  Unavailable,
   Unknown,
 }
 
 pub async fn execute(repo: Arc<dyn Repository>, req: Request) -> Result<Response, Error> {
   match UserId::try_from(req.id) {
     Ok(id) => match repo.fetch_one(id).await {
-      Ok(user) => Ok(Response {
-        id: user.id,
-        login: user.login,
-        name: user.name,
-        avatar_url: user.avatar_url,
-        email: user.email,
-      }),
+      Ok(user) => Ok(Response::from(user)),
       Err(FetchOneError::NotFound) => Err(Error::NotFound),
-      Err(FetchOneError::Unknown) => Err(Error::Unknown),
+      Err(FetchOneError::Unavailable) => Err(Error::Unavailable),
+      Err(FetchOneError::Unknown(_)) => Err(Error::Unknown),
     },
     _ => Err(Error::BadRequest),
   }
@@ -63,10 +82,23 @@ mod tests {
     }
   }
 
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_error_when_the_repo_fails() {
+    let repo = Arc::new(InMemoryRepository::_new().with_error());
+    let req = Request::new(UserId::one());
+
+    let res = execute(repo, req).await;
+
+    match res {
+      Err(Error::Unknown) => {},
+      _ => unreachable!(),
+    }
+  }
+
   #[tokio::test]
   async fn it_should_be_return_the_user_otherwise() {
     let repo = Arc::new(InMemoryRepository::_new());
-    let _ = repo.insert(UserId::one(), UserLogin::kent_back(), UserName::kent_back(), UserAvatar::user()).await;
+    let _ = repo.upsert_from_provider(UserId::one(), UserLogin::kent_back(), UserName::kent_back(), UserAvatar::user(), None).await;
 
     let req = Request::new(UserId::one());
 
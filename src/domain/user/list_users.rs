@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::domain::{auth::entity::Principal, cursor::CursorToken, user::entity::UserEntity};
+use crate::repositories::user::{Cursor, FetchError, ListQuery, Repository, UserFilter, UserSort};
+
+const DEFAULT_LIMIT: u64 = 20;
+const MAX_LIMIT: u64 = 100;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub enum Sort {
+  /// newest first
+  #[default]
+  CreatedAt,
+  Name,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct Request {
+  #[serde(default)]
+  pub sort: Sort,
+  pub company: Option<String>,
+  /// title of a current career
+  pub job: Option<String>,
+  /// signed-in callers only, whether someone left an address is not for everybody to know
+  pub has_email: Option<bool>,
+  /// `nextCursor` of the previous page
+  pub cursor: Option<String>,
+  pub limit: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct Response {
+  pub users: Vec<UserEntity>,
+  /// `None` on the last page
+  pub next_cursor: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+  /// not between 1 and 100
+  InvalidLimit,
+  /// not issued by this endpoint, or for another sort
+  InvalidCursor,
+  /// an anonymous caller filtered by `hasEmail`
+  SignInRequired,
+  /// the database is unreachable, the request may be retried
+  Unavailable,
+  Unknown,
+}
+
+fn encode(user: &UserEntity, sort: Sort) -> String {
+  match sort {
+    Sort::CreatedAt => CursorToken::CreatedAt { at: user.created_at, id: user.id },
+    Sort::Name => CursorToken::Name { name: user.name.clone(), id: user.id },
+  }.encode()
+}
+
+fn decode(cursor: &str, sort: Sort) -> Result<Cursor, Error> {
+  match (CursorToken::decode(cursor), sort) {
+    (Some(CursorToken::CreatedAt { at, id }), Sort::CreatedAt) => Ok(Cursor::CreatedAt(at, id)),
+    (Some(CursorToken::Name { name, id }), Sort::Name) => Ok(Cursor::Name(name, id)),
+    _ => Err(Error::InvalidCursor),
+  }
+}
+
+fn non_blank(value: Option<String>) -> Option<String> {
+  value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+pub async fn execute(repo: Arc<dyn Repository>, viewer: Option<Principal>, req: Request) -> Result<Response, Error> {
+  if req.has_email.is_some() && viewer.is_none() {
+    return Err(Error::SignInRequired);
+  }
+  let limit = req.limit.unwrap_or(DEFAULT_LIMIT);
+  if !(1..=MAX_LIMIT).contains(&limit) {
+    return Err(Error::InvalidLimit);
+  }
+  let after = req.cursor.as_deref().map(|cursor| decode(cursor, req.sort)).transpose()?;
+
+  let query = ListQuery {
+    sort: match req.sort {
+      Sort::CreatedAt => UserSort::CreatedAt,
+      Sort::Name => UserSort::Name,
+    },
+    filter: UserFilter {
+      company: non_blank(req.company),
+      job: non_blank(req.job),
+      has_email: req.has_email,
+    },
+    after,
+    // one more than asked tells whether another page follows
+    limit: limit + 1,
+  };
+
+  let mut users = match repo.list(query).await {
+    Ok(users) => users,
+    Err(FetchError::Unavailable) => return Err(Error::Unavailable),
+    Err(FetchError::Unknown(_)) => return Err(Error::Unknown),
+  };
+
+  let next_cursor = if users.len() as u64 > limit {
+    users.truncate(limit as usize);
+    users.last().map(|user| encode(user, req.sort))
+  } else {
+    None
+  };
+
+  Ok(Response { users, next_cursor })
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::NaiveDate;
+
+  use crate::{
+    domain::{career::entity::{CareerEntity, CareerPeriod, CompanyName, JobTitle}, user::entity::{UserAvatar, UserEmail, UserId, UserLogin, UserName}},
+    repositories::user::InMemoryRepository,
+  };
+
+  use super::*;
+
+  async fn repo() -> Arc<InMemoryRepository> {
+    let careers = vec![
+      CareerEntity::new(1, 1, CompanyName::pine_apple(), JobTitle::server_engineer(), CareerPeriod::since(NaiveDate::from_ymd(2020, 1, 1)), true),
+      CareerEntity::new(2, 2, CompanyName::pine_apple(), JobTitle::server_engineer(), CareerPeriod::between(NaiveDate::from_ymd(2018, 1, 1), NaiveDate::from_ymd(2019, 12, 31)), true),
+      CareerEntity::new(3, 3, CompanyName::micro_hard(), JobTitle::server_engineer(), CareerPeriod::since(NaiveDate::from_ymd(2021, 1, 1)), true),
+    ];
+    let repo = Arc::new(InMemoryRepository::_new().with_careers(careers));
+    for (id, login, name) in [(1, "ann", "Ann"), (2, "cid", "Cid"), (3, "bob", "Bob"), (4, "dan", "Dan")] {
+      let _ = repo.upsert_from_provider(
+        UserId::try_from(id).unwrap(),
+        UserLogin::try_from(login.to_string()).unwrap(),
+        UserName::try_from(name.to_string()).unwrap(),
+        UserAvatar::user(),
+        (id % 2 == 0).then(UserEmail::gmail),
+      ).await;
+    }
+
+    repo
+  }
+
+  fn viewer() -> Option<Principal> {
+    Some(Principal { user_id: 1, permissions: vec![] })
+  }
+
+  fn names(res: &Response) -> Vec<&str> {
+    res.users.iter().map(|user| user.name.as_str()).collect()
+  }
+
+  #[tokio::test]
+  async fn it_should_be_page_through_every_user_by_name() {
+    let repo = repo().await;
+
+    let first = execute(repo.clone(), viewer(), Request { sort: Sort::Name, limit: Some(3), ..Request::default() }).await.unwrap();
+    let second = execute(repo, viewer(), Request { sort: Sort::Name, limit: Some(3), cursor: first.next_cursor.clone(), ..Request::default() }).await.unwrap();
+
+    assert_eq!(names(&first), vec!["Ann", "Bob", "Cid"]);
+    assert_eq!(names(&second), vec!["Dan"]);
+    assert!(second.next_cursor.is_none());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_list_the_newest_first_by_default() {
+    let res = execute(repo().await, viewer(), Request::default()).await.unwrap();
+
+    assert_eq!(names(&res), vec!["Dan", "Bob", "Cid", "Ann"]);
+    assert!(res.next_cursor.is_none());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_filter_by_company_current_job_and_email() {
+    let repo = repo().await;
+
+    let company = execute(repo.clone(), viewer(), Request { sort: Sort::Name, company: Some("pineapple".to_string()), ..Request::default() }).await.unwrap();
+    let job = execute(repo.clone(), viewer(), Request { sort: Sort::Name, job: Some("server engineer".to_string()), ..Request::default() }).await.unwrap();
+    let email = execute(repo, viewer(), Request { sort: Sort::Name, has_email: Some(true), ..Request::default() }).await.unwrap();
+
+    assert_eq!(names(&company), vec!["Ann", "Cid"]);
+    assert_eq!(names(&job), vec!["Ann", "Bob"]);
+    assert_eq!(names(&email), vec!["Cid", "Dan"]);
+  }
+
+  #[tokio::test]
+  async fn it_should_be_not_filter_by_email_for_an_anonymous_caller() {
+    match execute(repo().await, None, Request { has_email: Some(true), ..Request::default() }).await {
+      Err(Error::SignInRequired) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_reject_a_cursor_of_another_sort() {
+    let repo = repo().await;
+    let first = execute(repo.clone(), viewer(), Request { limit: Some(1), ..Request::default() }).await.unwrap();
+
+    let res = execute(repo, viewer(), Request { sort: Sort::Name, cursor: first.next_cursor, ..Request::default() }).await;
+
+    match res {
+      Err(Error::InvalidCursor) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_reject_an_invalid_limit() {
+    for limit in [0, MAX_LIMIT + 1] {
+      match execute(repo().await, viewer(), Request { limit: Some(limit), ..Request::default() }).await {
+        Err(Error::InvalidLimit) => {},
+        _ => unreachable!(),
+      }
+    }
+  }
+}
@@ -1,4 +1,8 @@
 pub mod entity;
-pub mod create_user;
 pub mod fetch_one_user;
 pub mod update_user;
+pub mod sign_in;
+pub mod verify_email;
+pub mod update_channels;
+pub mod fetch_by_login;
+pub mod list_users;
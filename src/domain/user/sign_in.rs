@@ -0,0 +1,215 @@
+use std::sync::Arc;
+
+use crate::{domain::{auth::entity::ResUserProfile, user::entity::{UserAvatar, UserEmail, UserId, UserLogin, UserName}}, middleware::trace_id, repositories::user::{InsertError, Repository}};
+
+/// The account as the provider describes it right now.
+pub struct Request {
+  pub id: i64,
+  /// the provider the user signed in with, it tells apart the same login at two providers
+  pub provider: String,
+  pub login: String,
+  /// GitHub leaves it null for accounts that never set one
+  pub name: Option<String>,
+  pub avatar_url: String,
+  /// primary verified address, if the provider shared one
+  pub email: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+  BadRequest,
+  /// the database is unreachable, signing in may be retried
+  Unavailable,
+  Unknown,
+}
+
+/// Logins to try in turn when another account holds the provider's one, e.g. a `john` at
+/// GitHub and another at Google: the provider's, then one marked with the provider, then
+/// one marked with the user id as well.
+fn logins(login: &str, provider: &str, id: i64) -> [String; 3] {
+  let provider = provider.to_lowercase();
+
+  [login.to_string(), format!("{}-{}", login, provider), format!("{}-{}-{}", login, provider, id)]
+}
+
+/// Provisions the user behind a provider sign-in: created the first time, refreshed with
+/// the provider's login, name and avatar every time after. A login clash never refuses the
+/// sign-in, the user gets the first of `logins` no one else holds.
+pub async fn execute(repo: Arc<dyn Repository>, req: Request) -> Result<ResUserProfile, Error> {
+  let name = match req.name {
+    Some(name) if !name.trim().is_empty() => name,
+    _ => req.login.clone(),
+  };
+  // an address we cannot store is no reason to refuse the sign-in
+  let email = req.email.and_then(|email| match UserEmail::try_from(email) {
+    Ok(email) => Some(email),
+    Err(e) => {
+      log::warn!("[{}] dropped the provider email of user {}: {:?}", trace_id::current(), req.id, e);
+      None
+    },
+  });
+
+  let (id, name, avatar_url) = match (UserId::try_from(req.id), UserName::try_from(name), UserAvatar::try_from(req.avatar_url)) {
+    (Ok(id), Ok(name), Ok(avatar_url)) => (id, name, avatar_url),
+    _ => return Err(Error::BadRequest),
+  };
+  for login in logins(&req.login, &req.provider, req.id) {
+    let login = UserLogin::try_from(login).map_err(|_| Error::BadRequest)?;
+
+    match repo.upsert_from_provider(id, login, name.clone(), avatar_url.clone(), email.clone()).await {
+      Ok(user) => return Ok(ResUserProfile {
+        id: user.id,
+        login: user.login,
+        name: Some(user.name),
+        avatar_url: user.avatar_url,
+      }),
+      Err(InsertError::Conflict) => continue,
+      Err(InsertError::Unavailable) => return Err(Error::Unavailable),
+      Err(InsertError::Unknown(_)) => return Err(Error::Unknown),
+    }
+  }
+
+  // the last login carries the user id, only a deliberately made clash gets here
+  Err(Error::Unknown)
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::repositories::user::InMemoryRepository;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn it_should_be_create_the_user_on_the_first_sign_in() {
+    let repo = Arc::new(InMemoryRepository::_new());
+
+    let res = execute(repo.clone(), Request::new("kent-back", Some("kent back"), "avatar_url")).await;
+
+    match res {
+      Ok(user) => assert_eq!(user.name, Some("kent back".to_string())),
+      _ => unreachable!(),
+    }
+    assert!(repo.fetch_one(UserId::one()).await.ok().unwrap().last_signed_in_at.is_some());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_refresh_a_returning_user_from_the_provider() {
+    let repo = Arc::new(InMemoryRepository::_new());
+    let _ = execute(repo.clone(), Request::new("kent-back", Some("kent back"), "avatar_url")).await;
+
+    let res = execute(repo.clone(), Request::new("kent", Some("Kent Back"), "new_avatar_url")).await;
+
+    assert!(res.is_ok());
+    let user = repo.fetch_one(UserId::one()).await.ok().unwrap();
+    assert_eq!((user.login, user.name, user.avatar_url), ("kent".to_string(), "Kent Back".to_string(), "new_avatar_url".to_string()));
+  }
+
+  #[tokio::test]
+  async fn it_should_be_fall_back_to_the_login_without_a_name() {
+    let repo = Arc::new(InMemoryRepository::_new());
+
+    for name in [None, Some(" ")] {
+      match execute(repo.clone(), Request::new("kent-back", name, "avatar_url")).await {
+        Ok(user) => assert_eq!(user.name, Some("kent-back".to_string())),
+        _ => unreachable!(),
+      }
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_store_the_provider_email() {
+    let repo = Arc::new(InMemoryRepository::_new());
+
+    let res = execute(repo.clone(), Request { email: Some("kent@gmail.com".to_string()), ..Request::new("kent-back", None, "avatar_url") }).await;
+
+    assert!(res.is_ok());
+    assert_eq!(repo.fetch_one(UserId::one()).await.ok().unwrap().email, Some("kent@gmail.com".to_string()));
+  }
+
+  #[tokio::test]
+  async fn it_should_be_sign_in_without_an_invalid_email() {
+    let repo = Arc::new(InMemoryRepository::_new());
+
+    let res = execute(repo.clone(), Request { email: Some("kent".to_string()), ..Request::new("kent-back", None, "avatar_url") }).await;
+
+    assert!(res.is_ok());
+    assert_eq!(repo.fetch_one(UserId::one()).await.ok().unwrap().email, None);
+  }
+
+  #[tokio::test]
+  async fn it_should_be_mark_a_login_another_provider_already_signed_in_with() {
+    let repo = Arc::new(InMemoryRepository::_new());
+    let _ = execute(repo.clone(), Request::new("john", None, "avatar_url")).await;
+
+    let google = Request { id: i64::from(UserId::two()), provider: "Google".to_string(), ..Request::new("John", None, "avatar_url") };
+    let res = execute(repo.clone(), google).await;
+
+    match res {
+      Ok(user) => assert_eq!(user.login, "John-google".to_string()),
+      _ => unreachable!(),
+    }
+    assert_eq!(repo.fetch_one(UserId::one()).await.ok().unwrap().login, "john".to_string());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_keep_the_marked_login_on_the_next_sign_in() {
+    let repo = Arc::new(InMemoryRepository::_new());
+    let _ = execute(repo.clone(), Request::new("john", None, "avatar_url")).await;
+    let google = || Request { id: i64::from(UserId::two()), provider: "google".to_string(), ..Request::new("john", None, "avatar_url") };
+    let _ = execute(repo.clone(), google()).await;
+
+    let res = execute(repo, google()).await;
+
+    match res {
+      Ok(user) => assert_eq!(user.login, "john-google".to_string()),
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_add_the_user_id_when_the_marked_login_is_taken_too() {
+    let repo = Arc::new(InMemoryRepository::_new());
+    let _ = execute(repo.clone(), Request::new("john-google", None, "avatar_url")).await;
+    let _ = execute(repo.clone(), Request { id: 4000, ..Request::new("john", None, "avatar_url") }).await;
+
+    let res = execute(repo, Request { id: i64::from(UserId::two()), provider: "google".to_string(), ..Request::new("john", None, "avatar_url") }).await;
+
+    match res {
+      Ok(user) => assert_eq!(user.login, format!("john-google-{}", i64::from(UserId::two()))),
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_a_bad_request_without_a_login() {
+    let repo = Arc::new(InMemoryRepository::_new());
+
+    match execute(repo, Request::new("", Some("kent back"), "avatar_url")).await {
+      Err(Error::BadRequest) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_error_when_the_repo_fails() {
+    let repo = Arc::new(InMemoryRepository::_new().with_error());
+
+    match execute(repo, Request::new("kent-back", None, "avatar_url")).await {
+      Err(Error::Unknown) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  impl Request {
+    fn new(login: &str, name: Option<&str>, avatar_url: &str) -> Self {
+      Self {
+        id: i64::from(UserId::one()),
+        provider: "github".to_string(),
+        login: login.to_string(),
+        name: name.map(str::to_string),
+        avatar_url: avatar_url.to_string(),
+        email: None,
+      }
+    }
+  }
+}
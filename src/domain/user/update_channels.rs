@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::repositories::user::{Repository, UpdateError};
+use crate::domain::{auth::{entity::Principal, role::USER_WRITE}, user::entity::{ChannelError, ContactChannel, ContactChannels, UserId}};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct Request {
+  /// defaults to the caller, somebody else takes `user:write`
+  pub id: Option<i64>,
+  /// the complete list, channels left out are removed
+  pub channels: Vec<ContactChannel>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+  BadRequest,
+  Validation(Vec<ChannelError>),
+  Forbidden,
+  /// the database is unreachable, the request may be retried
+  Unavailable,
+  Unknown,
+}
+
+pub async fn execute(repo: Arc<dyn Repository>, principal: Principal, req: Request) -> Result<ContactChannels, Error> {
+  let id = req.id.unwrap_or(principal.user_id);
+  if !principal.can_act_for(id, USER_WRITE) {
+    return Err(Error::Forbidden);
+  }
+
+  let id = UserId::try_from(id).map_err(|_| Error::BadRequest)?;
+  let channels = ContactChannels::try_from(req.channels).map_err(Error::Validation)?;
+
+  match repo.update_channels(id, channels).await {
+    Ok(user) => Ok(user.channels),
+    Err(UpdateError::Unavailable) => Err(Error::Unavailable),
+    Err(UpdateError::Unknown(_)) => Err(Error::Unknown),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{repositories::user::InMemoryRepository, domain::user::entity::{ChannelKind, UserAvatar, UserLogin, UserName}};
+
+  use super::*;
+
+  fn principal(id: UserId, permissions: &[&str]) -> Principal {
+    Principal { user_id: i64::from(id), permissions: permissions.iter().map(|p| p.to_string()).collect() }
+  }
+
+  fn channel(kind: ChannelKind, value: &str, visible: bool) -> ContactChannel {
+    ContactChannel { kind, value: value.to_string(), visible }
+  }
+
+  async fn repo() -> Arc<InMemoryRepository> {
+    let repo = Arc::new(InMemoryRepository::_new());
+    let _ = repo.upsert_from_provider(UserId::one(), UserLogin::kent_back(), UserName::kent_back(), UserAvatar::user(), None).await;
+
+    repo
+  }
+
+  #[tokio::test]
+  async fn it_should_be_replace_the_channels() {
+    let repo = repo().await;
+    let _ = execute(repo.clone(), principal(UserId::one(), &[]), Request { id: None, channels: vec![channel(ChannelKind::Phone, "010-1234-5678", false)] }).await;
+
+    let res = execute(repo.clone(), principal(UserId::one(), &[]), Request { id: None, channels: vec![channel(ChannelKind::Twitter, "@kent", true)] }).await;
+
+    assert_eq!(res.ok().unwrap().into_inner(), vec![channel(ChannelKind::Twitter, "kent", true)]);
+    assert_eq!(repo.fetch_one(UserId::one()).await.ok().unwrap().channels.into_inner(), vec![channel(ChannelKind::Twitter, "kent", true)]);
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_the_validation_errors() {
+    let req = Request { id: None, channels: vec![channel(ChannelKind::Blog, "kent.dev", true)] };
+
+    match execute(repo().await, principal(UserId::one(), &[]), req).await {
+      Err(Error::Validation(errors)) => assert_eq!(errors.iter().map(ChannelError::kind).collect::<Vec<_>>(), vec![ChannelKind::Blog]),
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_a_forbidden_error_for_another_user() {
+    let req = Request { id: Some(i64::from(UserId::one())), channels: vec![] };
+
+    match execute(repo().await, principal(UserId::two(), &[]), req).await {
+      Err(Error::Forbidden) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_error_when_the_repo_fails() {
+    let repo = Arc::new(InMemoryRepository::_new().with_error());
+
+    match execute(repo, principal(UserId::one(), &[]), Request { id: None, channels: vec![] }).await {
+      Err(Error::Unknown) => {},
+      _ => unreachable!(),
+    }
+  }
+}
@@ -1,28 +1,245 @@
 use std::sync::Arc;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::repositories::user::Repository;
-use crate::domain::user::entity::{UserId, UserName, UserAvatar};
+use crate::repositories::{email_verification, user::{Repository, UpdateError}};
+use crate::domain::{auth::{entity::Principal, role::USER_WRITE}, career::entity::FieldError, user::{entity::{UserId, UserName, UserAvatar, UserEmail}, verify_email::{self, SendError}}};
+use crate::infrastructure::{mailer::Mailer, settings::MailSettings};
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all="camelCase")]
 pub struct Request {
-  pub id: i64,
+  /// defaults to the caller, somebody else takes `user:write`
+  pub id: Option<i64>,
   pub name: String,
   pub avatar_url: String,
+  /// a new address is only mailed a verification link, see `verify_email`
+  pub email: Option<String>,
 }
 
-pub async fn execute(repo: Arc<dyn Repository>, req: Request) -> Result<(), ()> {
-  match (
-    UserId::try_from(req.id),
-    UserName::try_from(req.name),
-    UserAvatar::try_from(req.avatar_url)
-  ) {
-    (Ok(id), Ok(name), Ok(avatar_url)) => match repo.update(id, name, avatar_url).await {
-      Ok(res) => Ok(()),
-      Err(_) => todo!(),
+#[derive(Debug, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct Response {
+  /// the address waiting for its link to be opened
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub pending_email: Option<String>,
+}
+
+/// What sending a verification link takes.
+pub struct EmailChange<'a> {
+  pub verifications: Arc<dyn email_verification::Repository>,
+  pub mailer: Arc<dyn Mailer>,
+  pub settings: &'a MailSettings,
+}
+
+#[derive(Debug)]
+pub enum Error {
+  BadRequest,
+  /// one entry per field that did not validate
+  Validation(Vec<FieldError>),
+  Forbidden,
+  /// the database is unreachable, the request may be retried
+  Unavailable,
+  Unknown,
+}
+
+pub async fn execute(repo: Arc<dyn Repository>, email_change: EmailChange<'_>, principal: Principal, req: Request) -> Result<Response, Error> {
+  let id = req.id.unwrap_or(principal.user_id);
+  if !principal.can_act_for(id, USER_WRITE) {
+    return Err(Error::Forbidden);
+  }
+
+  // moderators edit profiles, but only the owner can prove a new address
+  if req.email.is_some() && id != principal.user_id {
+    return Err(Error::Forbidden);
+  }
+  let id = UserId::try_from(id).map_err(|_| Error::BadRequest)?;
+
+  let name = UserName::try_from(req.name);
+  let avatar_url = UserAvatar::try_from(req.avatar_url);
+  let email = req.email.map(UserEmail::try_from).transpose();
+  let errors = [
+    name.is_err().then(|| FieldError { field: "name", message: "Name is empty.".to_string() }),
+    avatar_url.is_err().then(|| FieldError { field: "avatarUrl", message: "Avatar url is empty.".to_string() }),
+    email.as_ref().err().map(|e| FieldError { field: "email", message: e.to_string() }),
+  ].into_iter().flatten().collect::<Vec<_>>();
+
+  let (name, avatar_url, email) = match (name, avatar_url, email) {
+    (Ok(name), Ok(avatar_url), Ok(email)) => (name, avatar_url, email),
+    _ => return Err(Error::Validation(errors)),
+  };
+
+  let user = match repo.update(id, name, avatar_url).await {
+    Ok(user) => user,
+    Err(UpdateError::Unavailable) => return Err(Error::Unavailable),
+    Err(UpdateError::Unknown(_)) => return Err(Error::Unknown),
+  };
+
+  let pending_email = match email {
+    Some(email) if user.email != Some(String::from(email.clone())) => {
+      let pending = String::from(email.clone());
+      match verify_email::send(email_change.verifications, email_change.mailer, email_change.settings, id, email).await {
+        Ok(_) => Some(pending),
+        Err(SendError::Unavailable) => return Err(Error::Unavailable),
+        Err(SendError::Unknown) => return Err(Error::Unknown),
+      }
     },
-    _ =>  todo!()
+    _ => None,
+  };
+
+  Ok(Response { pending_email })
+}
+#[cfg(test)]
+mod tests {
+  use crate::{
+    domain::user::entity::{UserLogin, UserAvatar},
+    infrastructure::{mailer::InMemoryMailer, settings::Settings},
+    repositories::{user::InMemoryRepository, email_verification::InMemoryRepository as InMemoryVerifications},
+  };
+
+  use super::*;
+
+  fn principal(id: UserId, permissions: &[&str]) -> Principal {
+    Principal { user_id: i64::from(id), permissions: permissions.iter().map(|p| p.to_string()).collect() }
+  }
+
+  async fn update(repo: Arc<InMemoryRepository>, mailer: Arc<InMemoryMailer>, principal: Principal, req: Request) -> Result<Response, Error> {
+    let settings = Settings::test();
+    let email_change = EmailChange {
+      verifications: Arc::new(InMemoryVerifications::new()),
+      mailer,
+      settings: &settings.mail,
+    };
+
+    execute(repo, email_change, principal, req).await
+  }
+
+  async fn repo() -> Arc<InMemoryRepository> {
+    let repo = Arc::new(InMemoryRepository::_new());
+    let _ = repo.upsert_from_provider(UserId::one(), UserLogin::kent_back(), UserName::kent_back(), UserAvatar::user(), None).await;
+    let _ = repo.upsert_from_provider(UserId::two(), UserLogin::no_name(), UserName::kent_back(), UserAvatar::user(), None).await;
+
+    repo
+  }
+
+  #[tokio::test]
+  async fn it_should_be_update_the_user() {
+    let repo = repo().await;
+
+    let req = Request::new(Some(UserId::one()), "kent".to_string());
+
+    let res = update(repo.clone(), Arc::new(InMemoryMailer::new()), principal(UserId::one(), &[]), req).await;
+
+    assert!(res.is_ok());
+    match repo.fetch_one(UserId::one()).await {
+      Ok(user) => assert_eq!(user.name, "kent".to_string()),
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_update_the_caller_when_no_id_is_given() {
+    let repo = repo().await;
+
+    let res = update(repo.clone(), Arc::new(InMemoryMailer::new()), principal(UserId::two(), &[]), Request::new(None, "kent".to_string())).await;
+
+    assert!(res.is_ok());
+    assert_eq!(repo.fetch_one(UserId::two()).await.ok().unwrap().name, "kent".to_string());
+    assert_eq!(repo.fetch_one(UserId::one()).await.ok().unwrap().name, String::from(UserName::kent_back()));
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_a_forbidden_error_for_another_user() {
+    let repo = repo().await;
+
+    let res = update(repo.clone(), Arc::new(InMemoryMailer::new()), principal(UserId::two(), &[]), Request::new(Some(UserId::one()), "kent".to_string())).await;
+
+    match res {
+      Err(Error::Forbidden) => {},
+      _ => unreachable!(),
+    }
+    assert_eq!(repo.fetch_one(UserId::one()).await.ok().unwrap().name, String::from(UserName::kent_back()));
+  }
+
+  #[tokio::test]
+  async fn it_should_be_let_a_moderator_update_another_user() {
+    let repo = repo().await;
+
+    let res = update(repo.clone(), Arc::new(InMemoryMailer::new()), principal(UserId::two(), &[USER_WRITE]), Request::new(Some(UserId::one()), "kent".to_string())).await;
+
+    assert!(res.is_ok());
+    assert_eq!(repo.fetch_one(UserId::one()).await.ok().unwrap().name, "kent".to_string());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_every_invalid_field() {
+    let req = Request { avatar_url: String::new(), email: Some("kent".to_string()), ..Request::new(None, String::new()) };
+
+    let res = update(repo().await, Arc::new(InMemoryMailer::new()), principal(UserId::one(), &[]), req).await;
+
+    match res {
+      Err(Error::Validation(errors)) => assert_eq!(errors.iter().map(|e| e.field).collect::<Vec<_>>(), vec!["name", "avatarUrl", "email"]),
+      _ => unreachable!(),
+    }
   }
-}
\ No newline at end of file
+
+  #[tokio::test]
+  async fn it_should_be_mail_a_link_instead_of_changing_the_email() {
+    let repo = repo().await;
+    let mailer = Arc::new(InMemoryMailer::new());
+    let req = Request { email: Some("kent@gmail.com".to_string()), ..Request::new(None, "kent".to_string()) };
+
+    let res = update(repo.clone(), mailer.clone(), principal(UserId::one(), &[]), req).await;
+
+    match res {
+      Ok(res) => assert_eq!(res.pending_email, Some("kent@gmail.com".to_string())),
+      _ => unreachable!(),
+    }
+    assert_eq!(mailer.sent.lock().unwrap()[0].to, "kent@gmail.com".to_string());
+    assert_eq!(repo.fetch_one(UserId::one()).await.ok().unwrap().email, None);
+  }
+
+  #[tokio::test]
+  async fn it_should_be_not_mail_the_current_email() {
+    let repo = repo().await;
+    let _ = repo.update_email(UserId::one(), UserEmail::gmail()).await;
+    let mailer = Arc::new(InMemoryMailer::new());
+    let req = Request { email: Some(String::from(UserEmail::gmail())), ..Request::new(None, "kent".to_string()) };
+
+    let res = update(repo, mailer.clone(), principal(UserId::one(), &[]), req).await;
+
+    assert_eq!(res.ok().unwrap().pending_email, None);
+    assert!(mailer.sent.lock().unwrap().is_empty());
+  }
+
+  #[tokio::test]
+  async fn it_should_be_reject_an_invalid_email() {
+    let req = Request { email: Some("kent".to_string()), ..Request::new(None, "kent".to_string()) };
+
+    match update(repo().await, Arc::new(InMemoryMailer::new()), principal(UserId::one(), &[]), req).await {
+      Err(Error::Validation(errors)) => assert_eq!(errors, vec![FieldError { field: "email", message: "Missing separator character '@'.".to_string() }]),
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_not_let_a_moderator_change_the_email() {
+    let req = Request { email: Some("kent@gmail.com".to_string()), ..Request::new(Some(UserId::one()), "kent".to_string()) };
+
+    match update(repo().await, Arc::new(InMemoryMailer::new()), principal(UserId::two(), &[USER_WRITE]), req).await {
+      Err(Error::Forbidden) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  impl Request {
+    fn new(id: Option<UserId>, name: String) -> Self {
+      Self {
+        id: id.map(i64::from),
+        name,
+        avatar_url: String::from(UserAvatar::user()),
+        email: None,
+      }
+    }
+  }
+}
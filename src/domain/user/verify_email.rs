@@ -0,0 +1,203 @@
+use std::sync::Arc;
+
+use chrono::{Duration, FixedOffset, Utc};
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+  domain::user::entity::{UserEmail, UserId},
+  infrastructure::{mailer::{Mail, Mailer}, settings::MailSettings},
+  repositories::{user::{self, UpdateError}, email_verification::{self, InsertError, TakeError}},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+  /// the token from the mailed link
+  pub token: String,
+}
+
+#[derive(Debug)]
+pub enum Error {
+  /// unknown, already used, replaced by a newer change or expired
+  InvalidToken,
+  Unavailable,
+  Unknown,
+}
+
+#[derive(Debug)]
+pub enum SendError {
+  /// the database or the mail transport is unreachable, the change may be retried
+  Unavailable,
+  Unknown,
+}
+
+fn hash_token(token: &str) -> String {
+  format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn generate_token() -> String {
+  let mut bytes = [0u8; 32];
+  rand::thread_rng().fill_bytes(&mut bytes);
+
+  base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+/// Mails a one-time link to `email`; the address only replaces the current one once the link is used.
+pub async fn send(
+  verifications: Arc<dyn email_verification::Repository>,
+  mailer: Arc<dyn Mailer>,
+  settings: &MailSettings,
+  user_id: UserId,
+  email: UserEmail,
+) -> Result<(), SendError> {
+  let token = generate_token();
+  let expires_at = Utc::now().with_timezone(&FixedOffset::east(9 * 3600)) + Duration::seconds(settings.verification_ttl);
+  let to = String::from(email.clone());
+
+  match verifications.insert(user_id, email, hash_token(&token), expires_at).await {
+    Ok(_) => {},
+    Err(InsertError::Unavailable) => return Err(SendError::Unavailable),
+    Err(InsertError::Unknown) => return Err(SendError::Unknown),
+  }
+
+  let mail = Mail {
+    to,
+    subject: "Confirm your email address".to_string(),
+    body: format!(
+      "Open the link below to use this address for your decafo account.\n\n{}?token={}\n\nThe link expires in {} hours. If you did not ask for this, ignore this mail.",
+      settings.verify_url, token, settings.verification_ttl / 3600,
+    ),
+  };
+
+  mailer.send(mail).await.map_err(|_| SendError::Unavailable)
+}
+
+/// Applies the email change the token was mailed for.
+pub async fn execute(
+  users: Arc<dyn user::Repository>,
+  verifications: Arc<dyn email_verification::Repository>,
+  req: Request,
+) -> Result<(), Error> {
+  let verification = match verifications.take(&hash_token(&req.token)).await {
+    Ok(verification) if verification.expires_at > Utc::now() => verification,
+    Ok(_) | Err(TakeError::NotFound) => return Err(Error::InvalidToken),
+    Err(TakeError::Unavailable) => return Err(Error::Unavailable),
+    Err(TakeError::Unknown) => return Err(Error::Unknown),
+  };
+
+  let (user_id, email) = match (UserId::try_from(verification.user_id), UserEmail::try_from(verification.email)) {
+    (Ok(user_id), Ok(email)) => (user_id, email),
+    _ => return Err(Error::Unknown),
+  };
+
+  match users.update_email(user_id, email).await {
+    Ok(_) => Ok(()),
+    Err(UpdateError::Unavailable) => Err(Error::Unavailable),
+    Err(UpdateError::Unknown(_)) => Err(Error::Unknown),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    domain::user::entity::{UserAvatar, UserLogin, UserName},
+    infrastructure::{mailer::InMemoryMailer, settings::Settings},
+    repositories::{user::{InMemoryRepository, Repository as _}, email_verification::{InMemoryRepository as InMemoryVerifications, Repository as _}},
+  };
+
+  use super::*;
+
+  async fn users() -> Arc<InMemoryRepository> {
+    let users = Arc::new(InMemoryRepository::_new());
+    let _ = users.upsert_from_provider(UserId::one(), UserLogin::kent_back(), UserName::kent_back(), UserAvatar::user(), None).await;
+
+    users
+  }
+
+  fn last_token(mailer: &InMemoryMailer) -> String {
+    let sent = mailer.sent.lock().unwrap();
+    let body = &sent.last().unwrap().body;
+
+    body.split("?token=").nth(1).unwrap().split_whitespace().next().unwrap().to_string()
+  }
+
+  #[tokio::test]
+  async fn it_should_be_change_the_email_once_the_link_is_used() {
+    let (users, verifications, mailer) = (users().await, Arc::new(InMemoryVerifications::new()), Arc::new(InMemoryMailer::new()));
+
+    assert!(send(verifications.clone(), mailer.clone(), &Settings::test().mail, UserId::one(), UserEmail::gmail()).await.is_ok());
+    assert_eq!(mailer.sent.lock().unwrap()[0].to, String::from(UserEmail::gmail()));
+    assert_eq!(users.fetch_one(UserId::one()).await.ok().unwrap().email, None);
+
+    let res = execute(users.clone(), verifications, Request { token: last_token(&mailer) }).await;
+
+    assert!(res.is_ok());
+    assert_eq!(users.fetch_one(UserId::one()).await.ok().unwrap().email, Some(String::from(UserEmail::gmail())));
+  }
+
+  #[tokio::test]
+  async fn it_should_be_use_a_link_only_once() {
+    let (users, verifications, mailer) = (users().await, Arc::new(InMemoryVerifications::new()), Arc::new(InMemoryMailer::new()));
+    let _ = send(verifications.clone(), mailer.clone(), &Settings::test().mail, UserId::one(), UserEmail::gmail()).await;
+    let token = last_token(&mailer);
+
+    let _ = execute(users.clone(), verifications.clone(), Request { token: token.clone() }).await;
+    let res = execute(users, verifications, Request { token }).await;
+
+    match res {
+      Err(Error::InvalidToken) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_replace_an_earlier_link() {
+    let (users, verifications, mailer) = (users().await, Arc::new(InMemoryVerifications::new()), Arc::new(InMemoryMailer::new()));
+    let _ = send(verifications.clone(), mailer.clone(), &Settings::test().mail, UserId::one(), UserEmail::gmail()).await;
+    let first = last_token(&mailer);
+    let _ = send(verifications.clone(), mailer.clone(), &Settings::test().mail, UserId::one(), UserEmail::gmail()).await;
+
+    let res = execute(users, verifications, Request { token: first }).await;
+
+    match res {
+      Err(Error::InvalidToken) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_reject_an_expired_link() {
+    let (users, verifications) = (users().await, Arc::new(InMemoryVerifications::new()));
+    let expired = Utc::now().with_timezone(&FixedOffset::east(9 * 3600)) - Duration::seconds(1);
+    let _ = verifications.insert(UserId::one(), UserEmail::gmail(), hash_token("token"), expired).await;
+
+    let res = execute(users.clone(), verifications, Request { token: "token".to_string() }).await;
+
+    match res {
+      Err(Error::InvalidToken) => {},
+      _ => unreachable!(),
+    }
+    assert_eq!(users.fetch_one(UserId::one()).await.ok().unwrap().email, None);
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_unavailable_when_the_mail_cannot_be_sent() {
+    let mailer = Arc::new(InMemoryMailer::new().with_error());
+
+    match send(Arc::new(InMemoryVerifications::new()), mailer, &Settings::test().mail, UserId::one(), UserEmail::gmail()).await {
+      Err(SendError::Unavailable) => {},
+      _ => unreachable!(),
+    }
+  }
+
+  #[tokio::test]
+  async fn it_should_be_return_an_unknown_error_when_the_repo_fails() {
+    let verifications = Arc::new(InMemoryVerifications::new().with_error());
+
+    match send(verifications, Arc::new(InMemoryMailer::new()), &Settings::test().mail, UserId::one(), UserEmail::gmail()).await {
+      Err(SendError::Unknown) => {},
+      _ => unreachable!(),
+    }
+  }
+}
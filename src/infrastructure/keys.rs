@@ -0,0 +1,388 @@
+use std::fmt::{Display, Formatter};
+
+use jsonwebtoken::{
+  Algorithm, DecodingKey, EncodingKey, Header, TokenData, Validation,
+  errors::ErrorKind,
+  jwk::{AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters, Jwk, JwkSet, PublicKeyUse, RSAKeyParameters},
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::settings::{JwtSettings, KeyMaterial, KeySettings};
+
+// DER encoded object identifiers found in SubjectPublicKeyInfo
+const OID_RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+const OID_P256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const OID_P384: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x22];
+
+#[derive(Debug, PartialEq)]
+pub struct Error {
+  pub kid: String,
+  pub reason: String,
+}
+
+impl std::error::Error for Error {}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "invalid jwt key `{}`: {}", self.kid, self.reason)
+  }
+}
+
+struct Key {
+  kid: String,
+  algorithm: Algorithm,
+  encoding: Option<EncodingKey>,
+  decoding: DecodingKey,
+  jwk: Option<Jwk>,
+}
+
+/// Signs tokens with the configured signing key and verifies them against every active key.
+pub struct KeyStore {
+  signing_kid: String,
+  keys: Vec<Key>,
+}
+
+// key material stays out of logs
+impl std::fmt::Debug for KeyStore {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("KeyStore")
+      .field("signing_kid", &self.signing_kid)
+      .field("kids", &self.keys.iter().map(|key| key.kid.as_str()).collect::<Vec<&str>>())
+      .finish()
+  }
+}
+
+impl KeyStore {
+  pub fn new(settings: &JwtSettings) -> Result<Self, Error> {
+    let keys = settings.keys.iter()
+      .map(|key| load_key(key).map_err(|reason| Error { kid: key.kid.clone(), reason }))
+      .collect::<Result<Vec<Key>, Error>>()?;
+
+    match keys.iter().find(|key| key.kid == settings.signing_kid) {
+      Some(key) if key.encoding.is_some() => Ok(Self {
+        signing_kid: settings.signing_kid.clone(),
+        keys,
+      }),
+      _ => Err(Error {
+        kid: settings.signing_kid.clone(),
+        reason: "signing key is not configured with private material".to_string(),
+      }),
+    }
+  }
+
+  pub fn encode<T: Serialize>(&self, claims: &T) -> jsonwebtoken::errors::Result<String> {
+    let key = self.signing_key();
+    let header = Header {
+      kid: Some(key.kid.clone()),
+      ..Header::new(key.algorithm)
+    };
+
+    match &key.encoding {
+      Some(encoding) => jsonwebtoken::encode(&header, claims, encoding),
+      None => Err(ErrorKind::InvalidKeyFormat.into()),
+    }
+  }
+
+  /// Tokens without a `kid` header are checked against the signing key.
+  pub fn decode<T: DeserializeOwned>(&self, token: &str) -> jsonwebtoken::errors::Result<TokenData<T>> {
+    let header = jsonwebtoken::decode_header(token)?;
+    let key = match &header.kid {
+      Some(kid) => self.keys.iter().find(|key| &key.kid == kid),
+      None => Some(self.signing_key()),
+    };
+
+    match key {
+      Some(key) if key.algorithm == header.alg => {
+        jsonwebtoken::decode::<T>(token, &key.decoding, &Validation::new(key.algorithm))
+      },
+      Some(_) => Err(ErrorKind::InvalidAlgorithm.into()),
+      None => Err(ErrorKind::InvalidToken.into()),
+    }
+  }
+
+  /// Public half of every asymmetric key, HMAC secrets are never published.
+  pub fn jwks(&self) -> JwkSet {
+    JwkSet {
+      keys: self.keys.iter().filter_map(|key| key.jwk.clone()).collect(),
+    }
+  }
+
+  fn signing_key(&self) -> &Key {
+    self.keys.iter()
+      .find(|key| key.kid == self.signing_kid)
+      .expect("signing key is checked in KeyStore::new")
+  }
+}
+
+fn load_key(settings: &KeySettings) -> Result<Key, String> {
+  let algorithm = settings.algorithm;
+
+  match &settings.material {
+    KeyMaterial::Secret(secret) => Ok(Key {
+      kid: settings.kid.clone(),
+      algorithm,
+      encoding: Some(EncodingKey::from_secret(secret.as_bytes())),
+      decoding: DecodingKey::from_secret(secret.as_bytes()),
+      jwk: None,
+    }),
+    KeyMaterial::Pem { private_key, public_key } => {
+      let (encoding, decoding, parameters) = match algorithm {
+        Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512
+        | Algorithm::PS256 | Algorithm::PS384 | Algorithm::PS512 => (
+          private_key.as_ref().map(|pem| EncodingKey::from_rsa_pem(pem.as_bytes())).transpose(),
+          DecodingKey::from_rsa_pem(public_key.as_bytes()),
+          rsa_parameters(public_key)?,
+        ),
+        Algorithm::ES256 | Algorithm::ES384 => (
+          private_key.as_ref().map(|pem| EncodingKey::from_ec_pem(pem.as_bytes())).transpose(),
+          DecodingKey::from_ec_pem(public_key.as_bytes()),
+          ec_parameters(public_key, algorithm)?,
+        ),
+        _ => return Err(format!("{:?} keys can not be loaded from pem", algorithm)),
+      };
+
+      Ok(Key {
+        kid: settings.kid.clone(),
+        algorithm,
+        encoding: encoding.map_err(|e| format!("private_key: {}", e))?,
+        decoding: decoding.map_err(|e| format!("public_key: {}", e))?,
+        jwk: Some(Jwk {
+          common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            algorithm: Some(algorithm),
+            key_id: Some(settings.kid.clone()),
+            ..Default::default()
+          },
+          algorithm: parameters,
+        }),
+      })
+    },
+  }
+}
+
+fn rsa_parameters(pem: &str) -> Result<AlgorithmParameters, String> {
+  let (label, der) = pem_to_der(pem)?;
+  let rsa_key = match label.as_str() {
+    "RSA PUBLIC KEY" => der,
+    "PUBLIC KEY" => {
+      let (oid, key) = subject_public_key_info(&der)?;
+      if oid.first() != Some(&OID_RSA_ENCRYPTION) {
+        return Err("public_key is not an RSA key".to_string());
+      }
+      key
+    },
+    other => return Err(format!("unexpected pem block `{}` for public_key", other)),
+  };
+
+  let (modulus, exponent) = match read_tlv(&rsa_key) {
+    Some((0x30, sequence, _)) => {
+      let (modulus, rest) = read_integer(sequence)?;
+      let (exponent, _) = read_integer(rest)?;
+      (modulus, exponent)
+    },
+    _ => return Err("malformed RSA public key".to_string()),
+  };
+
+  Ok(AlgorithmParameters::RSA(RSAKeyParameters {
+    n: base64::encode_config(modulus, base64::URL_SAFE_NO_PAD),
+    e: base64::encode_config(exponent, base64::URL_SAFE_NO_PAD),
+    ..Default::default()
+  }))
+}
+
+fn ec_parameters(pem: &str, algorithm: Algorithm) -> Result<AlgorithmParameters, String> {
+  let (_, der) = pem_to_der(pem)?;
+  let (oids, point) = subject_public_key_info(&der)?;
+
+  let (curve, size) = match (oids.as_slice(), algorithm) {
+    ([OID_EC_PUBLIC_KEY, OID_P256], Algorithm::ES256) => (EllipticCurve::P256, 32),
+    ([OID_EC_PUBLIC_KEY, OID_P384], Algorithm::ES384) => (EllipticCurve::P384, 48),
+    _ => return Err(format!("public_key does not match {:?}", algorithm)),
+  };
+
+  // uncompressed point: 0x04 || x || y
+  if point.len() != 1 + 2 * size || point[0] != 0x04 {
+    return Err("public_key is not an uncompressed EC point".to_string());
+  }
+
+  Ok(AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+    curve,
+    x: base64::encode_config(&point[1..=size], base64::URL_SAFE_NO_PAD),
+    y: base64::encode_config(&point[1 + size..], base64::URL_SAFE_NO_PAD),
+    ..Default::default()
+  }))
+}
+
+fn pem_to_der(pem: &str) -> Result<(String, Vec<u8>), String> {
+  let label = pem.lines()
+    .find_map(|line| line.trim().strip_prefix("-----BEGIN ").and_then(|l| l.strip_suffix("-----")))
+    .ok_or_else(|| "missing pem header".to_string())?;
+  let body = pem.lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty() && !line.starts_with("-----"))
+    .collect::<String>();
+
+  base64::decode(body)
+    .map(|der| (label.to_string(), der))
+    .map_err(|e| e.to_string())
+}
+
+// SEQUENCE { SEQUENCE { OID algorithm, parameters }, BIT STRING key }
+fn subject_public_key_info(der: &[u8]) -> Result<(Vec<&[u8]>, Vec<u8>), String> {
+  let malformed = || "malformed SubjectPublicKeyInfo".to_string();
+
+  let (_, spki, _) = read_tlv(der).filter(|(tag, _, _)| *tag == 0x30).ok_or_else(malformed)?;
+  let (_, mut algorithm, rest) = read_tlv(spki).filter(|(tag, _, _)| *tag == 0x30).ok_or_else(malformed)?;
+  let (_, bits, _) = read_tlv(rest).filter(|(tag, _, _)| *tag == 0x03).ok_or_else(malformed)?;
+
+  let mut oids = vec![];
+  while let Some((tag, value, rest)) = read_tlv(algorithm) {
+    if tag == 0x06 {
+      oids.push(value);
+    }
+    algorithm = rest;
+  }
+
+  match bits.split_first() {
+    Some((0, key)) => Ok((oids, key.to_vec())),
+    _ => Err(malformed()),
+  }
+}
+
+fn read_integer(der: &[u8]) -> Result<(&[u8], &[u8]), String> {
+  match read_tlv(der) {
+    Some((0x02, value, rest)) => {
+      let start = value.iter().position(|b| *b != 0).unwrap_or(value.len() - 1);
+      Ok((&value[start..], rest))
+    },
+    _ => Err("expected DER integer".to_string()),
+  }
+}
+
+fn read_tlv(der: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+  let tag = *der.first()?;
+  let first = *der.get(1)? as usize;
+
+  let (length, header) = if first < 0x80 {
+    (first, 2)
+  } else {
+    let count = first & 0x7f;
+    if count == 0 || count > 4 {
+      return None;
+    }
+    let length = der.get(2..2 + count)?.iter().fold(0, |acc, b| (acc << 8) | *b as usize);
+    (length, 2 + count)
+  };
+
+  let value = der.get(header..header + length)?;
+  Some((tag, value, &der[header + length..]))
+}
+
+#[cfg(test)]
+mod tests {
+  use serde::Deserialize;
+
+  use super::*;
+
+  const RSA_PRIVATE: &str = include_str!("../../tests/fixtures/jwt/rsa_private.pem");
+  const RSA_PUBLIC: &str = include_str!("../../tests/fixtures/jwt/rsa_public.pem");
+  const EC_PRIVATE: &str = include_str!("../../tests/fixtures/jwt/ec_private.pem");
+  const EC_PUBLIC: &str = include_str!("../../tests/fixtures/jwt/ec_public.pem");
+
+  #[derive(Debug, PartialEq, Serialize, Deserialize)]
+  struct TestClaims {
+    sub: String,
+    exp: u64,
+  }
+
+  fn claims() -> TestClaims {
+    TestClaims { sub: "kent-back".to_string(), exp: 32_503_680_000 }
+  }
+
+  fn key(kid: &str, algorithm: Algorithm, material: KeyMaterial) -> KeySettings {
+    KeySettings { kid: kid.to_string(), algorithm, material }
+  }
+
+  fn jwt(signing_kid: &str, keys: Vec<KeySettings>) -> JwtSettings {
+    JwtSettings { signing_kid: signing_kid.to_string(), keys, access_token_ttl: 900, refresh_token_ttl: 3600 }
+  }
+
+  fn pem(private_key: Option<&str>, public_key: &str) -> KeyMaterial {
+    KeyMaterial::Pem { private_key: private_key.map(str::to_string), public_key: public_key.to_string() }
+  }
+
+  #[test]
+  fn it_should_be_stamp_the_kid_and_verify_the_token() {
+    let store = KeyStore::new(&jwt("rsa", vec![key("rsa", Algorithm::RS256, pem(Some(RSA_PRIVATE), RSA_PUBLIC))])).unwrap();
+
+    let token = store.encode(&claims()).unwrap();
+    let header = jsonwebtoken::decode_header(&token).unwrap();
+
+    assert_eq!(header.kid, Some("rsa".to_string()));
+    assert_eq!(header.alg, Algorithm::RS256);
+    assert_eq!(store.decode::<TestClaims>(&token).unwrap().claims, claims());
+  }
+
+  #[test]
+  fn it_should_be_verify_tokens_signed_with_a_rotated_key() {
+    let old = KeyStore::new(&jwt("old", vec![key("old", Algorithm::HS256, KeyMaterial::Secret("old secret".to_string()))])).unwrap();
+    let token = old.encode(&claims()).unwrap();
+
+    let store = KeyStore::new(&jwt("ec", vec![
+        key("ec", Algorithm::ES256, pem(Some(EC_PRIVATE), EC_PUBLIC)),
+        key("old", Algorithm::HS256, KeyMaterial::Secret("old secret".to_string())),
+      ])).unwrap();
+
+    assert!(store.decode::<TestClaims>(&token).is_ok());
+    assert!(store.decode::<TestClaims>(&store.encode(&claims()).unwrap()).is_ok());
+  }
+
+  #[test]
+  fn it_should_be_reject_an_unknown_kid() {
+    let other = KeyStore::new(&jwt("other", vec![key("other", Algorithm::HS256, KeyMaterial::Secret("secret".to_string()))])).unwrap();
+    let store = KeyStore::new(&jwt("default", vec![key("default", Algorithm::HS256, KeyMaterial::Secret("secret".to_string()))])).unwrap();
+
+    let token = other.encode(&claims()).unwrap();
+
+    assert!(store.decode::<TestClaims>(&token).is_err());
+  }
+
+  #[test]
+  fn it_should_be_publish_only_public_keys() {
+    let store = KeyStore::new(&jwt("rsa", vec![
+        key("rsa", Algorithm::RS256, pem(Some(RSA_PRIVATE), RSA_PUBLIC)),
+        key("ec", Algorithm::ES256, pem(None, EC_PUBLIC)),
+        key("hmac", Algorithm::HS256, KeyMaterial::Secret("secret".to_string())),
+      ])).unwrap();
+
+    let jwks = store.jwks();
+
+    assert_eq!(jwks.keys.len(), 2);
+    assert!(jwks.find("hmac").is_none());
+    match &jwks.find("ec").unwrap().algorithm {
+      AlgorithmParameters::EllipticCurve(ec) => {
+        assert_eq!(base64::decode_config(&ec.x, base64::URL_SAFE_NO_PAD).unwrap().len(), 32);
+        assert_eq!(base64::decode_config(&ec.y, base64::URL_SAFE_NO_PAD).unwrap().len(), 32);
+      },
+      _ => unreachable!(),
+    }
+
+    // the published modulus and exponent must verify what the private key signed
+    let token = store.encode(&claims()).unwrap();
+    match &jwks.find("rsa").unwrap().algorithm {
+      AlgorithmParameters::RSA(rsa) => {
+        let decoding = DecodingKey::from_rsa_components(&rsa.n, &rsa.e).unwrap();
+        assert!(jsonwebtoken::decode::<TestClaims>(&token, &decoding, &Validation::new(Algorithm::RS256)).is_ok());
+      },
+      _ => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn it_should_be_require_private_material_for_the_signing_key() {
+    let res = KeyStore::new(&jwt("ec", vec![key("ec", Algorithm::ES256, pem(None, EC_PUBLIC))]));
+
+    assert!(res.is_err());
+  }
+}
@@ -0,0 +1,138 @@
+#[cfg(any(test, feature = "test-support"))]
+use std::sync::Mutex;
+
+use std::{path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::middleware::trace_id;
+
+use super::settings::{MailSettings, MailTransport};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mail {
+  pub to: String,
+  pub subject: String,
+  pub body: String,
+}
+
+#[derive(Debug)]
+pub enum MailError {
+  /// the transport refused the mail, sending may be retried
+  Unavailable,
+}
+
+/// Delivers mail on behalf of the use cases. Pick an implementation with `from_settings`.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+  async fn send(&self, mail: Mail) -> Result<(), MailError>;
+}
+
+pub fn from_settings(settings: &MailSettings) -> Arc<dyn Mailer> {
+  match &settings.transport {
+    MailTransport::Log => Arc::new(LogMailer { from: settings.from.clone() }),
+    MailTransport::File(dir) => Arc::new(FileMailer { from: settings.from.clone(), dir: dir.clone() }),
+  }
+}
+
+fn message(from: &str, mail: &Mail) -> String {
+  format!(
+    "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n",
+    from, mail.to, mail.subject, Utc::now().to_rfc2822(), mail.body,
+  )
+}
+
+pub struct LogMailer {
+  from: String,
+}
+
+#[async_trait]
+impl Mailer for LogMailer {
+  async fn send(&self, mail: Mail) -> Result<(), MailError> {
+    log::info!("[mail]\n{}", message(&self.from, &mail));
+
+    Ok(())
+  }
+}
+
+pub struct FileMailer {
+  from: String,
+  dir: PathBuf,
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+  async fn send(&self, mail: Mail) -> Result<(), MailError> {
+    let path = self.dir.join(format!("{}-{}.eml", Utc::now().format("%Y%m%dT%H%M%S%.6f"), mail.to.replace(['/', '\\'], "_")));
+
+    let written = match tokio::fs::create_dir_all(&self.dir).await {
+      Ok(_) => tokio::fs::write(&path, message(&self.from, &mail)).await,
+      Err(e) => Err(e),
+    };
+
+    written.map_err(|e| {
+      log::error!("[{}] {}: {:?}", trace_id::current(), path.display(), e);
+      MailError::Unavailable
+    })
+  }
+}
+
+/// Keeps sent mail for tests to read the links from.
+#[cfg(any(test, feature = "test-support"))]
+pub struct InMemoryMailer {
+  error: bool,
+  pub sent: Mutex<Vec<Mail>>,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl InMemoryMailer {
+  pub fn new() -> Self {
+    Self {
+      error: false,
+      sent: Mutex::new(vec![]),
+    }
+  }
+
+  pub fn with_error(self) -> Self {
+    Self {
+      error: true,
+      ..self
+    }
+  }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+#[async_trait]
+impl Mailer for InMemoryMailer {
+  async fn send(&self, mail: Mail) -> Result<(), MailError> {
+    if self.error {
+      return Err(MailError::Unavailable);
+    }
+
+    self.sent.lock().unwrap().push(mail);
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn it_should_be_write_one_file_per_mail() {
+    let dir = std::env::temp_dir().join(format!("decafo-mail-{}", uuid::Uuid::new_v4().simple()));
+    let mailer = FileMailer { from: "no-reply@decafo.local".to_string(), dir: dir.clone() };
+
+    let res = mailer.send(Mail { to: "kent@gmail.com".to_string(), subject: "hello".to_string(), body: "world".to_string() }).await;
+
+    assert!(res.is_ok());
+    let files = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).collect::<Vec<_>>();
+    assert_eq!(files.len(), 1);
+    let content = std::fs::read_to_string(&files[0]).unwrap();
+    assert!(content.contains("To: kent@gmail.com\r\n"));
+    assert!(content.ends_with("\r\n\r\nworld\r\n"));
+    let _ = std::fs::remove_dir_all(dir);
+  }
+}
@@ -0,0 +1,148 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer, http::header::{AUTHORIZATION, LOCATION}};
+use oauth2::url::Url;
+use rand::RngCore;
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use super::settings::{MockProviderSettings, MockUserSettings};
+
+/// Stands in for GitHub's `/login/oauth/authorize`, `/login/oauth/access_token`, `/user` and `/user/emails`.
+/// Authorization is granted without asking, client credentials are not checked.
+pub struct MockProvider {
+  users: Vec<MockUserSettings>,
+  // code => (login, code_challenge)
+  codes: Mutex<HashMap<String, (String, Option<String>)>>,
+  // access token => login
+  tokens: Mutex<HashMap<String, String>>,
+}
+
+#[derive(Deserialize)]
+pub struct AuthorizeQuery {
+  pub redirect_uri: String,
+  pub state: Option<String>,
+  pub code_challenge: Option<String>,
+  /// which fixture user signs in, the first one by default
+  pub login: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TokenForm {
+  pub code: String,
+  pub code_verifier: Option<String>,
+}
+
+fn random_token() -> String {
+  let mut bytes = [0u8; 20];
+  rand::thread_rng().fill_bytes(&mut bytes);
+
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl MockProvider {
+  pub fn new(users: Vec<MockUserSettings>) -> Self {
+    Self {
+      users,
+      codes: Mutex::new(HashMap::new()),
+      tokens: Mutex::new(HashMap::new()),
+    }
+  }
+
+  pub async fn run(settings: &MockProviderSettings, host: &str) -> std::io::Result<()> {
+    let provider = web::Data::new(Self::new(settings.users.clone()));
+
+    HttpServer::new(move || App::new().app_data(provider.clone()).configure(routes))
+      .bind((host, settings.port))?
+      .run()
+      .await
+  }
+}
+
+pub fn routes(cfg: &mut web::ServiceConfig) {
+  cfg
+    .route("/login/oauth/authorize", web::get().to(authorize))
+    .route("/login/oauth/access_token", web::post().to(access_token))
+    .route("/user", web::get().to(user))
+    .route("/user/emails", web::get().to(emails));
+}
+
+async fn authorize(provider: web::Data<MockProvider>, query: web::Query<AuthorizeQuery>) -> HttpResponse {
+  let user = match &query.login {
+    Some(login) => provider.users.iter().find(|u| &u.login == login),
+    None => provider.users.first(),
+  };
+  let (user, mut redirect) = match (user, Url::parse(&query.redirect_uri)) {
+    (Some(user), Ok(redirect)) => (user, redirect),
+    _ => return HttpResponse::BadRequest().finish(),
+  };
+
+  let code = random_token();
+  provider.codes.lock().unwrap().insert(code.clone(), (user.login.clone(), query.code_challenge.clone()));
+
+  redirect.query_pairs_mut().append_pair("code", &code);
+  if let Some(state) = &query.state {
+    redirect.query_pairs_mut().append_pair("state", state);
+  }
+
+  HttpResponse::Found().insert_header((LOCATION, redirect.to_string())).finish()
+}
+
+async fn access_token(provider: web::Data<MockProvider>, form: web::Form<TokenForm>) -> HttpResponse {
+  let bad_code = || HttpResponse::BadRequest().json(json!({ "error": "bad_verification_code" }));
+
+  let (login, challenge) = match provider.codes.lock().unwrap().remove(&form.code) {
+    Some(grant) => grant,
+    None => return bad_code(),
+  };
+
+  if let Some(challenge) = challenge {
+    let verifier = form.code_verifier.as_deref().unwrap_or_default();
+    let expected = base64::encode_config(Sha256::digest(verifier.as_bytes()), base64::URL_SAFE_NO_PAD);
+    if expected != challenge {
+      return bad_code();
+    }
+  }
+
+  let token = random_token();
+  provider.tokens.lock().unwrap().insert(token.clone(), login);
+
+  HttpResponse::Ok().json(json!({
+    "access_token": token,
+    "token_type": "bearer",
+    "scope": "read:user,user:email",
+  }))
+}
+
+impl MockProvider {
+  /// The fixture user the bearer token was issued to.
+  fn signed_in(&self, req: &HttpRequest) -> Option<&MockUserSettings> {
+    let token = req.headers().get(AUTHORIZATION)
+      .and_then(|value| value.to_str().ok())
+      .and_then(|value| value.strip_prefix("Bearer ").or_else(|| value.strip_prefix("token ")))?;
+
+    let login = self.tokens.lock().unwrap().get(token).cloned()?;
+    self.users.iter().find(|u| u.login == login)
+  }
+}
+
+fn bad_credentials() -> HttpResponse {
+  HttpResponse::Unauthorized().json(json!({ "message": "Bad credentials" }))
+}
+
+async fn user(provider: web::Data<MockProvider>, req: HttpRequest) -> HttpResponse {
+  match provider.signed_in(&req) {
+    Some(user) => HttpResponse::Ok().json(user),
+    None => bad_credentials(),
+  }
+}
+
+async fn emails(provider: web::Data<MockProvider>, req: HttpRequest) -> HttpResponse {
+  match provider.signed_in(&req) {
+    Some(user) => HttpResponse::Ok().json(
+      user.email.iter().map(|email| json!({ "email": email, "primary": true, "verified": true, "visibility": "private" })).collect::<Vec<_>>()
+    ),
+    None => bad_credentials(),
+  }
+}
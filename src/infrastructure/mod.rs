@@ -1,4 +1,8 @@
 mod server;
 pub mod database;
+pub mod settings;
+pub mod keys;
+pub mod mock_provider;
+pub mod mailer;
 
-pub use server::Server;
\ No newline at end of file
+pub use server::{routes, Server};
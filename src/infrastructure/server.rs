@@ -1,50 +1,153 @@
-use std::env;
+use std::{env, sync::Arc};
 
 use actix_cors::Cors;
-use actix_web::{HttpServer, App, middleware::{Logger}, web, HttpRequest};
+use actix_web::{HttpServer, App, guard, middleware::{Logger}, web, HttpRequest};
 use sea_orm::DatabaseConnection;
-use serde::Deserialize;
 
-use crate::{api::{fetch_access_token::fetch_access_token, authorization_code::{authorization_code}, create_career::create_career, fetch_career::fetch_career, user::{update_user, fetch_user}}, middleware::auth_middleware::Authentication};
+use crate::{api::{error::ApiError, fetch_access_token::fetch_access_token, authorization_code::{authorization_code}, create_career::create_career, fetch_career::{fetch_career, fetch_career_by_login}, experience_summary::fetch_experience, resume::export_resume, update_career::update_career, delete_career::delete_career, hide_career::{hide_career, show_career}, user::{update_user, update_user_channels, fetch_user, fetch_user_by_login, list_users, verify_email}, jwks::jwks, refresh_token::refresh_token, sign_out::sign_out, revoke_sessions::revoke_sessions, assign_role::{grant_role, revoke_role}, personal_access_token::{create_token, list_tokens, revoke_token}, search::search}, middleware::{auth_middleware::Authentication, permission::require_permission, trace_id::TraceId}, repositories::{user, career, refresh_token as refresh_token_repo, revocation, pending_authorization, identity, role, personal_access_token, email_verification, search as search_repo}, domain::auth::{provider::Providers, role::{CAREER_HIDE, CAREER_WRITE, ROLE_ASSIGN, SESSION_REVOKE}}};
 
-pub struct Server {
-  port: u16,
-}
+use super::{keys::KeyStore, mailer::{self, Mailer}, settings::{StoreBackend, Settings}};
 
-#[derive(Debug, Deserialize)]
-pub struct RequestName {
-  pub name: String,
+pub struct Server {
+  settings: Settings,
+  keys: Arc<KeyStore>,
 }
 
 async fn index(_req: HttpRequest) -> &'static str {
   "body"
 }
 
+/// Every endpoint of the api. Sign-in and key discovery are public, the resources
+/// wrapped in `Authentication` need a bearer token, and the admin resources
+/// additionally a permission. Moderators edit and hide careers of other
+/// users under `/admin/careers`.
+pub fn routes(cfg: &mut web::ServiceConfig) {
+  cfg
+    // malformed bodies, paths and queries answer with a problem document like every other error
+    .app_data(web::JsonConfig::default().error_handler(|e, _| ApiError::from(e).into()))
+    .app_data(web::PathConfig::default().error_handler(|e, _| ApiError::from(e).into()))
+    .app_data(web::QueryConfig::default().error_handler(|e, _| ApiError::from(e).into()))
+    .route("/", web::get().to(index))
+    .route("/signin", web::post().to(fetch_access_token))
+    .route("/token/refresh", web::post().to(refresh_token))
+    .route("/authorization/code", web::get().to(authorization_code))
+    .route("/.well-known/jwks.json", web::get().to(jwks))
+    .route("/user/{id}", web::get().to(fetch_user))
+    .route("/user/email/verify", web::post().to(verify_email))
+    .route("/users", web::get().to(list_users))
+    .route("/users/by-login/{login}", web::get().to(fetch_user_by_login))
+    .route("/search", web::get().to(search))
+    // each resource carries its own `Authentication`, so a path nothing serves is a 404 rather than a sign-in challenge;
+    // the method guard lets `/career/{user_id}` and `/career/{id}` share a pattern
+    .service(web::resource("/signout").guard(guard::Post()).wrap(Authentication).to(sign_out))
+    .service(web::resource("/career").guard(guard::Post()).wrap(Authentication).to(create_career))
+    .service(web::resource("/career/{user_id}").guard(guard::Get()).wrap(Authentication).to(fetch_career))
+    .service(web::resource("/career/{id}").guard(guard::Patch()).wrap(Authentication).to(update_career))
+    .service(web::resource("/career/{id}").guard(guard::Delete()).wrap(Authentication).to(delete_career))
+    .service(web::resource("/users/by-login/{login}/careers").guard(guard::Get()).wrap(Authentication).to(fetch_career_by_login))
+    .service(web::resource("/users/{id}/experience").guard(guard::Get()).wrap(Authentication).to(fetch_experience))
+    .service(web::resource("/users/{id}/resume.json").guard(guard::Get()).wrap(Authentication).to(export_resume))
+    .service(web::resource("/user").guard(guard::Patch()).wrap(Authentication).to(update_user))
+    .service(web::resource("/user/channels").guard(guard::Put()).wrap(Authentication).to(update_user_channels))
+    .service(web::resource("/tokens").guard(guard::Post()).wrap(Authentication).to(create_token))
+    .service(web::resource("/tokens").guard(guard::Get()).wrap(Authentication).to(list_tokens))
+    .service(web::resource("/tokens/{id}").guard(guard::Delete()).wrap(Authentication).to(revoke_token))
+    // `require_permission` checks the bearer token itself
+    .service(
+      web::resource("/admin/users/{id}/sessions")
+        .wrap(require_permission(SESSION_REVOKE))
+        .route(web::delete().to(revoke_sessions))
+    )
+    .service(
+      web::resource("/admin/users/{id}/roles/{role}")
+        .wrap(require_permission(ROLE_ASSIGN))
+        .route(web::put().to(grant_role))
+        .route(web::delete().to(revoke_role))
+    )
+    .service(
+      web::resource("/admin/careers")
+        .wrap(require_permission(CAREER_WRITE))
+        .route(web::post().to(create_career))
+    )
+    .service(
+      web::resource("/admin/careers/{id}")
+        .wrap(require_permission(CAREER_WRITE))
+        .route(web::patch().to(update_career))
+        .route(web::delete().to(delete_career))
+    )
+    .service(
+      web::resource("/admin/careers/{id}/hidden")
+        .wrap(require_permission(CAREER_HIDE))
+        .route(web::put().to(hide_career))
+        .route(web::delete().to(show_career))
+    );
+}
+
 impl Server {
-  pub fn new(port: u16) -> Self {
-    Self { port }
+  pub fn new(settings: Settings, keys: Arc<KeyStore>) -> Self {
+    Self { settings, keys }
   }
 
   pub async fn run(&self, pool: DatabaseConnection) -> std::io::Result<()> {
     env::set_var("RUST_LOG", "info,axtix_web=debug,actix_server=info");
 
+    // repositories are built once and shared by every worker
+    let user_repo: Arc<dyn user::Repository> = Arc::new(user::PgRepository::new(pool.clone()));
+    let career_repo: Arc<dyn career::Repository> = Arc::new(career::PgRepository::new(pool.clone()));
+    let token_repo: Arc<dyn refresh_token_repo::Repository> = Arc::new(refresh_token_repo::PgRepository::new(pool.clone()));
+    let revocations: Arc<dyn revocation::Repository> = match self.settings.auth.revocation_store {
+      StoreBackend::Postgres => Arc::new(revocation::PgRepository::new(pool.clone())),
+      StoreBackend::Memory => Arc::new(revocation::InMemoryRepository::new()),
+    };
+    let authorizations: Arc<dyn pending_authorization::Repository> = match self.settings.auth.authorization_store {
+      StoreBackend::Postgres => Arc::new(pending_authorization::PgRepository::new(pool.clone())),
+      StoreBackend::Memory => Arc::new(pending_authorization::InMemoryRepository::new()),
+    };
+    let identities: Arc<dyn identity::Repository> = Arc::new(identity::PgRepository::new(pool.clone()));
+    let roles: Arc<dyn role::Repository> = Arc::new(role::PgRepository::new(pool.clone()));
+    let personal_access_tokens: Arc<dyn personal_access_token::Repository> = Arc::new(personal_access_token::PgRepository::new(pool.clone()));
+    let verifications: Arc<dyn email_verification::Repository> = Arc::new(email_verification::PgRepository::new(pool.clone()));
+    let search_repo: Arc<dyn search_repo::Repository> = Arc::new(search_repo::PgRepository::new(pool));
+    let mailer: Arc<dyn Mailer> = mailer::from_settings(&self.settings.mail);
+    let user_repo = web::Data::new(user_repo);
+    let identities = web::Data::new(identities);
+    let roles = web::Data::new(roles);
+    let personal_access_tokens = web::Data::new(personal_access_tokens);
+    let verifications = web::Data::new(verifications);
+    let search_repo = web::Data::new(search_repo);
+    let mailer = web::Data::new(mailer);
+    let career_repo = web::Data::new(career_repo);
+    let token_repo = web::Data::new(token_repo);
+    let revocations = web::Data::new(revocations);
+    let authorizations = web::Data::new(authorizations);
+    let settings = web::Data::new(self.settings.clone());
+    let keys = web::Data::new(self.keys.clone());
+    let providers = web::Data::new(Arc::new(Providers::new(&self.settings)));
+
     let server = HttpServer::new(move || {
       App::new()
         .wrap(
           Cors::default().allow_any_origin().allow_any_method().allow_any_header()
         )
         .wrap(Logger::default())
-        .wrap(Authentication)
-        .app_data(pool.clone())
-        .route("/", web::get().to(index))
-        .route("/signin", web::post().to(fetch_access_token))
-        .route("/authorization/code", web::get().to(authorization_code))
-        .route("/career", web::post().to(create_career))
-        .route("/career/{user_id}", web::get().to(fetch_career))
-        .route("/user", web::patch().to(update_user))
-        .route("/user/{id}", web::get().to(fetch_user))
+        .wrap(TraceId)
+        .app_data(settings.clone())
+        .app_data(keys.clone())
+        .app_data(providers.clone())
+        .app_data(user_repo.clone())
+        .app_data(identities.clone())
+        .app_data(roles.clone())
+        .app_data(personal_access_tokens.clone())
+        .app_data(verifications.clone())
+        .app_data(search_repo.clone())
+        .app_data(mailer.clone())
+        .app_data(career_repo.clone())
+        .app_data(token_repo.clone())
+        .app_data(revocations.clone())
+        .app_data(authorizations.clone())
+        .configure(routes)
     });
 
-    server.bind(format!("{}:{}", "127.0.0.1", self.port))?.run().await
+    server.bind((self.settings.server.host.as_str(), self.settings.server.port))?.run().await
   }
-}
\ No newline at end of file
+}
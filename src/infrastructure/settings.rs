@@ -0,0 +1,882 @@
+use std::{collections::{BTreeMap, HashMap}, env, fmt::{Display, Formatter}, fs, path::{Path, PathBuf}, str::FromStr};
+
+use clap::{Parser, Subcommand};
+use jsonwebtoken::Algorithm;
+use oauth2::url::Url;
+
+const DEFAULT_CONFIG_DIR: &str = "config";
+const JWT_KEYS_PREFIX: &str = "jwt.keys.";
+const OAUTH_PREFIX: &str = "oauth.";
+const DEFAULT_KID: &str = "default";
+
+// every fixed setting the service understands: (toml key, env var, default)
+const KEYS: [(&str, &str, Option<&str>); 25] = [
+  ("server.host", "SERVER_HOST", Some("127.0.0.1")),
+  ("server.port", "SERVER_PORT", Some("8082")),
+  ("database.url", "DATABASE_URL", None),
+  ("github.client_id", "GITHUB_CLIENT_ID", None),
+  ("github.client_secret", "GITHUB_CLIENT_SECRET", None),
+  ("github.auth_url", "GITHUB_AUTH_URL", None),
+  ("github.token_url", "GITHUB_TOKEN_URL", None),
+  ("github.redirect_url", "GITHUB_REDIRECT_URL", None),
+  ("github.api_url", "GITHUB_API_URL", Some("https://api.github.com")),
+  ("jwt.secret", "JWT_SECRET", None),
+  ("jwt.signing_kid", "JWT_SIGNING_KID", Some(DEFAULT_KID)),
+  ("jwt.access_token_ttl", "JWT_ACCESS_TOKEN_TTL", Some("900")),
+  ("jwt.refresh_token_ttl", "JWT_REFRESH_TOKEN_TTL", Some("1209600")),
+  ("auth.revocation_store", "AUTH_REVOCATION_STORE", Some("postgres")),
+  ("auth.authorization_store", "AUTH_AUTHORIZATION_STORE", Some("postgres")),
+  ("auth.authorization_ttl", "AUTH_AUTHORIZATION_TTL", Some("600")),
+  ("career.overlap_policy", "CAREER_OVERLAP_POLICY", Some("warn")),
+  ("mail.transport", "MAIL_TRANSPORT", Some("log")),
+  ("mail.dir", "MAIL_DIR", Some("tmp/mail")),
+  ("mail.from", "MAIL_FROM", Some("decafo <no-reply@decafo.local>")),
+  ("mail.verify_url", "MAIL_VERIFY_URL", Some("http://localhost:3000/email/verify")),
+  ("mail.verification_ttl", "MAIL_VERIFICATION_TTL", Some("86400")),
+  ("mock_provider.enabled", "MOCK_PROVIDER_ENABLED", Some("false")),
+  ("mock_provider.port", "MOCK_PROVIDER_PORT", Some("8083")),
+  ("mock_provider.users_file", "MOCK_PROVIDER_USERS_FILE", None),
+];
+
+#[derive(Debug, Parser)]
+#[clap(name = "rust_decafo")]
+pub struct Cli {
+  /// Environment name, loads `config/<env>.toml` on top of `config/default.toml`
+  #[clap(long, env = "APP_ENV")]
+  pub env: Option<String>,
+
+  /// Extra TOML file applied after the environment file
+  #[clap(long)]
+  pub config: Option<PathBuf>,
+
+  #[clap(long)]
+  pub host: Option<String>,
+
+  #[clap(long)]
+  pub port: Option<String>,
+
+  #[clap(long)]
+  pub database_url: Option<String>,
+
+  #[clap(subcommand)]
+  pub command: Option<Command>,
+}
+
+/// One-off tasks run instead of the server.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// Grants the admin role to a user, to set up the first admin
+  PromoteAdmin {
+    user_id: i64,
+  },
+  /// Grants the moderator role to a user, who can then edit and hide careers of others
+  PromoteModerator {
+    user_id: i64,
+  },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+  Missing(String, Option<&'static str>),
+  Invalid(String, String),
+}
+
+#[derive(Debug)]
+pub enum Error {
+  Read(PathBuf, String),
+  Validation(Vec<Problem>),
+}
+
+impl std::error::Error for Error {}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Error::Read(path, reason) => write!(f, "failed to read config file {}: {}", path.display(), reason),
+      Error::Validation(problems) => {
+        write!(f, "invalid configuration:")?;
+        for problem in problems {
+          match problem {
+            Problem::Missing(key, Some(env)) => write!(f, "\n  - missing `{}` (env {})", key, env)?,
+            Problem::Missing(key, None) => write!(f, "\n  - missing `{}`", key)?,
+            Problem::Invalid(key, reason) => write!(f, "\n  - invalid `{}`: {}", key, reason)?,
+          }
+        }
+        Ok(())
+      },
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerSettings {
+  pub host: String,
+  pub port: u16,
+}
+
+#[derive(Debug, Clone)]
+pub struct DatabaseSettings {
+  pub url: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct GithubSettings {
+  pub client_id: String,
+  pub client_secret: String,
+  pub auth_url: String,
+  pub token_url: String,
+  pub redirect_url: String,
+  /// base of the REST api the profile is read from
+  pub api_url: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProviderKind {
+  Gitlab,
+  Google,
+  Oidc,
+}
+
+/// An additional sign-in provider configured by an `[oauth.<name>]` table.
+#[derive(Debug, Clone)]
+pub struct ProviderSettings {
+  pub name: String,
+  pub kind: ProviderKind,
+  pub client_id: String,
+  pub client_secret: String,
+  pub redirect_url: String,
+  /// GitLab instance, or the issuer OIDC discovery starts from
+  pub base_url: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum KeyMaterial {
+  Secret(String),
+  Pem {
+    private_key: Option<String>,
+    public_key: String,
+  },
+}
+
+#[derive(Debug, Clone)]
+pub struct KeySettings {
+  pub kid: String,
+  pub algorithm: Algorithm,
+  pub material: KeyMaterial,
+}
+
+#[derive(Debug, Clone)]
+pub struct JwtSettings {
+  /// kid of the key new tokens are signed with, every other key only verifies
+  pub signing_kid: String,
+  pub keys: Vec<KeySettings>,
+  /// lifetimes in seconds
+  pub access_token_ttl: i64,
+  pub refresh_token_ttl: i64,
+}
+
+/// Backend of the short-lived auth state stores.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoreBackend {
+  Postgres,
+  /// per process, entries are lost on restart and not shared between instances
+  Memory,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthSettings {
+  pub revocation_store: StoreBackend,
+  /// where the OAuth `state` and PKCE verifier wait for the provider's redirect
+  pub authorization_store: StoreBackend,
+  /// seconds a started authorization stays valid
+  pub authorization_ttl: i64,
+}
+
+/// What happens when a full-time career overlaps another full-time career of the same user.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverlapPolicy {
+  Allow,
+  /// saved, the response carries a warning
+  Warn,
+  /// refused with 422
+  Reject,
+}
+
+#[derive(Debug, Clone)]
+pub struct CareerSettings {
+  pub overlap_policy: OverlapPolicy,
+}
+
+/// Where outgoing mail goes. There is no SMTP transport yet, both are meant for development.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MailTransport {
+  /// printed to stdout
+  Log,
+  /// one `.eml` file per mail in the directory
+  File(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct MailSettings {
+  pub transport: MailTransport,
+  pub from: String,
+  /// page the verification link points at, the token is appended as `?token=`
+  pub verify_url: String,
+  /// seconds a verification link stays valid
+  pub verification_ttl: i64,
+}
+
+/// An account the mock provider signs in as, see `config/mock_users.toml`.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct MockUserSettings {
+  pub id: i64,
+  pub login: String,
+  pub name: Option<String>,
+  pub avatar_url: String,
+  /// served from `/user/emails` as the primary verified address
+  #[serde(default)]
+  pub email: Option<String>,
+}
+
+/// Built-in stand-in for GitHub's OAuth endpoints, for offline development.
+#[derive(Debug, Clone)]
+pub struct MockProviderSettings {
+  pub enabled: bool,
+  pub port: u16,
+  pub users: Vec<MockUserSettings>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Settings {
+  pub server: ServerSettings,
+  pub database: DatabaseSettings,
+  pub github: GithubSettings,
+  pub oauth: Vec<ProviderSettings>,
+  pub jwt: JwtSettings,
+  pub auth: AuthSettings,
+  pub career: CareerSettings,
+  pub mail: MailSettings,
+  pub mock_provider: MockProviderSettings,
+}
+
+/// Flattened `table.key => value` view of one configuration source.
+#[derive(Debug, Default)]
+pub struct Layer(HashMap<String, String>);
+
+impl Layer {
+  pub fn from_toml(source: &str) -> Result<Self, String> {
+    let value = source.parse::<toml::Value>().map_err(|e| e.to_string())?;
+    let mut layer = Self::default();
+    layer.flatten("", &value);
+
+    Ok(layer)
+  }
+
+  pub fn from_file(path: &Path) -> Result<Self, Error> {
+    fs::read_to_string(path)
+      .map_err(|e| e.to_string())
+      .and_then(|source| Self::from_toml(&source))
+      .map_err(|reason| Error::Read(path.to_path_buf(), reason))
+  }
+
+  pub fn from_env<I: IntoIterator<Item = (String, String)>>(vars: I) -> Self {
+    let vars = vars.into_iter().collect::<HashMap<String, String>>();
+    let mut layer = Self::default();
+
+    for (key, env, _) in KEYS.iter() {
+      if let Some(value) = vars.get(*env) {
+        layer.set(key, value.clone());
+      }
+    }
+
+    layer
+  }
+
+  pub fn from_cli(cli: &Cli) -> Self {
+    let mut layer = Self::default();
+
+    for (key, value) in [
+      ("server.host", &cli.host),
+      ("server.port", &cli.port),
+      ("database.url", &cli.database_url),
+    ] {
+      if let Some(value) = value {
+        layer.set(key, value.clone());
+      }
+    }
+
+    layer
+  }
+
+  pub fn set(&mut self, key: &str, value: String) {
+    self.0.insert(key.to_string(), value);
+  }
+
+  fn flatten(&mut self, prefix: &str, value: &toml::Value) {
+    match value {
+      toml::Value::Table(table) => {
+        for (key, value) in table {
+          let key = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+          self.flatten(&key, value);
+        }
+      },
+      toml::Value::String(s) => self.set(prefix, s.clone()),
+      other => self.set(prefix, other.to_string()),
+    }
+  }
+}
+
+impl Settings {
+  /// Loads settings from `config/default.toml`, `config/<env>.toml`, `--config`,
+  /// the process environment and command line flags, later sources winning.
+  pub fn load(cli: &Cli) -> Result<Self, Error> {
+    let mut layers = vec![];
+
+    let mut files = vec![Path::new(DEFAULT_CONFIG_DIR).join("default.toml")];
+    if let Some(name) = &cli.env {
+      files.push(Path::new(DEFAULT_CONFIG_DIR).join(format!("{}.toml", name)));
+    }
+    for file in files {
+      if file.exists() {
+        layers.push(Layer::from_file(&file)?);
+      }
+    }
+    if let Some(file) = &cli.config {
+      layers.push(Layer::from_file(file)?);
+    }
+
+    layers.push(Layer::from_env(env::vars()));
+    layers.push(Layer::from_cli(cli));
+
+    Self::from_layers(layers)
+  }
+
+  /// Merges the given layers in order and validates the result, collecting every problem.
+  pub fn from_layers(layers: Vec<Layer>) -> Result<Self, Error> {
+    let mut values: HashMap<String, String> = KEYS.iter()
+      .filter_map(|(key, _, default)| default.map(|d| (key.to_string(), d.to_string())))
+      .collect();
+    for layer in layers {
+      values.extend(layer.0);
+    }
+
+    let mut problems = vec![];
+    let mut get = |key: &'static str| -> String {
+      match values.get(key).filter(|v| !v.trim().is_empty()) {
+        Some(value) => value.clone(),
+        None => {
+          let env = KEYS.iter().find(|(k, _, _)| *k == key).map(|(_, env, _)| *env);
+          problems.push(Problem::Missing(key.to_string(), env));
+          String::new()
+        },
+      }
+    };
+
+    let host = get("server.host");
+    let port = get("server.port");
+    let database_url = get("database.url");
+    let github = GithubSettings {
+      client_id: get("github.client_id"),
+      client_secret: get("github.client_secret"),
+      auth_url: get("github.auth_url"),
+      token_url: get("github.token_url"),
+      redirect_url: get("github.redirect_url"),
+      api_url: get("github.api_url"),
+    };
+    let signing_kid = get("jwt.signing_kid");
+    let access_token_ttl = get("jwt.access_token_ttl");
+    let refresh_token_ttl = get("jwt.refresh_token_ttl");
+    let revocation_store = get("auth.revocation_store");
+    let authorization_store = get("auth.authorization_store");
+    let authorization_ttl = get("auth.authorization_ttl");
+    let overlap_policy = get("career.overlap_policy");
+    let mail_transport = get("mail.transport");
+    let mail_dir = get("mail.dir");
+    let mail_from = get("mail.from");
+    let verify_url = get("mail.verify_url");
+    let verification_ttl = get("mail.verification_ttl");
+
+    let port = match port.parse::<u16>() {
+      Ok(port) => port,
+      Err(e) => {
+        if !port.is_empty() {
+          problems.push(Problem::Invalid("server.port".to_string(), e.to_string()));
+        }
+        0
+      },
+    };
+
+    for (key, url) in [
+      ("github.auth_url", &github.auth_url),
+      ("github.token_url", &github.token_url),
+      ("github.redirect_url", &github.redirect_url),
+      ("github.api_url", &github.api_url),
+      ("mail.verify_url", &verify_url),
+    ] {
+      if !url.is_empty() {
+        if let Err(e) = Url::parse(url) {
+          problems.push(Problem::Invalid(key.to_string(), e.to_string()));
+        }
+      }
+    }
+
+    let mut ttl = |key: &'static str, value: String| -> i64 {
+      match value.parse::<i64>() {
+        Ok(seconds) if seconds > 0 => seconds,
+        _ => {
+          if !value.is_empty() {
+            problems.push(Problem::Invalid(key.to_string(), format!("`{}` is not a positive number of seconds", value)));
+          }
+          0
+        },
+      }
+    };
+    let access_token_ttl = ttl("jwt.access_token_ttl", access_token_ttl);
+    let refresh_token_ttl = ttl("jwt.refresh_token_ttl", refresh_token_ttl);
+    let authorization_ttl = ttl("auth.authorization_ttl", authorization_ttl);
+    let verification_ttl = ttl("mail.verification_ttl", verification_ttl);
+
+    let mut backend = |key: &'static str, value: String| -> StoreBackend {
+      match value.as_str() {
+        "memory" => StoreBackend::Memory,
+        "postgres" | "" => StoreBackend::Postgres,
+        other => {
+          problems.push(Problem::Invalid(key.to_string(), format!("`{}` is not one of postgres, memory", other)));
+          StoreBackend::Postgres
+        },
+      }
+    };
+    let revocation_store = backend("auth.revocation_store", revocation_store);
+    let authorization_store = backend("auth.authorization_store", authorization_store);
+
+    let overlap_policy = match overlap_policy.as_str() {
+      "allow" => OverlapPolicy::Allow,
+      "warn" | "" => OverlapPolicy::Warn,
+      "reject" => OverlapPolicy::Reject,
+      other => {
+        problems.push(Problem::Invalid("career.overlap_policy".to_string(), format!("`{}` is not one of allow, warn, reject", other)));
+        OverlapPolicy::Warn
+      },
+    };
+
+    let transport = match mail_transport.as_str() {
+      "log" | "" => MailTransport::Log,
+      "file" => MailTransport::File(PathBuf::from(mail_dir)),
+      other => {
+        problems.push(Problem::Invalid("mail.transport".to_string(), format!("`{}` is not one of log, file", other)));
+        MailTransport::Log
+      },
+    };
+
+    let keys = jwt_keys(&values, &signing_kid, &mut problems);
+    let oauth = oauth_providers(&values, &mut problems);
+    let mock_provider = mock_provider(&values, &mut problems);
+
+    if !problems.is_empty() {
+      return Err(Error::Validation(problems));
+    }
+
+    Ok(Self {
+      server: ServerSettings { host, port },
+      database: DatabaseSettings { url: database_url },
+      github,
+      oauth,
+      jwt: JwtSettings { signing_kid, keys, access_token_ttl, refresh_token_ttl },
+      auth: AuthSettings { revocation_store, authorization_store, authorization_ttl },
+      career: CareerSettings { overlap_policy },
+      mail: MailSettings { transport, from: mail_from, verify_url, verification_ttl },
+      mock_provider,
+    })
+  }
+}
+
+// `jwt.secret` is shorthand for a single HS256 key, `[jwt.keys.<kid>]` tables configure named keys
+fn jwt_keys(values: &HashMap<String, String>, signing_kid: &str, problems: &mut Vec<Problem>) -> Vec<KeySettings> {
+  let mut tables: BTreeMap<&str, HashMap<&str, &str>> = BTreeMap::new();
+  for (key, value) in values {
+    if let Some((kid, field)) = key.strip_prefix(JWT_KEYS_PREFIX).and_then(|rest| rest.rsplit_once('.')) {
+      tables.entry(kid).or_default().insert(field, value.as_str());
+    }
+  }
+
+  let mut keys = vec![];
+  if let Some(secret) = values.get("jwt.secret").filter(|v| !v.trim().is_empty()) {
+    keys.push(KeySettings {
+      kid: DEFAULT_KID.to_string(),
+      algorithm: Algorithm::HS256,
+      material: KeyMaterial::Secret(secret.clone()),
+    });
+  }
+
+  for (kid, fields) in tables {
+    let key = |field: &str| format!("{}{}.{}", JWT_KEYS_PREFIX, kid, field);
+    let read = |field: &str, problems: &mut Vec<Problem>| -> Option<String> {
+      let path = fields.get(field)?;
+      match fs::read_to_string(path) {
+        Ok(pem) => Some(pem),
+        Err(e) => {
+          problems.push(Problem::Invalid(key(field), format!("{}: {}", path, e)));
+          None
+        },
+      }
+    };
+
+    let algorithm = match fields.get("algorithm").map(|a| Algorithm::from_str(a)) {
+      Some(Ok(algorithm)) => algorithm,
+      Some(Err(e)) => {
+        problems.push(Problem::Invalid(key("algorithm"), e.to_string()));
+        continue;
+      },
+      None => {
+        problems.push(Problem::Missing(key("algorithm"), None));
+        continue;
+      },
+    };
+
+    let material = match algorithm {
+      Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => match fields.get("secret") {
+        Some(secret) => KeyMaterial::Secret(secret.to_string()),
+        None => {
+          problems.push(Problem::Missing(key("secret"), None));
+          continue;
+        },
+      },
+      Algorithm::EdDSA => {
+        problems.push(Problem::Invalid(key("algorithm"), "EdDSA is not supported".to_string()));
+        continue;
+      },
+      _ => {
+        let private_key = read("private_key", problems);
+        match read("public_key", problems) {
+          Some(public_key) => KeyMaterial::Pem { private_key, public_key },
+          None => {
+            if !fields.contains_key("public_key") {
+              problems.push(Problem::Missing(key("public_key"), None));
+            }
+            continue;
+          },
+        }
+      },
+    };
+
+    keys.push(KeySettings { kid: kid.to_string(), algorithm, material });
+  }
+
+  if keys.is_empty() {
+    problems.push(Problem::Missing("jwt.secret".to_string(), Some("JWT_SECRET")));
+  } else if !signing_kid.is_empty() {
+    match keys.iter().find(|k| k.kid == signing_kid) {
+      Some(KeySettings { material: KeyMaterial::Pem { private_key: None, .. }, .. }) => {
+        problems.push(Problem::Invalid("jwt.signing_kid".to_string(), format!("key `{}` has no private_key", signing_kid)));
+      },
+      Some(_) => {},
+      None => {
+        problems.push(Problem::Invalid("jwt.signing_kid".to_string(), format!("no key named `{}`", signing_kid)));
+      },
+    }
+  }
+
+  keys
+}
+
+// `[oauth.<name>]` tables; `kind` is gitlab, google or oidc, GitHub keeps its own `[github]` table
+fn oauth_providers(values: &HashMap<String, String>, problems: &mut Vec<Problem>) -> Vec<ProviderSettings> {
+  let mut tables: BTreeMap<&str, HashMap<&str, &str>> = BTreeMap::new();
+  for (key, value) in values {
+    if let Some((name, field)) = key.strip_prefix(OAUTH_PREFIX).and_then(|rest| rest.rsplit_once('.')) {
+      tables.entry(name).or_default().insert(field, value.as_str());
+    }
+  }
+
+  let mut providers = vec![];
+  for (name, fields) in tables {
+    let key = |field: &str| format!("{}{}.{}", OAUTH_PREFIX, name, field);
+
+    if name.eq_ignore_ascii_case("github") {
+      problems.push(Problem::Invalid(key("kind"), "github is configured by the [github] table".to_string()));
+      continue;
+    }
+
+    let (kind, default_url, url_field) = match fields.get("kind").copied() {
+      Some("gitlab") => (ProviderKind::Gitlab, Some("https://gitlab.com"), "base_url"),
+      Some("google") => (ProviderKind::Google, Some("https://accounts.google.com"), "issuer"),
+      Some("oidc") => (ProviderKind::Oidc, None, "issuer"),
+      Some(other) => {
+        problems.push(Problem::Invalid(key("kind"), format!("`{}` is not one of gitlab, google, oidc", other)));
+        continue;
+      },
+      None => {
+        problems.push(Problem::Missing(key("kind"), None));
+        continue;
+      },
+    };
+
+    let mut field = |field: &str, default: Option<&str>| -> String {
+      match fields.get(field).copied().or(default) {
+        Some(value) => value.to_string(),
+        None => {
+          problems.push(Problem::Missing(key(field), None));
+          String::new()
+        },
+      }
+    };
+    let client_id = field("client_id", None);
+    let client_secret = field("client_secret", None);
+    let redirect_url = field("redirect_url", None);
+    let base_url = field(url_field, default_url);
+
+    for (field, url) in [("redirect_url", &redirect_url), (url_field, &base_url)] {
+      if !url.is_empty() {
+        if let Err(e) = Url::parse(url) {
+          problems.push(Problem::Invalid(key(field), e.to_string()));
+        }
+      }
+    }
+
+    providers.push(ProviderSettings {
+      name: name.to_string(),
+      kind,
+      client_id,
+      client_secret,
+      redirect_url,
+      base_url: base_url.trim_end_matches('/').to_string(),
+    });
+  }
+
+  providers
+}
+
+#[derive(serde::Deserialize)]
+struct MockUsersFile {
+  users: Vec<MockUserSettings>,
+}
+
+fn mock_provider(values: &HashMap<String, String>, problems: &mut Vec<Problem>) -> MockProviderSettings {
+  let value = |key: &str| values.get(key).map(String::as_str).unwrap_or_default();
+
+  let enabled = match value("mock_provider.enabled") {
+    "true" => true,
+    "false" | "" => false,
+    other => {
+      problems.push(Problem::Invalid("mock_provider.enabled".to_string(), format!("`{}` is not a boolean", other)));
+      false
+    },
+  };
+  let port = match value("mock_provider.port").parse::<u16>() {
+    Ok(port) => port,
+    Err(e) => {
+      problems.push(Problem::Invalid("mock_provider.port".to_string(), e.to_string()));
+      0
+    },
+  };
+
+  let users = match values.get("mock_provider.users_file").filter(|path| !path.trim().is_empty()) {
+    Some(path) => match fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|s| toml::from_str::<MockUsersFile>(&s).map_err(|e| e.to_string())) {
+      Ok(file) if !file.users.is_empty() => file.users,
+      Ok(_) => {
+        problems.push(Problem::Invalid("mock_provider.users_file".to_string(), format!("{}: no [[users]]", path)));
+        vec![]
+      },
+      Err(e) => {
+        problems.push(Problem::Invalid("mock_provider.users_file".to_string(), format!("{}: {}", path, e)));
+        vec![]
+      },
+    },
+    None => vec![MockUserSettings {
+      id: 1,
+      login: "octocat".to_string(),
+      name: Some("The Octocat".to_string()),
+      avatar_url: "https://avatars.githubusercontent.com/u/583231".to_string(),
+      email: Some("octocat@github.com".to_string()),
+    }],
+  };
+
+  MockProviderSettings { enabled, port, users }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl Settings {
+  pub fn test() -> Self {
+    let mut layer = Layer::default();
+    layer.set("database.url", "postgres://decafo@localhost/decafo".to_string());
+    layer.set("github.client_id", "client_id".to_string());
+    layer.set("github.client_secret", "client_secret".to_string());
+    layer.set("github.auth_url", "https://github.com/login/oauth/authorize".to_string());
+    layer.set("github.token_url", "https://github.com/login/oauth/access_token".to_string());
+    layer.set("github.redirect_url", "http://localhost:3000/signin".to_string());
+    layer.set("jwt.secret", "secret".to_string());
+
+    Self::from_layers(vec![layer]).unwrap()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_should_be_report_every_missing_key() {
+    let res = Settings::from_layers(vec![]);
+
+    match res {
+      Err(Error::Validation(problems)) => {
+        assert_eq!(problems.len(), 7);
+        assert!(problems.contains(&Problem::Missing("database.url".to_string(), Some("DATABASE_URL"))));
+        assert!(problems.contains(&Problem::Missing("jwt.secret".to_string(), Some("JWT_SECRET"))));
+      },
+      _ => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn it_should_be_apply_layers_in_order() {
+    let file = Layer::from_toml("[server]\nport = 9000\nhost = \"0.0.0.0\"").unwrap();
+    let env = Layer::from_env(vec![("SERVER_PORT".to_string(), "9100".to_string())]);
+    let mut cli = Layer::default();
+    cli.set("database.url", "postgres://cli".to_string());
+
+    let mut base = Layer::default();
+    for (key, value) in [
+      ("database.url", "postgres://file"),
+      ("github.client_id", "id"),
+      ("github.client_secret", "secret"),
+      ("github.auth_url", "https://github.com/login/oauth/authorize"),
+      ("github.token_url", "https://github.com/login/oauth/access_token"),
+      ("github.redirect_url", "http://localhost:3000"),
+      ("jwt.secret", "secret"),
+    ] {
+      base.set(key, value.to_string());
+    }
+
+    let settings = Settings::from_layers(vec![base, file, env, cli]).unwrap();
+
+    assert_eq!(settings.server.host, "0.0.0.0".to_string());
+    assert_eq!(settings.server.port, 9100);
+    assert_eq!(settings.database.url, "postgres://cli".to_string());
+  }
+
+  #[test]
+  fn it_should_be_load_named_jwt_keys() {
+    let mut layer = Layer::from_toml(r#"
+      [jwt]
+      signing_kid = "2022-12"
+
+      [jwt.keys.2022-12]
+      algorithm = "ES256"
+      private_key = "tests/fixtures/jwt/ec_private.pem"
+      public_key = "tests/fixtures/jwt/ec_public.pem"
+
+      [jwt.keys.2022-06]
+      algorithm = "RS256"
+      public_key = "tests/fixtures/jwt/rsa_public.pem"
+    "#).unwrap();
+    layer.set("jwt.keys.2022-01.algorithm", "HS256".to_string());
+
+    match Settings::from_layers(vec![layer]) {
+      Err(Error::Validation(problems)) => {
+        assert!(problems.contains(&Problem::Missing("jwt.keys.2022-01.secret".to_string(), None)));
+        assert!(!problems.iter().any(|p| matches!(p, Problem::Missing(key, _) if key == "jwt.secret")));
+        assert!(!problems.iter().any(|p| matches!(p, Problem::Invalid(key, _) if key.starts_with("jwt."))));
+      },
+      _ => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn it_should_be_reject_a_verification_only_signing_key() {
+    let layer = Layer::from_toml(r#"
+      [jwt]
+      signing_kid = "2022-06"
+
+      [jwt.keys.2022-06]
+      algorithm = "RS256"
+      public_key = "tests/fixtures/jwt/rsa_public.pem"
+    "#).unwrap();
+
+    match Settings::from_layers(vec![layer]) {
+      Err(Error::Validation(problems)) => {
+        assert!(problems.iter().any(|p| matches!(p, Problem::Invalid(key, _) if key == "jwt.signing_kid")));
+      },
+      _ => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn it_should_be_parse_auth_settings() {
+    let mut layer = Layer::from_toml("[auth]\nrevocation_store = \"memory\"").unwrap();
+    layer.set("database.url", "postgres://decafo@localhost/decafo".to_string());
+    for (key, value) in [
+      ("github.client_id", "id"),
+      ("github.client_secret", "secret"),
+      ("github.auth_url", "https://github.com/login/oauth/authorize"),
+      ("github.token_url", "https://github.com/login/oauth/access_token"),
+      ("github.redirect_url", "http://localhost:3000"),
+      ("jwt.secret", "secret"),
+    ] {
+      layer.set(key, value.to_string());
+    }
+
+    let settings = Settings::from_layers(vec![layer]).unwrap();
+
+    assert_eq!(settings.auth.revocation_store, StoreBackend::Memory);
+    assert_eq!(settings.auth.authorization_store, StoreBackend::Postgres);
+    assert_eq!(settings.auth.authorization_ttl, 600);
+  }
+
+  #[test]
+  fn it_should_be_load_oauth_providers() {
+    let layer = Layer::from_toml(r#"
+      [oauth.google]
+      kind = "google"
+      client_id = "id"
+      client_secret = "secret"
+      redirect_url = "http://localhost:3000/signin"
+
+      [oauth.corp]
+      kind = "oidc"
+      client_id = "id"
+      client_secret = "secret"
+      redirect_url = "http://localhost:3000/signin"
+
+      [oauth.github]
+      kind = "gitlab"
+    "#).unwrap();
+    let mut problems = vec![];
+    let values = layer.0.into_iter().collect();
+
+    let providers = oauth_providers(&values, &mut problems);
+
+    assert_eq!(providers.len(), 2);
+    assert!(providers.iter().any(|p| p.name == "google" && p.base_url == "https://accounts.google.com"));
+    assert!(problems.contains(&Problem::Missing("oauth.corp.issuer".to_string(), None)));
+    assert!(problems.iter().any(|p| matches!(p, Problem::Invalid(key, _) if key == "oauth.github.kind")));
+  }
+
+  #[test]
+  fn it_should_be_load_mock_provider_users() {
+    let mut values = HashMap::new();
+    values.insert("mock_provider.enabled".to_string(), "true".to_string());
+    values.insert("mock_provider.port".to_string(), "8083".to_string());
+    values.insert("mock_provider.users_file".to_string(), "config/mock_users.toml".to_string());
+    let mut problems = vec![];
+
+    let mock = mock_provider(&values, &mut problems);
+
+    assert!(problems.is_empty());
+    assert!(mock.enabled);
+    assert!(mock.users.iter().any(|u| u.login == "kent-back"));
+  }
+
+  #[test]
+  fn it_should_be_reject_an_invalid_port() {
+    let mut layer = Layer::default();
+    layer.set("server.port", "http".to_string());
+
+    match Settings::from_layers(vec![layer]) {
+      Err(Error::Validation(problems)) => {
+        assert!(problems.iter().any(|p| matches!(p, Problem::Invalid(key, _) if key == "server.port")));
+      },
+      _ => unreachable!(),
+    }
+  }
+}
@@ -0,0 +1,8 @@
+// the in-memory stand-ins are built with `new()` like their Postgres counterparts
+#![allow(clippy::new_without_default)]
+
+pub mod api;
+pub mod infrastructure;
+pub mod domain;
+pub mod repositories;
+pub mod middleware;
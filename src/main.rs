@@ -1,13 +1,60 @@
-mod api;
-mod infrastructure;
-mod domain;
-mod repositories;
-mod middleware;
+use std::sync::Arc;
+
+use clap::Parser;
+use dotenv::dotenv;
+use rust_decafo::{domain::auth::assign_role, infrastructure::{self, keys::KeyStore, mock_provider::MockProvider, settings::{Cli, Command, Settings}}, repositories::role};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-  let pool = infrastructure::database::Database::establish_connection().await;
-  let server = infrastructure::Server::new(8082);
+  // before parsing, `--env` may come from APP_ENV in .env
+  dotenv().ok();
+  // RUST_LOG picks the level, errors the repositories and handlers log show up by default
+  env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+  let cli = Cli::parse();
+
+  let settings = match Settings::load(&cli) {
+    Ok(settings) => settings,
+    Err(e) => {
+      eprintln!("{}", e);
+      std::process::exit(1);
+    },
+  };
+  let keys = match KeyStore::new(&settings.jwt) {
+    Ok(keys) => Arc::new(keys),
+    Err(e) => {
+      eprintln!("{}", e);
+      std::process::exit(1);
+    },
+  };
+  let pool = infrastructure::database::Database::establish_connection(&settings.database).await;
+
+  if let Some(command) = cli.command {
+    let roles: Arc<dyn role::Repository> = Arc::new(role::PgRepository::new(pool));
+    let (user_id, role, res) = match command {
+      Command::PromoteAdmin { user_id } => (user_id, "an admin", assign_role::bootstrap_admin(roles, user_id).await),
+      Command::PromoteModerator { user_id } => (user_id, "a moderator", assign_role::bootstrap_moderator(roles, user_id).await),
+    };
+    match res {
+      Ok(_) => {
+        println!("user {} is {} from their next sign-in or token refresh", user_id, role);
+        return Ok(());
+      },
+      Err(e) => {
+        eprintln!("could not promote user {}: {:?}", user_id, e);
+        std::process::exit(1);
+      },
+    }
+  }
+
+  let mock_provider = settings.mock_provider.clone();
+  let host = settings.server.host.clone();
+  let server = infrastructure::Server::new(settings, keys);
+
+  if mock_provider.enabled {
+    println!("mock OAuth provider listening on {}:{}", host, mock_provider.port);
+    futures_util::try_join!(server.run(pool), MockProvider::run(&mock_provider, &host))?;
+    return Ok(());
+  }
 
   server.run(pool).await
 }
@@ -1,29 +1,22 @@
-use std::future::{ready, Ready};
+use std::{future::{ready, Ready}, rc::Rc};
 
 use actix_web::{
   body::EitherBody,
   dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-  http::{Method},
-  Error, HttpResponse
+  http::Method,
+  Error, HttpMessage, ResponseError
 };
-use chrono::Local;
 use futures_util::future::LocalBoxFuture;
-use jsonwebtoken::{Validation, DecodingKey};
-use serde::Serialize;
 
-use crate::domain::auth::entity::Claims;
-
-const IGNORE_ROUTES: [&str; 2] = ["/authorization/code", "/signin"];
-
-#[derive(Serialize)]
-pub struct AuthRes {
-  message: String
-}
+use super::auth_user::authenticate;
 
+/// Rejects requests without a valid bearer token. Wrap the resources that need a signed-in user;
+/// handlers read the caller through `AuthUser`.
 pub struct Authentication;
+
 impl<S, B> Transform<S, ServiceRequest> for Authentication
 where
-  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
   S::Future: 'static,
   B: 'static,
 {
@@ -34,16 +27,17 @@ where
   type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
   fn new_transform(&self, service: S) -> Self::Future {
-      ready(Ok(AuthenticationMiddleware { service }))
+      ready(Ok(AuthenticationMiddleware { service: Rc::new(service) }))
   }
 }
 
 pub struct AuthenticationMiddleware<S> {
-  service: S
+  service: Rc<S>,
 }
+
 impl<S, B> Service<ServiceRequest> for AuthenticationMiddleware<S>
 where
-  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
   S::Future: 'static,
   B: 'static,
 {
@@ -54,48 +48,24 @@ where
   forward_ready!(service);
 
   fn call(&self, req: ServiceRequest) -> Self::Future {
-    let mut authenticate_pass = false;
+    let service = self.service.clone();
 
-    if Method::OPTIONS == *req.method() {
-      authenticate_pass = true;
-    } else {
-      for ignore_route in IGNORE_ROUTES.iter() {
-        if req.path().starts_with(ignore_route) {
-          authenticate_pass = true;
-          break;
-        }
+    Box::pin(async move {
+      if Method::OPTIONS == *req.method() {
+        return Ok(service.call(req).await?.map_into_left_body());
       }
 
-      if !authenticate_pass {
-        if let Some(token) = req.headers().get("Authorization") {
-          match jsonwebtoken::decode::<Claims>(token.to_str().unwrap(), &DecodingKey::from_secret("secret".as_ref()), &Validation::default()) {
-            Ok(jwt) => {
-              let exp = i64::try_from(jwt.claims.exp).expect("0");
-              if !Local::now().timestamp_millis().lt(&exp) {
-                authenticate_pass = false;
-              } else {
-                authenticate_pass = true;
-              }
-            },
-            Err(e) => {
-              println!("{:?}", e);
-            },
-          };
-        }
+      match authenticate(req.request()).await {
+        Ok(claims) => {
+          req.extensions_mut().insert(claims);
+          Ok(service.call(req).await?.map_into_left_body())
+        },
+        Err(e) => {
+          let (request, _pl) = req.into_parts();
+          let response = e.error_response().map_into_right_body();
+          Ok(ServiceResponse::new(request, response))
+        },
       }
-    }
-
-    if authenticate_pass {
-      let fut = self.service.call(req);
-      Box::pin(async move {
-        Ok(fut.await?.map_into_left_body())
-      })
-    } else {
-      let (request, _pl) = req.into_parts();
-      let response = HttpResponse::Unauthorized()
-        .finish()
-        .map_into_right_body();
-      return Box::pin(async { Ok(ServiceResponse::new(request, response)) });
-    }
+    })
   }
-}
\ No newline at end of file
+}
@@ -0,0 +1,212 @@
+use std::{fmt::{Display, Formatter}, future::ready, ops::Deref, sync::Arc};
+
+use actix_web::{dev::Payload, http::{header::AUTHORIZATION, StatusCode}, web, FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError};
+use chrono::Local;
+use futures_util::future::LocalBoxFuture;
+
+use crate::{api::error::ApiError, domain::auth::{entity::{Claims, Principal, ResUserProfile}, personal_access_token::{self, TOKEN_PREFIX, VerifyError}}, infrastructure::keys::KeyStore, middleware::trace_id, repositories::{personal_access_token as token_repo, revocation, role, user}};
+
+#[derive(Debug, Clone, Copy)]
+pub enum AuthError {
+  /// no `Authorization` header
+  Missing,
+  /// malformed header, bad signature, expired or revoked token
+  Invalid,
+  /// signed in, but without the permission the route requires
+  Forbidden,
+  /// the revocation store could not be asked
+  Unavailable,
+}
+
+impl Display for AuthError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      AuthError::Missing => write!(f, "missing bearer token"),
+      AuthError::Invalid => write!(f, "invalid bearer token"),
+      AuthError::Forbidden => write!(f, "permission denied"),
+      AuthError::Unavailable => write!(f, "authentication unavailable"),
+    }
+  }
+}
+
+impl ResponseError for AuthError {
+  fn status_code(&self) -> StatusCode {
+    match self {
+      AuthError::Missing | AuthError::Invalid => StatusCode::UNAUTHORIZED,
+      AuthError::Forbidden => StatusCode::FORBIDDEN,
+      AuthError::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+    }
+  }
+
+  fn error_response(&self) -> HttpResponse {
+    ApiError::from(*self).error_response()
+  }
+}
+
+/// Token of an `Authorization: Bearer <token>` header; the scheme is case-insensitive.
+pub fn bearer_token(req: &HttpRequest) -> Result<Option<&str>, AuthError> {
+  let header = match req.headers().get(AUTHORIZATION) {
+    Some(header) => header.to_str().map_err(|_| AuthError::Invalid)?,
+    None => return Ok(None),
+  };
+
+  match header.split_once(' ') {
+    Some((scheme, token)) if scheme.eq_ignore_ascii_case("bearer") && !token.is_empty() && !token.contains(' ') => Ok(Some(token)),
+    _ => Err(AuthError::Invalid),
+  }
+}
+
+/// Verifies the bearer token of the request, a JWT or a personal access token.
+/// Read-only personal access tokens are refused for anything but safe methods.
+pub async fn authenticate(req: &HttpRequest) -> Result<Claims, AuthError> {
+  let token = bearer_token(req)?.ok_or(AuthError::Missing)?;
+
+  let claims = match token.starts_with(TOKEN_PREFIX) {
+    true => verify_personal_access_token(req, token).await?,
+    false => verify_jwt(req, token).await?,
+  };
+
+  if claims.is_read_only() && !req.method().is_safe() {
+    return Err(AuthError::Forbidden);
+  }
+
+  Ok(claims)
+}
+
+async fn verify_personal_access_token(req: &HttpRequest, token: &str) -> Result<Claims, AuthError> {
+  let (tokens, users, roles) = match (
+    req.app_data::<web::Data<Arc<dyn token_repo::Repository>>>(),
+    req.app_data::<web::Data<Arc<dyn user::Repository>>>(),
+    req.app_data::<web::Data<Arc<dyn role::Repository>>>(),
+  ) {
+    (Some(tokens), Some(users), Some(roles)) => (tokens.get_ref().clone(), users.get_ref().clone(), roles.get_ref().clone()),
+    _ => return Err(AuthError::Unavailable),
+  };
+
+  match personal_access_token::verify(tokens, users, roles, token).await {
+    Ok(claims) => Ok(claims),
+    Err(VerifyError::Invalid) => Err(AuthError::Invalid),
+    Err(VerifyError::Unknown) => Err(AuthError::Unavailable),
+  }
+}
+
+/// Signature, expiry and revocation.
+async fn verify_jwt(req: &HttpRequest, token: &str) -> Result<Claims, AuthError> {
+  let (keys, revocations) = match (
+    req.app_data::<web::Data<Arc<KeyStore>>>(),
+    req.app_data::<web::Data<Arc<dyn revocation::Repository>>>(),
+  ) {
+    (Some(keys), Some(revocations)) => (keys.get_ref().clone(), revocations.get_ref().clone()),
+    _ => return Err(AuthError::Unavailable),
+  };
+
+  let claims = match keys.decode::<Claims>(token) {
+    Ok(jwt) => jwt.claims,
+    Err(e) => {
+      log::warn!("[{}] rejected a bearer token: {:?}", trace_id::current(), e);
+      return Err(AuthError::Invalid);
+    },
+  };
+
+  // `exp` is in milliseconds, so it is checked here rather than by jsonwebtoken
+  if i64::try_from(claims.exp).map_or(true, |exp| Local::now().timestamp_millis() >= exp) {
+    return Err(AuthError::Invalid);
+  }
+
+  match revocations.is_revoked(claims.jti, claims.user.id, claims.issued_at()).await {
+    Ok(false) => Ok(claims),
+    Ok(true) => Err(AuthError::Invalid),
+    Err(e) => {
+      log::error!("[{}] {:?}", trace_id::current(), e);
+      Err(AuthError::Unavailable)
+    },
+  }
+}
+
+/// The verified caller. Reuses the claims `Authentication` already checked, or verifies the header itself.
+#[derive(Debug, Clone)]
+pub struct AuthUser(pub Claims);
+
+impl AuthUser {
+  pub fn principal(&self) -> Principal {
+    Principal {
+      user_id: self.id,
+      permissions: self.0.grants.permissions.clone(),
+    }
+  }
+}
+
+impl Deref for AuthUser {
+  type Target = ResUserProfile;
+
+  fn deref(&self) -> &Self::Target {
+    &self.0.user
+  }
+}
+
+impl FromRequest for AuthUser {
+  type Error = AuthError;
+  type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+  fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+    if let Some(claims) = req.extensions().get::<Claims>() {
+      return Box::pin(ready(Ok(AuthUser(claims.clone()))));
+    }
+
+    let req = req.clone();
+    Box::pin(async move {
+      let claims = authenticate(&req).await?;
+      req.extensions_mut().insert(claims.clone());
+      Ok(AuthUser(claims))
+    })
+  }
+}
+
+/// `Optional<AuthUser>` is `None` for anonymous requests, but unlike `Option<AuthUser>`
+/// a bad or revoked token is still rejected instead of being treated as anonymous.
+#[derive(Debug, Clone)]
+pub struct Optional<T>(pub Option<T>);
+
+impl FromRequest for Optional<AuthUser> {
+  type Error = AuthError;
+  type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+  fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+    if !req.headers().contains_key(AUTHORIZATION) {
+      return Box::pin(ready(Ok(Optional(None))));
+    }
+
+    let user = AuthUser::from_request(req, payload);
+    Box::pin(async move { user.await.map(|user| Optional(Some(user))) })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use actix_web::test::TestRequest;
+
+  use super::*;
+
+  #[test]
+  fn it_should_be_parse_a_bearer_header() {
+    let req = TestRequest::default().insert_header((AUTHORIZATION, "bearer abc.def.ghi")).to_http_request();
+
+    assert_eq!(bearer_token(&req).unwrap(), Some("abc.def.ghi"));
+  }
+
+  #[test]
+  fn it_should_be_reject_a_header_without_the_scheme() {
+    for header in ["abc.def.ghi", "Basic abc", "Bearer ", "Bearer a b"] {
+      let req = TestRequest::default().insert_header((AUTHORIZATION, header)).to_http_request();
+
+      assert!(matches!(bearer_token(&req), Err(AuthError::Invalid)), "{}", header);
+    }
+  }
+
+  #[test]
+  fn it_should_be_return_none_without_a_header() {
+    let req = TestRequest::default().to_http_request();
+
+    assert!(matches!(bearer_token(&req), Ok(None)));
+  }
+}
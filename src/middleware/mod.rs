@@ -1 +1,4 @@
-pub mod auth_middleware;
\ No newline at end of file
+pub mod auth_middleware;
+pub mod auth_user;
+pub mod permission;
+pub mod trace_id;
@@ -0,0 +1,151 @@
+use std::{future::{ready, Ready}, rc::Rc};
+
+use actix_web::{
+  body::EitherBody,
+  dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+  http::Method,
+  Error, HttpMessage, ResponseError
+};
+use futures_util::future::LocalBoxFuture;
+
+use crate::domain::auth::entity::Claims;
+
+use super::auth_user::{authenticate, AuthError};
+
+/// Lets a request through only if its access token grants `permission`, e.g.
+/// `web::resource("/admin/...").wrap(require_permission("session:revoke"))`.
+/// Anonymous requests get 401, signed-in users without the permission 403.
+pub fn require_permission(permission: &'static str) -> RequirePermission {
+  RequirePermission { permission }
+}
+
+pub struct RequirePermission {
+  permission: &'static str,
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<EitherBody<B>>;
+  type Error = Error;
+  type InitError = ();
+  type Transform = RequirePermissionMiddleware<S>;
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+      ready(Ok(RequirePermissionMiddleware { service: Rc::new(service), permission: self.permission }))
+  }
+}
+
+pub struct RequirePermissionMiddleware<S> {
+  service: Rc<S>,
+  permission: &'static str,
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePermissionMiddleware<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<EitherBody<B>>;
+  type Error = Error;
+  type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+  forward_ready!(service);
+
+  fn call(&self, req: ServiceRequest) -> Self::Future {
+    let service = self.service.clone();
+    let permission = self.permission;
+
+    Box::pin(async move {
+      if Method::OPTIONS == *req.method() {
+        return Ok(service.call(req).await?.map_into_left_body());
+      }
+
+      // an enclosing `Authentication` has already verified the token
+      let claims = req.extensions().get::<Claims>().cloned();
+      let claims = match claims {
+        Some(claims) => Ok(claims),
+        None => authenticate(req.request()).await,
+      };
+
+      let res = claims.and_then(|claims| match claims.grants.permissions.iter().any(|p| p == permission) {
+        true => Ok(claims),
+        false => Err(AuthError::Forbidden),
+      });
+
+      match res {
+        Ok(claims) => {
+          req.extensions_mut().insert(claims);
+          Ok(service.call(req).await?.map_into_left_body())
+        },
+        Err(e) => {
+          let (request, _pl) = req.into_parts();
+          let response = e.error_response().map_into_right_body();
+          Ok(ServiceResponse::new(request, response))
+        },
+      }
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use actix_web::{http::StatusCode, test, web, App, HttpResponse};
+
+  use crate::{
+    domain::auth::{entity::{create_jwt, ResUserProfile}, role::{Grants, SESSION_REVOKE}},
+    infrastructure::{keys::KeyStore, settings::Settings},
+    repositories::revocation,
+  };
+
+  use super::*;
+
+  async fn status(permissions: Option<&[&str]>) -> StatusCode {
+    let settings = Settings::test();
+    let keys = Arc::new(KeyStore::new(&settings.jwt).unwrap());
+    let revocations: Arc<dyn revocation::Repository> = Arc::new(revocation::InMemoryRepository::new());
+
+    let app = test::init_service(
+      App::new()
+        .app_data(web::Data::new(keys.clone()))
+        .app_data(web::Data::new(revocations))
+        .service(
+          web::resource("/admin")
+            .wrap(require_permission(SESSION_REVOKE))
+            .route(web::get().to(HttpResponse::Ok))
+        )
+    ).await;
+
+    let mut req = test::TestRequest::get().uri("/admin");
+    if let Some(permissions) = permissions {
+      let user = ResUserProfile { id: 1, login: "kent-back".to_string(), name: None, avatar_url: "".to_string() };
+      let grants = Grants { roles: vec![], permissions: permissions.iter().map(|p| p.to_string()).collect() };
+      let token = create_jwt(&keys, &settings.jwt, user, grants).unwrap();
+      req = req.insert_header(("Authorization", format!("Bearer {}", token)));
+    }
+
+    test::call_service(&app, req.to_request()).await.status()
+  }
+
+  #[actix_web::test]
+  async fn it_should_be_let_a_request_with_the_permission_through() {
+    assert_eq!(status(Some(&[SESSION_REVOKE])).await, StatusCode::OK);
+  }
+
+  #[actix_web::test]
+  async fn it_should_be_forbid_a_request_without_the_permission() {
+    assert_eq!(status(Some(&["career:write"])).await, StatusCode::FORBIDDEN);
+  }
+
+  #[actix_web::test]
+  async fn it_should_be_reject_an_anonymous_request() {
+    assert_eq!(status(None).await, StatusCode::UNAUTHORIZED);
+  }
+}
@@ -0,0 +1,88 @@
+use std::{future::{ready, Ready}, rc::Rc};
+
+use actix_web::{
+  dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+  Error,
+};
+use futures_util::future::LocalBoxFuture;
+use uuid::Uuid;
+
+tokio::task_local! {
+  static TRACE_ID: String;
+}
+
+/// The id of the request being served, the same one a problem document quotes to the client.
+/// Outside a request, e.g. on the command line, a fresh one.
+pub fn current() -> String {
+  TRACE_ID.try_with(String::clone).unwrap_or_else(|_| new())
+}
+
+fn new() -> String {
+  Uuid::new_v4().simple().to_string()
+}
+
+/// Gives every request a trace id, so what a handler or repository logs can be matched
+/// to the error the client reports. Wrap the whole `App`.
+pub struct TraceId;
+
+impl<S, B> Transform<S, ServiceRequest> for TraceId
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type InitError = ();
+  type Transform = TraceIdMiddleware<S>;
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+      ready(Ok(TraceIdMiddleware { service: Rc::new(service) }))
+  }
+}
+
+pub struct TraceIdMiddleware<S> {
+  service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for TraceIdMiddleware<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+  forward_ready!(service);
+
+  fn call(&self, req: ServiceRequest) -> Self::Future {
+    let service = self.service.clone();
+
+    Box::pin(TRACE_ID.scope(new(), async move { service.call(req).await }))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use actix_web::{test, web, App, HttpResponse};
+
+  use super::*;
+
+  #[actix_web::test]
+  async fn it_should_be_keep_one_trace_id_for_the_whole_request() {
+    let app = test::init_service(
+      App::new()
+        .wrap(TraceId)
+        .route("/", web::get().to(|| async { HttpResponse::Ok().body(format!("{} {}", current(), current())) }))
+    ).await;
+
+    let body = test::call_and_read_body(&app, test::TestRequest::get().uri("/").to_request()).await;
+    let ids = std::str::from_utf8(&body).unwrap().split(' ').map(str::to_string).collect::<Vec<_>>();
+
+    assert_eq!(ids[0], ids[1]);
+    assert_ne!(ids[0], current());
+  }
+}
@@ -1,20 +1,201 @@
+#[cfg(any(test, feature = "test-support"))]
 use std::sync::Mutex;
 
+use std::fmt::{Display, Formatter};
+
 use async_trait::async_trait;
-use chrono::{NaiveDate};
 use entity::career;
-use sea_orm::{DatabaseConnection, Set, ActiveModelTrait, EntityTrait, QueryFilter, ColumnTrait, QueryOrder};
+use chrono::NaiveDate;
+use sea_orm::{DatabaseConnection, DbErr, Set, Unchanged, ActiveModelTrait, EntityTrait, QueryFilter, ColumnTrait, QueryOrder, QuerySelect, PaginatorTrait, Condition, Value};
+
+use crate::domain::career::entity::{CareerEntity, CareerPeriod, CompanyName, JobTitle};
 
-use crate::{domain::career::entity::CareerEntity, infrastructure::database::Database};
+use super::error::DbError;
+#[cfg(any(test, feature = "test-support"))]
+use super::error::in_memory_failure;
 
+#[derive(Debug)]
 pub enum InsertError {
   Conflict,
-  Unknown
+  /// the user does not exist
+  InvalidReference,
+  Unavailable,
+  Unknown(DbErr),
 }
 
+#[derive(Debug)]
 pub enum FetchError {
+  Unavailable,
+  Unknown(DbErr),
+}
+
+#[derive(Debug)]
+pub enum FetchOneError {
+  NotFound,
+  Unavailable,
+  Unknown(DbErr),
+}
+
+#[derive(Debug)]
+pub enum UpdateError {
   NotFound,
-  Unknown
+  Unavailable,
+  Unknown(DbErr),
+}
+
+impl Display for InsertError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      InsertError::Conflict => write!(f, "career already exists"),
+      InsertError::InvalidReference => write!(f, "unknown user"),
+      InsertError::Unavailable => write!(f, "database unavailable"),
+      InsertError::Unknown(e) => write!(f, "insert failed: {}", e),
+    }
+  }
+}
+
+impl std::error::Error for InsertError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      InsertError::Unknown(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl Display for FetchError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      FetchError::Unavailable => write!(f, "database unavailable"),
+      FetchError::Unknown(e) => write!(f, "fetch failed: {}", e),
+    }
+  }
+}
+
+impl std::error::Error for FetchError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      FetchError::Unknown(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl Display for FetchOneError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      FetchOneError::NotFound => write!(f, "career not found"),
+      FetchOneError::Unavailable => write!(f, "database unavailable"),
+      FetchOneError::Unknown(e) => write!(f, "fetch failed: {}", e),
+    }
+  }
+}
+
+impl std::error::Error for FetchOneError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      FetchOneError::Unknown(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl Display for UpdateError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      UpdateError::NotFound => write!(f, "career not found"),
+      UpdateError::Unavailable => write!(f, "database unavailable"),
+      UpdateError::Unknown(e) => write!(f, "update failed: {}", e),
+    }
+  }
+}
+
+impl std::error::Error for UpdateError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      UpdateError::Unknown(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<DbError> for InsertError {
+  fn from(e: DbError) -> Self {
+    match e {
+      DbError::Conflict(_) => InsertError::Conflict,
+      DbError::InvalidReference(_) => InsertError::InvalidReference,
+      DbError::Unavailable(_) => InsertError::Unavailable,
+      DbError::Unknown(e) => InsertError::Unknown(e),
+    }
+  }
+}
+
+impl From<DbError> for FetchError {
+  fn from(e: DbError) -> Self {
+    match e {
+      DbError::Unavailable(_) => FetchError::Unavailable,
+      DbError::Conflict(e) | DbError::InvalidReference(e) | DbError::Unknown(e) => FetchError::Unknown(e),
+    }
+  }
+}
+
+impl From<DbError> for FetchOneError {
+  fn from(e: DbError) -> Self {
+    match e {
+      DbError::Unavailable(_) => FetchOneError::Unavailable,
+      DbError::Conflict(e) | DbError::InvalidReference(e) | DbError::Unknown(e) => FetchOneError::Unknown(e),
+    }
+  }
+}
+
+impl From<DbError> for UpdateError {
+  fn from(e: DbError) -> Self {
+    match e {
+      DbError::Unavailable(_) => UpdateError::Unavailable,
+      DbError::Conflict(e) | DbError::InvalidReference(e) | DbError::Unknown(e) => UpdateError::Unknown(e),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CareerSort {
+  InAt,
+  /// current careers count as the latest
+  OutAt,
+  Company,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+  Asc,
+  Desc,
+}
+
+/// The sort key and id of the last row of the previous page.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cursor {
+  InAt(NaiveDate, i64),
+  OutAt(Option<NaiveDate>, i64),
+  Company(String, i64),
+}
+
+#[derive(Debug)]
+pub struct ListQuery {
+  pub sort: CareerSort,
+  pub direction: Direction,
+  /// only careers without an `out_at`
+  pub current_only: bool,
+  /// hidden careers are left out otherwise
+  pub include_hidden: bool,
+  pub after: Option<Cursor>,
+  pub limit: u64,
+}
+
+#[derive(Debug)]
+pub struct CareerPage {
+  pub careers: Vec<CareerEntity>,
+  /// rows matching the filter across every page
+  pub total: u64,
 }
 
 #[async_trait]
@@ -22,49 +203,95 @@ pub trait Repository: Send + Sync {
   async fn insert(
     &self,
     user_id: i64,
-    company: String,
-    job: String,
-    in_at: NaiveDate,
-    out_at: Option<NaiveDate>
+    company: CompanyName,
+    job: JobTitle,
+    period: CareerPeriod,
+    full_time: bool,
   ) -> Result<CareerEntity, InsertError>;
 
   async fn find_by_user_id(
     &self,
     user_id: i64
   ) -> Result<Vec<CareerEntity>, FetchError>;
+
+  /// One page of a user's careers; `find_by_user_id` when every row is needed.
+  async fn list_by_user_id(&self, user_id: i64, query: ListQuery) -> Result<CareerPage, FetchError>;
+
+  async fn find_by_id(&self, id: i64) -> Result<CareerEntity, FetchOneError>;
+
+  async fn update(
+    &self,
+    id: i64,
+    company: CompanyName,
+    job: JobTitle,
+    period: CareerPeriod,
+    full_time: bool,
+  ) -> Result<CareerEntity, UpdateError>;
+
+  async fn delete(&self, id: i64) -> Result<(), UpdateError>;
+
+  async fn set_hidden(&self, id: i64, hidden: bool) -> Result<CareerEntity, UpdateError>;
 }
 
+#[cfg(any(test, feature = "test-support"))]
 pub struct InMemoryRepository {
   error: bool,
   careers: Mutex<Vec<CareerEntity>>,
+  // stands in for the foreign key on `user_id`, any user exists when `None`
+  users: Option<Vec<i64>>,
 }
 
+#[cfg(any(test, feature = "test-support"))]
 impl InMemoryRepository {
   pub fn new() -> Self {
     let careers = Mutex::new(vec![]);
     Self {
       error: false,
       careers,
+      users: None,
+    }
+  }
+
+  pub fn with_error(self) -> Self {
+    Self {
+      error: true,
+      ..self
+    }
+  }
+
+  pub fn with_users(self, users: Vec<i64>) -> Self {
+    Self {
+      users: Some(users),
+      ..self
     }
   }
 }
 
+#[cfg(any(test, feature = "test-support"))]
 #[async_trait]
 impl Repository for InMemoryRepository {
   async fn insert(
     &self,
     user_id: i64,
-    company: String,
-    job: String,
-    in_at: NaiveDate,
-    out_at: Option<NaiveDate>,
+    company: CompanyName,
+    job: JobTitle,
+    period: CareerPeriod,
+    full_time: bool,
   ) -> Result<CareerEntity, InsertError> {
+    if self.error {
+      return Err(InsertError::Unknown(in_memory_failure()));
+    }
+    if self.users.as_ref().is_some_and(|users| !users.contains(&user_id)) {
+      return Err(InsertError::InvalidReference);
+    }
+
     let mut lock = match self.careers.lock() {
       Ok(lock) => lock,
-      _ => return Err(InsertError::Unknown)
+      _ => return Err(InsertError::Unknown(in_memory_failure()))
     };
 
-    let career = CareerEntity::new(user_id, company, job, in_at, out_at);
+    let id = lock.iter().map(|c| c.id).max().unwrap_or(0) + 1;
+    let career = CareerEntity::new(id, user_id, company, job, period, full_time);
 
     lock.push(career.clone());
 
@@ -75,15 +302,148 @@ impl Repository for InMemoryRepository {
     &self,
     user_id: i64,
   ) -> Result<Vec<CareerEntity>, FetchError> {
+    if self.error {
+      return Err(FetchError::Unknown(in_memory_failure()));
+    }
+
     let lock = match self.careers.lock() {
       Ok(lock) => lock,
-      _ => return Err(FetchError::Unknown)
+      _ => return Err(FetchError::Unknown(in_memory_failure()))
     };
 
     let careers = lock.iter().filter(|c| c.user_id == user_id).cloned().collect();
 
     Ok(careers)
   }
+
+  async fn list_by_user_id(&self, user_id: i64, query: ListQuery) -> Result<CareerPage, FetchError> {
+    if self.error {
+      return Err(FetchError::Unknown(in_memory_failure()));
+    }
+
+    let lock = match self.careers.lock() {
+      Ok(lock) => lock,
+      _ => return Err(FetchError::Unknown(in_memory_failure()))
+    };
+
+    let ListQuery { sort, direction, current_only, include_hidden, after, limit } = query;
+    // a current career sorts as if it ended last
+    let out_at = |c: &CareerEntity| c.out_at.unwrap_or(NaiveDate::MAX);
+    let compare = |a: &CareerEntity, b: &CareerEntity| {
+      let order = match sort {
+        CareerSort::InAt => a.in_at.cmp(&b.in_at),
+        CareerSort::OutAt => out_at(a).cmp(&out_at(b)),
+        CareerSort::Company => a.company.cmp(&b.company),
+      }.then(a.id.cmp(&b.id));
+
+      match direction {
+        Direction::Asc => order,
+        Direction::Desc => order.reverse(),
+      }
+    };
+
+    let mut careers = lock.iter()
+      .filter(|c| c.user_id == user_id && (!current_only || c.out_at.is_none()) && (include_hidden || !c.hidden))
+      .cloned()
+      .collect::<Vec<_>>();
+    let total = careers.len() as u64;
+    careers.sort_by(compare);
+    if let Some(after) = after {
+      let (in_at, out_at, company, id) = match after {
+        Cursor::InAt(in_at, id) => (in_at, None, String::new(), id),
+        Cursor::OutAt(out_at, id) => (NaiveDate::MIN, out_at, String::new(), id),
+        Cursor::Company(company, id) => (NaiveDate::MIN, None, company, id),
+      };
+      // a stand-in for the previous page's last row, only its sort key and id are compared
+      let last = CareerEntity { id, user_id, company, job: String::new(), in_at, out_at, full_time: false, hidden: false };
+      careers.retain(|c| compare(c, &last).is_gt());
+    }
+    careers.truncate(limit as usize);
+
+    Ok(CareerPage { careers, total })
+  }
+
+  async fn find_by_id(&self, id: i64) -> Result<CareerEntity, FetchOneError> {
+    if self.error {
+      return Err(FetchOneError::Unknown(in_memory_failure()));
+    }
+
+    let lock = match self.careers.lock() {
+      Ok(lock) => lock,
+      _ => return Err(FetchOneError::Unknown(in_memory_failure()))
+    };
+
+    match lock.iter().find(|c| c.id == id) {
+      Some(career) => Ok(career.clone()),
+      None => Err(FetchOneError::NotFound),
+    }
+  }
+
+  async fn update(
+    &self,
+    id: i64,
+    company: CompanyName,
+    job: JobTitle,
+    period: CareerPeriod,
+    full_time: bool,
+  ) -> Result<CareerEntity, UpdateError> {
+    if self.error {
+      return Err(UpdateError::Unknown(in_memory_failure()));
+    }
+
+    let mut lock = match self.careers.lock() {
+      Ok(lock) => lock,
+      _ => return Err(UpdateError::Unknown(in_memory_failure()))
+    };
+
+    match lock.iter_mut().find(|c| c.id == id) {
+      Some(career) => {
+        *career = CareerEntity { hidden: career.hidden, ..CareerEntity::new(id, career.user_id, company, job, period, full_time) };
+
+        Ok(career.clone())
+      },
+      None => Err(UpdateError::NotFound),
+    }
+  }
+
+  async fn delete(&self, id: i64) -> Result<(), UpdateError> {
+    if self.error {
+      return Err(UpdateError::Unknown(in_memory_failure()));
+    }
+
+    let mut lock = match self.careers.lock() {
+      Ok(lock) => lock,
+      _ => return Err(UpdateError::Unknown(in_memory_failure()))
+    };
+
+    match lock.iter().position(|c| c.id == id) {
+      Some(index) => {
+        lock.remove(index);
+        Ok(())
+      },
+      None => Err(UpdateError::NotFound),
+    }
+  }
+
+  async fn set_hidden(&self, id: i64, hidden: bool) -> Result<CareerEntity, UpdateError> {
+    if self.error {
+      return Err(UpdateError::Unknown(in_memory_failure()));
+    }
+
+    let mut lock = match self.careers.lock() {
+      Ok(lock) => lock,
+      _ => return Err(UpdateError::Unknown(in_memory_failure()))
+    };
+
+    match lock.iter_mut().find(|c| c.id == id) {
+      Some(career) => {
+        career.hidden = hidden;
+
+        Ok(career.clone())
+      },
+      None => Err(UpdateError::NotFound),
+    }
+  }
 }
 
 pub struct PgRepository {
@@ -91,11 +451,24 @@ pub struct PgRepository {
 }
 
 impl PgRepository {
-  pub async fn try_new() -> Self {
-    let pool = Database::establish_connection().await;
+  pub fn new(conn: DatabaseConnection) -> Self {
+    Self {
+      conn,
+    }
+  }
+}
 
+impl From<career::Model> for CareerEntity {
+  fn from(model: career::Model) -> Self {
     Self {
-      conn: pool,
+      id: model.id,
+      user_id: model.user_id,
+      company: model.company,
+      job: model.job,
+      in_at: model.in_at,
+      out_at: model.out_at,
+      full_time: model.full_time,
+      hidden: model.hidden,
     }
   }
 }
@@ -105,32 +478,28 @@ impl Repository for PgRepository {
   async fn insert(
     &self,
     user_id: i64,
-    company: String,
-    job: String,
-    in_at: NaiveDate,
-    out_at: Option<NaiveDate>,
+    company: CompanyName,
+    job: JobTitle,
+    period: CareerPeriod,
+    full_time: bool,
   ) -> Result<CareerEntity, InsertError> {
     let conn = &self.conn;
 
-    let career = CareerEntity::new(user_id, company, job, in_at, out_at);
-
     let career_model = career::ActiveModel {
-      user_id: Set(career.user_id),
-      company: Set(career.company.clone()),
-      job: Set(career.job.clone()),
-      in_at: Set(career.in_at),
-      out_at: Set(career.out_at),
+      user_id: Set(user_id),
+      company: Set(String::from(company)),
+      job: Set(String::from(job)),
+      in_at: Set(period.in_at()),
+      out_at: Set(period.out_at()),
+      full_time: Set(full_time),
       ..Default::default()
     };
 
     let res = career_model.insert(conn).await;
 
     match res {
-      Ok(_) => Ok(career),
-      Err(e) => {
-        println!("{:?}", e);
-        Err(InsertError::Conflict)
-      }
+      Ok(career) => Ok(CareerEntity::from(career)),
+      Err(e) => Err(InsertError::from(DbError::from(e))),
     }
   }
 
@@ -142,8 +511,120 @@ impl Repository for PgRepository {
       .order_by_desc(career::Column::InAt)
       .all(conn)
       .await {
-        Ok(careers) => Ok(careers.iter().map(|career| CareerEntity::new(career.user_id, career.company.clone(), career.job.clone(), career.in_at, career.out_at)).collect::<Vec<CareerEntity>>()),
-        Err(_) => Err(FetchError::NotFound),
+        Ok(careers) => Ok(careers.into_iter().map(CareerEntity::from).collect::<Vec<CareerEntity>>()),
+        Err(e) => Err(FetchError::from(DbError::from(e))),
       }
   }
+
+  async fn list_by_user_id(&self, user_id: i64, query: ListQuery) -> Result<CareerPage, FetchError> {
+    let ListQuery { sort, direction, current_only, include_hidden, after, limit } = query;
+
+    let mut condition = Condition::all().add(career::Column::UserId.eq(user_id));
+    if current_only {
+      condition = condition.add(career::Column::OutAt.is_null());
+    }
+    if !include_hidden {
+      condition = condition.add(career::Column::Hidden.eq(false));
+    }
+    let total = career::Entity::find()
+      .filter(condition.clone())
+      .count(&self.conn)
+      .await
+      .map_err(|e| FetchError::from(DbError::from(e)))?;
+
+    // keyset pagination like the user directory; postgres puts nulls last ascending and first
+    // descending, so a current career without `out_at` sorts as the latest
+    let after_key = |column: career::Column, value: Value, id: i64| match direction {
+      Direction::Asc => Condition::any().add(column.gt(value.clone())).add(column.eq(value).and(career::Column::Id.gt(id))),
+      Direction::Desc => Condition::any().add(column.lt(value.clone())).add(column.eq(value).and(career::Column::Id.lt(id))),
+    };
+    let after_current = |id: i64| career::Column::OutAt.is_null().and(match direction {
+      Direction::Asc => career::Column::Id.gt(id),
+      Direction::Desc => career::Column::Id.lt(id),
+    });
+    match after {
+      Some(Cursor::InAt(in_at, id)) => condition = condition.add(after_key(career::Column::InAt, in_at.into(), id)),
+      Some(Cursor::Company(company, id)) => condition = condition.add(after_key(career::Column::Company, company.into(), id)),
+      Some(Cursor::OutAt(Some(out_at), id)) => condition = condition.add(match direction {
+        Direction::Asc => after_key(career::Column::OutAt, out_at.into(), id).add(career::Column::OutAt.is_null()),
+        Direction::Desc => after_key(career::Column::OutAt, out_at.into(), id),
+      }),
+      Some(Cursor::OutAt(None, id)) => condition = condition.add(match direction {
+        Direction::Asc => Condition::any().add(after_current(id)),
+        Direction::Desc => Condition::any().add(after_current(id)).add(career::Column::OutAt.is_not_null()),
+      }),
+      None => {},
+    }
+
+    let column = match sort {
+      CareerSort::InAt => career::Column::InAt,
+      CareerSort::OutAt => career::Column::OutAt,
+      CareerSort::Company => career::Column::Company,
+    };
+    let select = career::Entity::find().filter(condition);
+    let select = match direction {
+      Direction::Asc => select.order_by_asc(column).order_by_asc(career::Column::Id),
+      Direction::Desc => select.order_by_desc(column).order_by_desc(career::Column::Id),
+    };
+
+    match select.limit(limit).all(&self.conn).await {
+      Ok(careers) => Ok(CareerPage { careers: careers.into_iter().map(CareerEntity::from).collect(), total }),
+      Err(e) => Err(FetchError::from(DbError::from(e))),
+    }
+  }
+
+  async fn find_by_id(&self, id: i64) -> Result<CareerEntity, FetchOneError> {
+    match career::Entity::find_by_id(id).one(&self.conn).await {
+      Ok(Some(career)) => Ok(CareerEntity::from(career)),
+      Ok(None) => Err(FetchOneError::NotFound),
+      Err(e) => Err(FetchOneError::from(DbError::from(e))),
+    }
+  }
+
+  async fn update(
+    &self,
+    id: i64,
+    company: CompanyName,
+    job: JobTitle,
+    period: CareerPeriod,
+    full_time: bool,
+  ) -> Result<CareerEntity, UpdateError> {
+    let career_model = career::ActiveModel {
+      id: Unchanged(id),
+      company: Set(String::from(company)),
+      job: Set(String::from(job)),
+      in_at: Set(period.in_at()),
+      out_at: Set(period.out_at()),
+      full_time: Set(full_time),
+      ..Default::default()
+    };
+
+    match career_model.update(&self.conn).await {
+      Ok(career) => Ok(CareerEntity::from(career)),
+      Err(DbErr::RecordNotFound(_)) => Err(UpdateError::NotFound),
+      Err(e) => Err(UpdateError::from(DbError::from(e))),
+    }
+  }
+
+  async fn delete(&self, id: i64) -> Result<(), UpdateError> {
+    match career::Entity::delete_by_id(id).exec(&self.conn).await {
+      Ok(res) if res.rows_affected == 1 => Ok(()),
+      Ok(_) => Err(UpdateError::NotFound),
+      Err(e) => Err(UpdateError::from(DbError::from(e))),
+    }
+  }
+
+  async fn set_hidden(&self, id: i64, hidden: bool) -> Result<CareerEntity, UpdateError> {
+    let career_model = career::ActiveModel {
+      id: Unchanged(id),
+      hidden: Set(hidden),
+      ..Default::default()
+    };
+
+    match career_model.update(&self.conn).await {
+      Ok(career) => Ok(CareerEntity::from(career)),
+      Err(DbErr::RecordNotFound(_)) => Err(UpdateError::NotFound),
+      Err(e) => Err(UpdateError::from(DbError::from(e))),
+    }
+  }
 }
\ No newline at end of file
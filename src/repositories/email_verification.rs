@@ -0,0 +1,188 @@
+#[cfg(any(test, feature = "test-support"))]
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::{FixedOffset, Utc};
+use entity::email_verification;
+use sea_orm::{DatabaseConnection, Set, ActiveModelTrait, EntityTrait, QueryFilter, ColumnTrait, Condition, prelude::DateTimeWithTimeZone};
+
+use crate::domain::user::entity::{EmailVerificationEntity, UserEmail, UserId};
+
+use super::error::DbError;
+
+#[derive(Debug)]
+pub enum InsertError {
+  Unavailable,
+  Unknown,
+}
+
+#[derive(Debug)]
+pub enum TakeError {
+  NotFound,
+  Unavailable,
+  Unknown,
+}
+
+impl From<DbError> for InsertError {
+  fn from(e: DbError) -> Self {
+    match e {
+      DbError::Unavailable(_) => InsertError::Unavailable,
+      _ => InsertError::Unknown,
+    }
+  }
+}
+
+impl From<DbError> for TakeError {
+  fn from(e: DbError) -> Self {
+    match e {
+      DbError::Unavailable(_) => TakeError::Unavailable,
+      _ => TakeError::Unknown,
+    }
+  }
+}
+
+#[async_trait]
+pub trait Repository: Send + Sync {
+  /// Starts a change, any earlier one of the same user stops working.
+  async fn insert(
+    &self,
+    user_id: UserId,
+    email: UserEmail,
+    token_hash: String,
+    expires_at: DateTimeWithTimeZone,
+  ) -> Result<(), InsertError>;
+
+  /// Removes and returns the change for `token_hash`, so each link works only once.
+  async fn take(&self, token_hash: &str) -> Result<EmailVerificationEntity, TakeError>;
+}
+
+#[cfg(any(test, feature = "test-support"))]
+pub struct InMemoryRepository {
+  error: bool,
+  // (token_hash, verification)
+  verifications: Mutex<Vec<(String, EmailVerificationEntity)>>,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl InMemoryRepository {
+  pub fn new() -> Self {
+    Self {
+      error: false,
+      verifications: Mutex::new(vec![]),
+    }
+  }
+
+  pub fn with_error(self) -> Self {
+    Self {
+      error: true,
+      ..self
+    }
+  }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+#[async_trait]
+impl Repository for InMemoryRepository {
+  async fn insert(&self, user_id: UserId, email: UserEmail, token_hash: String, expires_at: DateTimeWithTimeZone) -> Result<(), InsertError> {
+    if self.error {
+      return Err(InsertError::Unknown);
+    }
+
+    let mut lock = match self.verifications.lock() {
+      Ok(lock) => lock,
+      _ => return Err(InsertError::Unknown),
+    };
+
+    lock.retain(|(_, v)| v.user_id != i64::from(user_id));
+    lock.push((token_hash, EmailVerificationEntity { user_id: i64::from(user_id), email: String::from(email), expires_at }));
+
+    Ok(())
+  }
+
+  async fn take(&self, token_hash: &str) -> Result<EmailVerificationEntity, TakeError> {
+    if self.error {
+      return Err(TakeError::Unknown);
+    }
+
+    let mut lock = match self.verifications.lock() {
+      Ok(lock) => lock,
+      _ => return Err(TakeError::Unknown),
+    };
+
+    match lock.iter().position(|(hash, _)| hash == token_hash) {
+      Some(index) => Ok(lock.remove(index).1),
+      None => Err(TakeError::NotFound),
+    }
+  }
+}
+
+pub struct PgRepository {
+  conn: DatabaseConnection,
+}
+
+impl PgRepository {
+  pub fn new(conn: DatabaseConnection) -> Self {
+    Self {
+      conn,
+    }
+  }
+}
+
+impl From<email_verification::Model> for EmailVerificationEntity {
+  fn from(model: email_verification::Model) -> Self {
+    Self {
+      user_id: model.user_id,
+      email: model.email,
+      expires_at: model.expires_at,
+    }
+  }
+}
+
+#[async_trait]
+impl Repository for PgRepository {
+  async fn insert(&self, user_id: UserId, email: UserEmail, token_hash: String, expires_at: DateTimeWithTimeZone) -> Result<(), InsertError> {
+    let conn = &self.conn;
+    let now = Utc::now().with_timezone(&FixedOffset::east(9 * 3600));
+
+    // the user's previous link and everybody's expired ones
+    email_verification::Entity::delete_many()
+      .filter(
+        Condition::any()
+          .add(email_verification::Column::UserId.eq(i64::from(user_id)))
+          .add(email_verification::Column::ExpiresAt.lt(now))
+      )
+      .exec(conn)
+      .await
+      .map_err(|e| InsertError::from(DbError::from(e)))?;
+
+    let model = email_verification::ActiveModel {
+      token_hash: Set(token_hash),
+      user_id: Set(i64::from(user_id)),
+      email: Set(String::from(email)),
+      expires_at: Set(expires_at),
+      created_at: Set(now),
+    };
+
+    match model.insert(conn).await {
+      Ok(_) => Ok(()),
+      Err(e) => Err(InsertError::from(DbError::from(e))),
+    }
+  }
+
+  async fn take(&self, token_hash: &str) -> Result<EmailVerificationEntity, TakeError> {
+    let conn = &self.conn;
+
+    let verification = match email_verification::Entity::find_by_id(token_hash.to_string()).one(conn).await {
+      Ok(Some(verification)) => verification,
+      Ok(None) => return Err(TakeError::NotFound),
+      Err(e) => return Err(TakeError::from(DbError::from(e))),
+    };
+
+    // only the request that actually deletes the row may use it
+    match email_verification::Entity::delete_by_id(token_hash.to_string()).exec(conn).await {
+      Ok(res) if res.rows_affected == 1 => Ok(EmailVerificationEntity::from(verification)),
+      Ok(_) => Err(TakeError::NotFound),
+      Err(e) => Err(TakeError::from(DbError::from(e))),
+    }
+  }
+}
@@ -0,0 +1,89 @@
+use sea_orm::{DbErr, RuntimeErr};
+
+use crate::middleware::trace_id;
+
+// https://www.postgresql.org/docs/current/errcodes-appendix.html
+const UNIQUE_VIOLATION: &str = "23505";
+const FOREIGN_KEY_VIOLATION: &str = "23503";
+const QUERY_CANCELED: &str = "57014";
+const ADMIN_SHUTDOWN: &str = "57P01";
+const CRASH_SHUTDOWN: &str = "57P02";
+const CANNOT_CONNECT_NOW: &str = "57P03";
+const TOO_MANY_CONNECTIONS: &str = "53300";
+const CONNECTION_EXCEPTION_CLASS: &str = "08";
+
+/// What a failed statement means to a repository. Every `PgRepository` maps its
+/// `DbErr`s through here instead of guessing from the variant.
+#[derive(Debug)]
+pub enum DbError {
+  /// a unique constraint refused the row
+  Conflict(DbErr),
+  /// a foreign key points at a row that does not exist
+  InvalidReference(DbErr),
+  /// the database is unreachable, overloaded or timed out; worth retrying
+  Unavailable(DbErr),
+  Unknown(DbErr),
+}
+
+impl From<DbErr> for DbError {
+  fn from(e: DbErr) -> Self {
+    let error = classify(e);
+    match &error {
+      DbError::Unavailable(e) => log::warn!("[{}] database unavailable: {:?}", trace_id::current(), e),
+      DbError::Unknown(e) => log::error!("[{}] database error: {:?}", trace_id::current(), e),
+      DbError::Conflict(_) | DbError::InvalidReference(_) => {},
+    }
+
+    error
+  }
+}
+
+fn classify(e: DbErr) -> DbError {
+  let sqlx_error = match &e {
+    DbErr::ConnectionAcquire | DbErr::Conn(_) => return DbError::Unavailable(e),
+    DbErr::Exec(RuntimeErr::SqlxError(sqlx_error)) | DbErr::Query(RuntimeErr::SqlxError(sqlx_error)) => sqlx_error,
+    _ => return DbError::Unknown(e),
+  };
+
+  match sqlx_error {
+    sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed | sqlx::Error::Io(_) | sqlx::Error::Tls(_) | sqlx::Error::WorkerCrashed => DbError::Unavailable(e),
+    sqlx::Error::Database(db_error) => match db_error.code().as_deref() {
+      Some(UNIQUE_VIOLATION) => DbError::Conflict(e),
+      Some(FOREIGN_KEY_VIOLATION) => DbError::InvalidReference(e),
+      Some(QUERY_CANCELED | ADMIN_SHUTDOWN | CRASH_SHUTDOWN | CANNOT_CONNECT_NOW | TOO_MANY_CONNECTIONS) => DbError::Unavailable(e),
+      Some(code) if code.starts_with(CONNECTION_EXCEPTION_CLASS) => DbError::Unavailable(e),
+      _ => DbError::Unknown(e),
+    },
+    _ => DbError::Unknown(e),
+  }
+}
+
+/// Stands in for the source of errors the in-memory repositories are told to fail with.
+#[cfg(any(test, feature = "test-support"))]
+pub fn in_memory_failure() -> DbErr {
+  DbErr::Custom("in-memory repository failure".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_should_be_treat_a_busy_pool_as_retryable() {
+    assert!(matches!(DbError::from(DbErr::ConnectionAcquire), DbError::Unavailable(_)));
+    assert!(matches!(DbError::from(DbErr::Query(RuntimeErr::SqlxError(sqlx::Error::PoolTimedOut))), DbError::Unavailable(_)));
+    assert!(matches!(DbError::from(DbErr::Exec(RuntimeErr::SqlxError(sqlx::Error::Io(std::io::ErrorKind::ConnectionReset.into())))), DbError::Unavailable(_)));
+  }
+
+  #[test]
+  fn it_should_be_keep_the_source_of_unknown_errors() {
+    match DbError::from(DbErr::Query(RuntimeErr::SqlxError(sqlx::Error::RowNotFound))) {
+      DbError::Unknown(DbErr::Query(_)) => {},
+      _ => unreachable!(),
+    }
+    match DbError::from(DbErr::Custom("broken".to_string())) {
+      DbError::Unknown(DbErr::Custom(message)) => assert_eq!(message, "broken".to_string()),
+      _ => unreachable!(),
+    }
+  }
+}
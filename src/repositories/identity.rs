@@ -0,0 +1,113 @@
+#[cfg(any(test, feature = "test-support"))]
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+use chrono::{FixedOffset, Utc};
+use entity::user_identity;
+use sea_orm::{DatabaseConnection, DbBackend, Set, EntityTrait, ConnectionTrait, Statement, sea_query::OnConflict};
+
+use crate::middleware::trace_id;
+
+#[derive(Debug)]
+pub enum ResolveError {
+  Unknown,
+}
+
+/// Maps a provider account (`provider`, `subject`) to the local user id.
+#[async_trait]
+pub trait Repository: Send + Sync {
+  /// Returns the user id linked to the account, reserving a new one on first sign-in.
+  async fn resolve(&self, provider: &str, subject: &str) -> Result<i64, ResolveError>;
+}
+
+#[cfg(any(test, feature = "test-support"))]
+pub struct InMemoryRepository {
+  identities: Mutex<HashMap<(String, String), i64>>,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl InMemoryRepository {
+  pub fn new() -> Self {
+    Self {
+      identities: Mutex::new(HashMap::new()),
+    }
+  }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+#[async_trait]
+impl Repository for InMemoryRepository {
+  async fn resolve(&self, provider: &str, subject: &str) -> Result<i64, ResolveError> {
+    let mut lock = match self.identities.lock() {
+      Ok(lock) => lock,
+      _ => return Err(ResolveError::Unknown),
+    };
+
+    let next_id = lock.len() as i64 + 1;
+    Ok(*lock.entry((provider.to_string(), subject.to_string())).or_insert(next_id))
+  }
+}
+
+pub struct PgRepository {
+  conn: DatabaseConnection,
+}
+
+impl PgRepository {
+  pub fn new(conn: DatabaseConnection) -> Self {
+    Self {
+      conn,
+    }
+  }
+
+  async fn find(&self, provider: &str, subject: &str) -> Result<Option<i64>, ResolveError> {
+    match user_identity::Entity::find_by_id((provider.to_string(), subject.to_string())).one(&self.conn).await {
+      Ok(identity) => Ok(identity.map(|i| i.user_id)),
+      Err(e) => {
+        log::error!("[{}] {:?}", trace_id::current(), e);
+        Err(ResolveError::Unknown)
+      },
+    }
+  }
+}
+
+#[async_trait]
+impl Repository for PgRepository {
+  async fn resolve(&self, provider: &str, subject: &str) -> Result<i64, ResolveError> {
+    let conn = &self.conn;
+
+    if let Some(user_id) = self.find(provider, subject).await? {
+      return Ok(user_id);
+    }
+
+    let next_id = conn.query_one(Statement::from_string(
+      DbBackend::Postgres,
+      r#"SELECT nextval(pg_get_serial_sequence('"user"', 'id')) AS "id""#.to_owned(),
+    )).await;
+    let next_id = match next_id {
+      Ok(Some(row)) => row.try_get::<i64>("", "id").map_err(|_| ResolveError::Unknown)?,
+      Ok(None) => return Err(ResolveError::Unknown),
+      Err(e) => {
+        log::error!("[{}] {:?}", trace_id::current(), e);
+        return Err(ResolveError::Unknown);
+      },
+    };
+
+    let model = user_identity::ActiveModel {
+      provider: Set(provider.to_string()),
+      subject: Set(subject.to_string()),
+      user_id: Set(next_id),
+      created_at: Set(Utc::now().with_timezone(&FixedOffset::east(9 * 3600))),
+    };
+
+    // a concurrent first sign-in may have linked the account in between, its id wins
+    if let Err(e) = user_identity::Entity::insert(model)
+      .on_conflict(OnConflict::columns([user_identity::Column::Provider, user_identity::Column::Subject]).do_nothing().to_owned())
+      // nothing comes back when the row already exists, so there is nothing to return
+      .exec_without_returning(conn)
+      .await {
+        log::error!("[{}] {:?}", trace_id::current(), e);
+      }
+
+    self.find(provider, subject).await?.ok_or(ResolveError::Unknown)
+  }
+}
@@ -1,2 +1,11 @@
+pub mod error;
 pub mod user;
-pub mod career;
\ No newline at end of file
+pub mod career;
+pub mod refresh_token;
+pub mod revocation;
+pub mod pending_authorization;
+pub mod identity;
+pub mod role;
+pub mod personal_access_token;
+pub mod email_verification;
+pub mod search;
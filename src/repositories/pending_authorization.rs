@@ -0,0 +1,160 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+use chrono::{FixedOffset, Utc};
+use entity::pending_authorization;
+use sea_orm::{DatabaseConnection, Set, EntityTrait, QueryFilter, ColumnTrait, ActiveModelTrait};
+
+use crate::{domain::auth::entity::PendingAuthorizationEntity, middleware::trace_id};
+
+#[derive(Debug)]
+pub enum InsertError {
+  Unknown,
+}
+
+#[derive(Debug)]
+pub enum TakeError {
+  NotFound,
+  Unknown,
+}
+
+#[async_trait]
+pub trait Repository: Send + Sync {
+  async fn insert(&self, authorization: PendingAuthorizationEntity) -> Result<(), InsertError>;
+
+  /// Removes and returns the authorization for `state`, so each one can be completed only once.
+  async fn take(&self, state: &str) -> Result<PendingAuthorizationEntity, TakeError>;
+}
+
+pub struct InMemoryRepository {
+  error: bool,
+  authorizations: Mutex<HashMap<String, PendingAuthorizationEntity>>,
+}
+
+impl InMemoryRepository {
+  pub fn new() -> Self {
+    Self {
+      error: false,
+      authorizations: Mutex::new(HashMap::new()),
+    }
+  }
+
+  #[cfg(any(test, feature = "test-support"))]
+  pub fn with_error(self) -> Self {
+    Self {
+      error: true,
+      ..self
+    }
+  }
+}
+
+#[async_trait]
+impl Repository for InMemoryRepository {
+  async fn insert(&self, authorization: PendingAuthorizationEntity) -> Result<(), InsertError> {
+    if self.error {
+      return Err(InsertError::Unknown);
+    }
+
+    let mut lock = match self.authorizations.lock() {
+      Ok(lock) => lock,
+      _ => return Err(InsertError::Unknown),
+    };
+
+    // abandoned sign-ins never come back, drop them as new ones start
+    let now = Utc::now();
+    lock.retain(|_, a| a.expires_at > now);
+    lock.insert(authorization.state.clone(), authorization);
+
+    Ok(())
+  }
+
+  async fn take(&self, state: &str) -> Result<PendingAuthorizationEntity, TakeError> {
+    if self.error {
+      return Err(TakeError::Unknown);
+    }
+
+    let mut lock = match self.authorizations.lock() {
+      Ok(lock) => lock,
+      _ => return Err(TakeError::Unknown),
+    };
+
+    lock.remove(state).ok_or(TakeError::NotFound)
+  }
+}
+
+pub struct PgRepository {
+  conn: DatabaseConnection,
+}
+
+impl PgRepository {
+  pub fn new(conn: DatabaseConnection) -> Self {
+    Self {
+      conn,
+    }
+  }
+}
+
+impl From<pending_authorization::Model> for PendingAuthorizationEntity {
+  fn from(model: pending_authorization::Model) -> Self {
+    Self {
+      state: model.state,
+      pkce_verifier: model.pkce_verifier,
+      provider: model.provider,
+      expires_at: model.expires_at,
+    }
+  }
+}
+
+#[async_trait]
+impl Repository for PgRepository {
+  async fn insert(&self, authorization: PendingAuthorizationEntity) -> Result<(), InsertError> {
+    let conn = &self.conn;
+    let now = Utc::now().with_timezone(&FixedOffset::east(9 * 3600));
+
+    if let Err(e) = pending_authorization::Entity::delete_many()
+      .filter(pending_authorization::Column::ExpiresAt.lt(now))
+      .exec(conn)
+      .await {
+        log::error!("[{}] {:?}", trace_id::current(), e);
+      }
+
+    let model = pending_authorization::ActiveModel {
+      state: Set(authorization.state),
+      pkce_verifier: Set(authorization.pkce_verifier),
+      provider: Set(authorization.provider),
+      expires_at: Set(authorization.expires_at),
+      created_at: Set(now),
+    };
+
+    match model.insert(conn).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        log::error!("[{}] {:?}", trace_id::current(), e);
+        Err(InsertError::Unknown)
+      },
+    }
+  }
+
+  async fn take(&self, state: &str) -> Result<PendingAuthorizationEntity, TakeError> {
+    let conn = &self.conn;
+
+    let authorization = match pending_authorization::Entity::find_by_id(state.to_string()).one(conn).await {
+      Ok(Some(authorization)) => authorization,
+      Ok(None) => return Err(TakeError::NotFound),
+      Err(e) => {
+        log::error!("[{}] {:?}", trace_id::current(), e);
+        return Err(TakeError::Unknown);
+      },
+    };
+
+    // only the request that actually deletes the row may use it
+    match pending_authorization::Entity::delete_by_id(state.to_string()).exec(conn).await {
+      Ok(res) if res.rows_affected == 1 => Ok(PendingAuthorizationEntity::from(authorization)),
+      Ok(_) => Err(TakeError::NotFound),
+      Err(e) => {
+        log::error!("[{}] {:?}", trace_id::current(), e);
+        Err(TakeError::Unknown)
+      },
+    }
+  }
+}
@@ -0,0 +1,315 @@
+#[cfg(any(test, feature = "test-support"))]
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::{Duration, FixedOffset, Utc};
+use entity::personal_access_token;
+use sea_orm::{DatabaseConnection, Set, ActiveModelTrait, EntityTrait, QueryFilter, QueryOrder, ColumnTrait, Condition, prelude::DateTimeWithTimeZone, sea_query::Expr};
+
+use crate::{domain::auth::entity::PersonalAccessTokenEntity, middleware::trace_id};
+
+use super::error::DbError;
+
+#[derive(Debug)]
+pub enum InsertError {
+  /// the user already has a token with that name
+  Conflict,
+  Unknown,
+}
+
+#[derive(Debug)]
+pub enum FetchError {
+  Unknown,
+}
+
+#[derive(Debug)]
+pub enum FetchOneError {
+  NotFound,
+  Unknown,
+}
+
+#[derive(Debug)]
+pub enum UpdateError {
+  Unknown,
+}
+
+#[async_trait]
+pub trait Repository: Send + Sync {
+  async fn insert(
+    &self,
+    user_id: i64,
+    name: String,
+    token_hash: String,
+    scopes: Vec<String>,
+    expires_at: Option<DateTimeWithTimeZone>,
+  ) -> Result<PersonalAccessTokenEntity, InsertError>;
+
+  /// Tokens of the user, newest first.
+  async fn list_by_user(&self, user_id: i64) -> Result<Vec<PersonalAccessTokenEntity>, FetchError>;
+
+  async fn find_by_hash(&self, token_hash: &str) -> Result<PersonalAccessTokenEntity, FetchOneError>;
+
+  /// Returns `false` when the user has no token with that id.
+  async fn delete(&self, id: i64, user_id: i64) -> Result<bool, UpdateError>;
+
+  /// Deletes every token of the user.
+  async fn delete_by_user(&self, user_id: i64) -> Result<(), UpdateError>;
+
+  /// Records a use. Writes at most once a minute per token, scripts may call in a loop.
+  async fn touch(&self, id: i64) -> Result<(), UpdateError>;
+}
+
+fn now() -> DateTimeWithTimeZone {
+  Utc::now().with_timezone(&FixedOffset::east(9 * 3600))
+}
+
+#[cfg(any(test, feature = "test-support"))]
+pub struct InMemoryRepository {
+  error: bool,
+  // (token_hash, token)
+  tokens: Mutex<Vec<(String, PersonalAccessTokenEntity)>>,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl InMemoryRepository {
+  pub fn new() -> Self {
+    Self {
+      error: false,
+      tokens: Mutex::new(vec![]),
+    }
+  }
+
+  pub fn with_error(self) -> Self {
+    Self {
+      error: true,
+      ..self
+    }
+  }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+#[async_trait]
+impl Repository for InMemoryRepository {
+  async fn insert(
+    &self,
+    user_id: i64,
+    name: String,
+    token_hash: String,
+    scopes: Vec<String>,
+    expires_at: Option<DateTimeWithTimeZone>,
+  ) -> Result<PersonalAccessTokenEntity, InsertError> {
+    if self.error {
+      return Err(InsertError::Unknown);
+    }
+
+    let mut lock = self.tokens.lock().map_err(|_| InsertError::Unknown)?;
+
+    if lock.iter().any(|(hash, token)| *hash == token_hash || (token.user_id == user_id && token.name == name)) {
+      return Err(InsertError::Conflict);
+    }
+
+    let token = PersonalAccessTokenEntity {
+      id: lock.iter().map(|(_, token)| token.id).max().unwrap_or(0) + 1,
+      user_id,
+      name,
+      scopes,
+      expires_at,
+      last_used_at: None,
+      created_at: now(),
+    };
+    lock.push((token_hash, token.clone()));
+
+    Ok(token)
+  }
+
+  async fn list_by_user(&self, user_id: i64) -> Result<Vec<PersonalAccessTokenEntity>, FetchError> {
+    if self.error {
+      return Err(FetchError::Unknown);
+    }
+
+    let lock = self.tokens.lock().map_err(|_| FetchError::Unknown)?;
+
+    Ok(lock.iter().rev().filter(|(_, token)| token.user_id == user_id).map(|(_, token)| token.clone()).collect())
+  }
+
+  async fn find_by_hash(&self, token_hash: &str) -> Result<PersonalAccessTokenEntity, FetchOneError> {
+    if self.error {
+      return Err(FetchOneError::Unknown);
+    }
+
+    let lock = self.tokens.lock().map_err(|_| FetchOneError::Unknown)?;
+
+    match lock.iter().find(|(hash, _)| hash == token_hash) {
+      Some((_, token)) => Ok(token.clone()),
+      None => Err(FetchOneError::NotFound),
+    }
+  }
+
+  async fn delete(&self, id: i64, user_id: i64) -> Result<bool, UpdateError> {
+    if self.error {
+      return Err(UpdateError::Unknown);
+    }
+
+    let mut lock = self.tokens.lock().map_err(|_| UpdateError::Unknown)?;
+    let before = lock.len();
+    lock.retain(|(_, token)| !(token.id == id && token.user_id == user_id));
+
+    Ok(lock.len() != before)
+  }
+
+  async fn delete_by_user(&self, user_id: i64) -> Result<(), UpdateError> {
+    if self.error {
+      return Err(UpdateError::Unknown);
+    }
+
+    let mut lock = self.tokens.lock().map_err(|_| UpdateError::Unknown)?;
+    lock.retain(|(_, token)| token.user_id != user_id);
+
+    Ok(())
+  }
+
+  async fn touch(&self, id: i64) -> Result<(), UpdateError> {
+    let mut lock = self.tokens.lock().map_err(|_| UpdateError::Unknown)?;
+
+    if let Some((_, token)) = lock.iter_mut().find(|(_, token)| token.id == id) {
+      token.last_used_at = Some(now());
+    }
+
+    Ok(())
+  }
+}
+
+pub struct PgRepository {
+  conn: DatabaseConnection,
+}
+
+impl PgRepository {
+  pub fn new(conn: DatabaseConnection) -> Self {
+    Self {
+      conn,
+    }
+  }
+}
+
+impl From<personal_access_token::Model> for PersonalAccessTokenEntity {
+  fn from(model: personal_access_token::Model) -> Self {
+    Self {
+      id: model.id,
+      user_id: model.user_id,
+      name: model.name,
+      scopes: model.scopes.split_whitespace().map(str::to_string).collect(),
+      expires_at: model.expires_at,
+      last_used_at: model.last_used_at,
+      created_at: model.created_at,
+    }
+  }
+}
+
+#[async_trait]
+impl Repository for PgRepository {
+  async fn insert(
+    &self,
+    user_id: i64,
+    name: String,
+    token_hash: String,
+    scopes: Vec<String>,
+    expires_at: Option<DateTimeWithTimeZone>,
+  ) -> Result<PersonalAccessTokenEntity, InsertError> {
+    let conn = &self.conn;
+
+    let token_model = personal_access_token::ActiveModel {
+      user_id: Set(user_id),
+      name: Set(name),
+      token_hash: Set(token_hash),
+      scopes: Set(scopes.join(" ")),
+      expires_at: Set(expires_at),
+      last_used_at: Set(None),
+      created_at: Set(now()),
+      ..Default::default()
+    };
+
+    match token_model.insert(conn).await {
+      Ok(token) => Ok(PersonalAccessTokenEntity::from(token)),
+      Err(e) => match DbError::from(e) {
+        DbError::Conflict(_) => Err(InsertError::Conflict),
+        _ => Err(InsertError::Unknown),
+      },
+    }
+  }
+
+  async fn list_by_user(&self, user_id: i64) -> Result<Vec<PersonalAccessTokenEntity>, FetchError> {
+    match personal_access_token::Entity::find()
+      .filter(personal_access_token::Column::UserId.eq(user_id))
+      .order_by_desc(personal_access_token::Column::Id)
+      .all(&self.conn)
+      .await {
+        Ok(tokens) => Ok(tokens.into_iter().map(PersonalAccessTokenEntity::from).collect()),
+        Err(e) => {
+          log::error!("[{}] {:?}", trace_id::current(), e);
+          Err(FetchError::Unknown)
+        },
+      }
+  }
+
+  async fn find_by_hash(&self, token_hash: &str) -> Result<PersonalAccessTokenEntity, FetchOneError> {
+    match personal_access_token::Entity::find()
+      .filter(personal_access_token::Column::TokenHash.eq(token_hash))
+      .one(&self.conn)
+      .await {
+        Ok(Some(token)) => Ok(PersonalAccessTokenEntity::from(token)),
+        Ok(None) => Err(FetchOneError::NotFound),
+        Err(e) => {
+          log::error!("[{}] {:?}", trace_id::current(), e);
+          Err(FetchOneError::Unknown)
+        },
+      }
+  }
+
+  async fn delete(&self, id: i64, user_id: i64) -> Result<bool, UpdateError> {
+    match personal_access_token::Entity::delete_many()
+      .filter(personal_access_token::Column::Id.eq(id))
+      .filter(personal_access_token::Column::UserId.eq(user_id))
+      .exec(&self.conn)
+      .await {
+        Ok(res) => Ok(res.rows_affected == 1),
+        Err(e) => {
+          log::error!("[{}] {:?}", trace_id::current(), e);
+          Err(UpdateError::Unknown)
+        },
+      }
+  }
+
+  async fn delete_by_user(&self, user_id: i64) -> Result<(), UpdateError> {
+    match personal_access_token::Entity::delete_many()
+      .filter(personal_access_token::Column::UserId.eq(user_id))
+      .exec(&self.conn)
+      .await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+          log::error!("[{}] {:?}", trace_id::current(), e);
+          Err(UpdateError::Unknown)
+        },
+      }
+  }
+
+  async fn touch(&self, id: i64) -> Result<(), UpdateError> {
+    let now = now();
+
+    match personal_access_token::Entity::update_many()
+      .col_expr(personal_access_token::Column::LastUsedAt, Expr::value(now))
+      .filter(personal_access_token::Column::Id.eq(id))
+      .filter(
+        Condition::any()
+          .add(personal_access_token::Column::LastUsedAt.is_null())
+          .add(personal_access_token::Column::LastUsedAt.lt(now - Duration::minutes(1)))
+      )
+      .exec(&self.conn)
+      .await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+          log::error!("[{}] {:?}", trace_id::current(), e);
+          Err(UpdateError::Unknown)
+        },
+      }
+  }
+}
@@ -0,0 +1,294 @@
+#[cfg(any(test, feature = "test-support"))]
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::{FixedOffset, Utc};
+use entity::refresh_token;
+use sea_orm::{DatabaseConnection, Set, ActiveModelTrait, EntityTrait, QueryFilter, ColumnTrait, prelude::{DateTimeWithTimeZone, Uuid}, sea_query::Expr};
+
+use crate::{domain::auth::entity::RefreshTokenEntity, middleware::trace_id};
+
+use super::error::DbError;
+
+#[derive(Debug)]
+pub enum InsertError {
+  Conflict,
+  Unknown,
+}
+
+#[derive(Debug)]
+pub enum FetchOneError {
+  NotFound,
+  Unknown,
+}
+
+#[derive(Debug)]
+pub enum UpdateError {
+  Unknown,
+}
+
+#[async_trait]
+pub trait Repository: Send + Sync {
+  async fn insert(
+    &self,
+    user_id: i64,
+    family_id: Uuid,
+    token_hash: String,
+    expires_at: DateTimeWithTimeZone,
+  ) -> Result<RefreshTokenEntity, InsertError>;
+
+  async fn find_by_hash(&self, token_hash: &str) -> Result<RefreshTokenEntity, FetchOneError>;
+
+  /// Returns `false` when the token had already been used, so concurrent refreshes only win once.
+  async fn mark_used(&self, id: i64) -> Result<bool, UpdateError>;
+
+  async fn revoke_family(&self, family_id: Uuid) -> Result<(), UpdateError>;
+
+  async fn revoke_by_user(&self, user_id: i64) -> Result<(), UpdateError>;
+}
+
+fn now() -> DateTimeWithTimeZone {
+  Utc::now().with_timezone(&FixedOffset::east(9 * 3600))
+}
+
+#[cfg(any(test, feature = "test-support"))]
+pub struct InMemoryRepository {
+  error: bool,
+  // (token_hash, token)
+  tokens: Mutex<Vec<(String, RefreshTokenEntity)>>,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl InMemoryRepository {
+  pub fn new() -> Self {
+    Self {
+      error: false,
+      tokens: Mutex::new(vec![]),
+    }
+  }
+
+  pub fn with_error(self) -> Self {
+    Self {
+      error: true,
+      ..self
+    }
+  }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+#[async_trait]
+impl Repository for InMemoryRepository {
+  async fn insert(
+    &self,
+    user_id: i64,
+    family_id: Uuid,
+    token_hash: String,
+    expires_at: DateTimeWithTimeZone,
+  ) -> Result<RefreshTokenEntity, InsertError> {
+    if self.error {
+      return Err(InsertError::Unknown);
+    }
+
+    let mut lock = match self.tokens.lock() {
+      Ok(lock) => lock,
+      _ => return Err(InsertError::Unknown),
+    };
+
+    if lock.iter().any(|(hash, _)| *hash == token_hash) {
+      return Err(InsertError::Conflict);
+    }
+
+    let token = RefreshTokenEntity {
+      id: lock.len() as i64 + 1,
+      user_id,
+      family_id,
+      expires_at,
+      used_at: None,
+      revoked_at: None,
+    };
+    lock.push((token_hash, token.clone()));
+
+    Ok(token)
+  }
+
+  async fn find_by_hash(&self, token_hash: &str) -> Result<RefreshTokenEntity, FetchOneError> {
+    if self.error {
+      return Err(FetchOneError::Unknown);
+    }
+
+    let lock = match self.tokens.lock() {
+      Ok(lock) => lock,
+      _ => return Err(FetchOneError::Unknown),
+    };
+
+    match lock.iter().find(|(hash, _)| hash == token_hash) {
+      Some((_, token)) => Ok(token.clone()),
+      None => Err(FetchOneError::NotFound),
+    }
+  }
+
+  async fn mark_used(&self, id: i64) -> Result<bool, UpdateError> {
+    let mut lock = match self.tokens.lock() {
+      Ok(lock) => lock,
+      _ => return Err(UpdateError::Unknown),
+    };
+
+    match lock.iter_mut().find(|(_, token)| token.id == id && token.used_at.is_none()) {
+      Some((_, token)) => {
+        token.used_at = Some(now());
+        Ok(true)
+      },
+      None => Ok(false),
+    }
+  }
+
+  async fn revoke_family(&self, family_id: Uuid) -> Result<(), UpdateError> {
+    let mut lock = match self.tokens.lock() {
+      Ok(lock) => lock,
+      _ => return Err(UpdateError::Unknown),
+    };
+
+    lock.iter_mut()
+      .filter(|(_, token)| token.family_id == family_id && token.revoked_at.is_none())
+      .for_each(|(_, token)| token.revoked_at = Some(now()));
+
+    Ok(())
+  }
+
+  async fn revoke_by_user(&self, user_id: i64) -> Result<(), UpdateError> {
+    let mut lock = match self.tokens.lock() {
+      Ok(lock) => lock,
+      _ => return Err(UpdateError::Unknown),
+    };
+
+    lock.iter_mut()
+      .filter(|(_, token)| token.user_id == user_id && token.revoked_at.is_none())
+      .for_each(|(_, token)| token.revoked_at = Some(now()));
+
+    Ok(())
+  }
+}
+
+pub struct PgRepository {
+  conn: DatabaseConnection,
+}
+
+impl PgRepository {
+  pub fn new(conn: DatabaseConnection) -> Self {
+    Self {
+      conn,
+    }
+  }
+}
+
+impl From<refresh_token::Model> for RefreshTokenEntity {
+  fn from(model: refresh_token::Model) -> Self {
+    Self {
+      id: model.id,
+      user_id: model.user_id,
+      family_id: model.family_id,
+      expires_at: model.expires_at,
+      used_at: model.used_at,
+      revoked_at: model.revoked_at,
+    }
+  }
+}
+
+#[async_trait]
+impl Repository for PgRepository {
+  async fn insert(
+    &self,
+    user_id: i64,
+    family_id: Uuid,
+    token_hash: String,
+    expires_at: DateTimeWithTimeZone,
+  ) -> Result<RefreshTokenEntity, InsertError> {
+    let conn = &self.conn;
+
+    let token_model = refresh_token::ActiveModel {
+      user_id: Set(user_id),
+      family_id: Set(family_id),
+      token_hash: Set(token_hash),
+      expires_at: Set(expires_at),
+      used_at: Set(None),
+      revoked_at: Set(None),
+      created_at: Set(now()),
+      ..Default::default()
+    };
+
+    match token_model.insert(conn).await {
+      Ok(token) => Ok(RefreshTokenEntity::from(token)),
+      Err(e) => match DbError::from(e) {
+        DbError::Conflict(_) => Err(InsertError::Conflict),
+        _ => Err(InsertError::Unknown),
+      },
+    }
+  }
+
+  async fn find_by_hash(&self, token_hash: &str) -> Result<RefreshTokenEntity, FetchOneError> {
+    let conn = &self.conn;
+
+    match refresh_token::Entity::find()
+      .filter(refresh_token::Column::TokenHash.eq(token_hash))
+      .one(conn)
+      .await {
+        Ok(Some(token)) => Ok(RefreshTokenEntity::from(token)),
+        Ok(None) => Err(FetchOneError::NotFound),
+        Err(e) => {
+          log::error!("[{}] {:?}", trace_id::current(), e);
+          Err(FetchOneError::Unknown)
+        },
+      }
+  }
+
+  async fn mark_used(&self, id: i64) -> Result<bool, UpdateError> {
+    let conn = &self.conn;
+
+    match refresh_token::Entity::update_many()
+      .col_expr(refresh_token::Column::UsedAt, Expr::value(now()))
+      .filter(refresh_token::Column::Id.eq(id))
+      .filter(refresh_token::Column::UsedAt.is_null())
+      .exec(conn)
+      .await {
+        Ok(res) => Ok(res.rows_affected == 1),
+        Err(e) => {
+          log::error!("[{}] {:?}", trace_id::current(), e);
+          Err(UpdateError::Unknown)
+        },
+      }
+  }
+
+  async fn revoke_family(&self, family_id: Uuid) -> Result<(), UpdateError> {
+    let conn = &self.conn;
+
+    match refresh_token::Entity::update_many()
+      .col_expr(refresh_token::Column::RevokedAt, Expr::value(now()))
+      .filter(refresh_token::Column::FamilyId.eq(family_id))
+      .filter(refresh_token::Column::RevokedAt.is_null())
+      .exec(conn)
+      .await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+          log::error!("[{}] {:?}", trace_id::current(), e);
+          Err(UpdateError::Unknown)
+        },
+      }
+  }
+
+  async fn revoke_by_user(&self, user_id: i64) -> Result<(), UpdateError> {
+    let conn = &self.conn;
+
+    match refresh_token::Entity::update_many()
+      .col_expr(refresh_token::Column::RevokedAt, Expr::value(now()))
+      .filter(refresh_token::Column::UserId.eq(user_id))
+      .filter(refresh_token::Column::RevokedAt.is_null())
+      .exec(conn)
+      .await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+          log::error!("[{}] {:?}", trace_id::current(), e);
+          Err(UpdateError::Unknown)
+        },
+      }
+  }
+}
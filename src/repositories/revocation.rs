@@ -0,0 +1,184 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use entity::{revoked_token, user_revocation};
+use sea_orm::{DatabaseConnection, Set, EntityTrait, QueryFilter, ColumnTrait, prelude::{DateTimeWithTimeZone, Uuid}, sea_query::OnConflict};
+
+use crate::middleware::trace_id;
+
+#[derive(Debug)]
+pub enum RevokeError {
+  Unknown,
+}
+
+#[derive(Debug)]
+pub enum FetchError {
+  Unknown,
+}
+
+/// Access tokens are stateless, so the ones that must stop working before their
+/// `exp` are recorded here and consulted on every authenticated request.
+#[async_trait]
+pub trait Repository: Send + Sync {
+  /// Revokes a single access token until it would have expired anyway.
+  async fn revoke(&self, jti: Uuid, user_id: i64, expires_at: DateTimeWithTimeZone) -> Result<(), RevokeError>;
+
+  /// Revokes every access token of the user issued before `revoked_before`.
+  async fn revoke_user(&self, user_id: i64, revoked_before: DateTimeWithTimeZone) -> Result<(), RevokeError>;
+
+  async fn is_revoked(&self, jti: Uuid, user_id: i64, issued_at: DateTimeWithTimeZone) -> Result<bool, FetchError>;
+}
+
+pub struct InMemoryRepository {
+  error: bool,
+  // jti => expires_at
+  tokens: Mutex<HashMap<Uuid, DateTimeWithTimeZone>>,
+  // user_id => revoked_before
+  users: Mutex<HashMap<i64, DateTimeWithTimeZone>>,
+}
+
+impl InMemoryRepository {
+  pub fn new() -> Self {
+    Self {
+      error: false,
+      tokens: Mutex::new(HashMap::new()),
+      users: Mutex::new(HashMap::new()),
+    }
+  }
+
+  #[cfg(any(test, feature = "test-support"))]
+  pub fn with_error(self) -> Self {
+    Self {
+      error: true,
+      ..self
+    }
+  }
+}
+
+#[async_trait]
+impl Repository for InMemoryRepository {
+  async fn revoke(&self, jti: Uuid, _user_id: i64, expires_at: DateTimeWithTimeZone) -> Result<(), RevokeError> {
+    if self.error {
+      return Err(RevokeError::Unknown);
+    }
+
+    let mut lock = match self.tokens.lock() {
+      Ok(lock) => lock,
+      _ => return Err(RevokeError::Unknown),
+    };
+
+    let now = Utc::now();
+    lock.retain(|_, expires_at| *expires_at > now);
+    lock.insert(jti, expires_at);
+
+    Ok(())
+  }
+
+  async fn revoke_user(&self, user_id: i64, revoked_before: DateTimeWithTimeZone) -> Result<(), RevokeError> {
+    if self.error {
+      return Err(RevokeError::Unknown);
+    }
+
+    let mut lock = match self.users.lock() {
+      Ok(lock) => lock,
+      _ => return Err(RevokeError::Unknown),
+    };
+    lock.insert(user_id, revoked_before);
+
+    Ok(())
+  }
+
+  async fn is_revoked(&self, jti: Uuid, user_id: i64, issued_at: DateTimeWithTimeZone) -> Result<bool, FetchError> {
+    if self.error {
+      return Err(FetchError::Unknown);
+    }
+
+    let (tokens, users) = match (self.tokens.lock(), self.users.lock()) {
+      (Ok(tokens), Ok(users)) => (tokens, users),
+      _ => return Err(FetchError::Unknown),
+    };
+
+    Ok(tokens.contains_key(&jti) || users.get(&user_id).is_some_and(|before| issued_at < *before))
+  }
+}
+
+pub struct PgRepository {
+  conn: DatabaseConnection,
+}
+
+impl PgRepository {
+  pub fn new(conn: DatabaseConnection) -> Self {
+    Self {
+      conn,
+    }
+  }
+}
+
+#[async_trait]
+impl Repository for PgRepository {
+  async fn revoke(&self, jti: Uuid, user_id: i64, expires_at: DateTimeWithTimeZone) -> Result<(), RevokeError> {
+    let conn = &self.conn;
+
+    // rows past their expiry can never match a valid token again
+    if let Err(e) = revoked_token::Entity::delete_many()
+      .filter(revoked_token::Column::ExpiresAt.lt(Utc::now()))
+      .exec(conn)
+      .await {
+        log::error!("[{}] {:?}", trace_id::current(), e);
+      }
+
+    let model = revoked_token::ActiveModel {
+      jti: Set(jti),
+      user_id: Set(user_id),
+      expires_at: Set(expires_at),
+    };
+
+    match revoked_token::Entity::insert(model)
+      .on_conflict(OnConflict::column(revoked_token::Column::Jti).update_column(revoked_token::Column::ExpiresAt).to_owned())
+      .exec(conn)
+      .await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+          log::error!("[{}] {:?}", trace_id::current(), e);
+          Err(RevokeError::Unknown)
+        },
+      }
+  }
+
+  async fn revoke_user(&self, user_id: i64, revoked_before: DateTimeWithTimeZone) -> Result<(), RevokeError> {
+    let conn = &self.conn;
+
+    let model = user_revocation::ActiveModel {
+      user_id: Set(user_id),
+      revoked_before: Set(revoked_before),
+    };
+
+    match user_revocation::Entity::insert(model)
+      .on_conflict(OnConflict::column(user_revocation::Column::UserId).update_column(user_revocation::Column::RevokedBefore).to_owned())
+      .exec(conn)
+      .await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+          log::error!("[{}] {:?}", trace_id::current(), e);
+          Err(RevokeError::Unknown)
+        },
+      }
+  }
+
+  async fn is_revoked(&self, jti: Uuid, user_id: i64, issued_at: DateTimeWithTimeZone) -> Result<bool, FetchError> {
+    let conn = &self.conn;
+
+    let token = revoked_token::Entity::find_by_id(jti).one(conn).await;
+    let user = user_revocation::Entity::find_by_id(user_id).one(conn).await;
+
+    match (token, user) {
+      (Ok(token), Ok(user)) => Ok(token.is_some() || user.is_some_and(|u| issued_at < u.revoked_before)),
+      (Err(e), _) | (_, Err(e)) => {
+        log::error!("[{}] {:?}", trace_id::current(), e);
+        Err(FetchError::Unknown)
+      },
+    }
+  }
+}
+
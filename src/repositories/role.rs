@@ -0,0 +1,202 @@
+#[cfg(any(test, feature = "test-support"))]
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::{FixedOffset, Utc};
+use entity::{role, role_permission, user, user_role};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set, sea_query::OnConflict};
+
+use crate::{domain::auth::role::Grants, middleware::trace_id};
+#[cfg(any(test, feature = "test-support"))]
+use crate::domain::auth::role::DEFAULT_ROLES;
+
+#[derive(Debug)]
+pub enum FetchError {
+  Unknown,
+}
+
+#[derive(Debug)]
+pub enum AssignError {
+  UnknownUser,
+  UnknownRole,
+  Unknown,
+}
+
+#[async_trait]
+pub trait Repository: Send + Sync {
+  /// Roles of the user and every permission they add up to, both sorted.
+  async fn grants(&self, user_id: i64) -> Result<Grants, FetchError>;
+
+  /// Gives the user the role, doing nothing if they already have it.
+  async fn assign(&self, user_id: i64, role: &str) -> Result<(), AssignError>;
+
+  /// Takes the role away, returns whether the user had it.
+  async fn unassign(&self, user_id: i64, role: &str) -> Result<bool, AssignError>;
+}
+
+fn sorted(mut names: Vec<String>) -> Vec<String> {
+  names.sort();
+  names.dedup();
+  names
+}
+
+/// Knows the `DEFAULT_ROLES`; any user id is accepted.
+#[cfg(any(test, feature = "test-support"))]
+pub struct InMemoryRepository {
+  error: bool,
+  assignments: Mutex<Vec<(i64, String)>>,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl InMemoryRepository {
+  pub fn new() -> Self {
+    Self {
+      error: false,
+      assignments: Mutex::new(vec![]),
+    }
+  }
+
+  pub fn with_error(self) -> Self {
+    Self {
+      error: true,
+      ..self
+    }
+  }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+#[async_trait]
+impl Repository for InMemoryRepository {
+  async fn grants(&self, user_id: i64) -> Result<Grants, FetchError> {
+    if self.error {
+      return Err(FetchError::Unknown);
+    }
+
+    let lock = self.assignments.lock().map_err(|_| FetchError::Unknown)?;
+    let roles: Vec<String> = lock.iter().filter(|(id, _)| *id == user_id).map(|(_, role)| role.clone()).collect();
+    let permissions = DEFAULT_ROLES.iter()
+      .filter(|(name, _)| roles.iter().any(|role| role == name))
+      .flat_map(|(_, permissions)| permissions.iter().map(|p| p.to_string()))
+      .collect();
+
+    Ok(Grants { roles: sorted(roles), permissions: sorted(permissions) })
+  }
+
+  async fn assign(&self, user_id: i64, role: &str) -> Result<(), AssignError> {
+    if self.error {
+      return Err(AssignError::Unknown);
+    }
+    if !DEFAULT_ROLES.iter().any(|(name, _)| *name == role) {
+      return Err(AssignError::UnknownRole);
+    }
+
+    let mut lock = self.assignments.lock().map_err(|_| AssignError::Unknown)?;
+    if !lock.iter().any(|(id, name)| *id == user_id && name == role) {
+      lock.push((user_id, role.to_string()));
+    }
+
+    Ok(())
+  }
+
+  async fn unassign(&self, user_id: i64, role: &str) -> Result<bool, AssignError> {
+    if self.error {
+      return Err(AssignError::Unknown);
+    }
+
+    let mut lock = self.assignments.lock().map_err(|_| AssignError::Unknown)?;
+    let before = lock.len();
+    lock.retain(|(id, name)| !(*id == user_id && name == role));
+
+    Ok(lock.len() != before)
+  }
+}
+
+pub struct PgRepository {
+  conn: DatabaseConnection,
+}
+
+impl PgRepository {
+  pub fn new(conn: DatabaseConnection) -> Self {
+    Self {
+      conn,
+    }
+  }
+}
+
+#[async_trait]
+impl Repository for PgRepository {
+  async fn grants(&self, user_id: i64) -> Result<Grants, FetchError> {
+    let conn = &self.conn;
+
+    let roles: Vec<String> = match user_role::Entity::find().filter(user_role::Column::UserId.eq(user_id)).all(conn).await {
+      Ok(rows) => rows.into_iter().map(|row| row.role).collect(),
+      Err(e) => {
+        log::error!("[{}] {:?}", trace_id::current(), e);
+        return Err(FetchError::Unknown);
+      },
+    };
+    if roles.is_empty() {
+      return Ok(Grants::default());
+    }
+
+    let permissions = match role_permission::Entity::find().filter(role_permission::Column::Role.is_in(roles.clone())).all(conn).await {
+      Ok(rows) => rows.into_iter().map(|row| row.permission).collect(),
+      Err(e) => {
+        log::error!("[{}] {:?}", trace_id::current(), e);
+        return Err(FetchError::Unknown);
+      },
+    };
+
+    Ok(Grants { roles: sorted(roles), permissions: sorted(permissions) })
+  }
+
+  async fn assign(&self, user_id: i64, role: &str) -> Result<(), AssignError> {
+    let conn = &self.conn;
+
+    match role::Entity::find_by_id(role.to_string()).one(conn).await {
+      Ok(Some(_)) => {},
+      Ok(None) => return Err(AssignError::UnknownRole),
+      Err(e) => {
+        log::error!("[{}] {:?}", trace_id::current(), e);
+        return Err(AssignError::Unknown);
+      },
+    }
+    match user::Entity::find_by_id(user_id).one(conn).await {
+      Ok(Some(_)) => {},
+      Ok(None) => return Err(AssignError::UnknownUser),
+      Err(e) => {
+        log::error!("[{}] {:?}", trace_id::current(), e);
+        return Err(AssignError::Unknown);
+      },
+    }
+
+    let model = user_role::ActiveModel {
+      user_id: Set(user_id),
+      role: Set(role.to_string()),
+      created_at: Set(Utc::now().with_timezone(&FixedOffset::east(9 * 3600))),
+    };
+
+    let res = user_role::Entity::insert(model)
+      .on_conflict(OnConflict::columns([user_role::Column::UserId, user_role::Column::Role]).do_nothing().to_owned())
+      .exec_without_returning(conn)
+      .await;
+
+    match res {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        log::error!("[{}] {:?}", trace_id::current(), e);
+        Err(AssignError::Unknown)
+      },
+    }
+  }
+
+  async fn unassign(&self, user_id: i64, role: &str) -> Result<bool, AssignError> {
+    match user_role::Entity::delete_by_id((user_id, role.to_string())).exec(&self.conn).await {
+      Ok(res) => Ok(res.rows_affected > 0),
+      Err(e) => {
+        log::error!("[{}] {:?}", trace_id::current(), e);
+        Err(AssignError::Unknown)
+      },
+    }
+  }
+}
@@ -0,0 +1,145 @@
+use std::fmt::{Display, Formatter};
+
+use async_trait::async_trait;
+use entity::prelude::User;
+use sea_orm::{DatabaseConnection, DbBackend, DbErr, EntityTrait, Statement};
+
+#[cfg(any(test, feature = "test-support"))]
+use crate::domain::career::entity::CareerEntity;
+use crate::domain::{search::entity::SearchTerms, user::entity::UserEntity};
+
+use super::error::DbError;
+#[cfg(any(test, feature = "test-support"))]
+use super::error::in_memory_failure;
+
+#[derive(Debug)]
+pub enum SearchError {
+  Unavailable,
+  Unknown(DbErr),
+}
+
+impl Display for SearchError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      SearchError::Unavailable => write!(f, "database unavailable"),
+      SearchError::Unknown(e) => write!(f, "search failed: {}", e),
+    }
+  }
+}
+
+impl std::error::Error for SearchError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      SearchError::Unknown(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<DbError> for SearchError {
+  fn from(e: DbError) -> Self {
+    match e {
+      DbError::Unavailable(_) => SearchError::Unavailable,
+      DbError::Conflict(e) | DbError::InvalidReference(e) | DbError::Unknown(e) => SearchError::Unknown(e),
+    }
+  }
+}
+
+#[async_trait]
+pub trait Repository: Send + Sync + 'static {
+  /// Users whose name, login or any career's company or job matches a term, best match first.
+  async fn search(&self, terms: SearchTerms, limit: u64) -> Result<Vec<UserEntity>, SearchError>;
+}
+
+/// Token matching over plain lists, ranks by how many terms a user matched, their own fields counting double.
+#[cfg(any(test, feature = "test-support"))]
+pub struct InMemoryRepository {
+  error: bool,
+  users: Vec<UserEntity>,
+  careers: Vec<CareerEntity>,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl InMemoryRepository {
+  pub fn new(users: Vec<UserEntity>, careers: Vec<CareerEntity>) -> Self {
+    Self {
+      error: false,
+      users,
+      careers,
+    }
+  }
+
+  pub fn with_error(self) -> Self {
+    Self {
+      error: true,
+      ..self
+    }
+  }
+
+  fn score(&self, terms: &SearchTerms, user: &UserEntity) -> usize {
+    let careers = self.careers.iter().filter(|career| career.user_id == user.id && !career.hidden).collect::<Vec<_>>();
+
+    terms.terms().iter().map(|term| {
+      if SearchTerms::matches(term, &user.name) || SearchTerms::matches(term, &user.login) {
+        2
+      } else if careers.iter().any(|career| SearchTerms::matches(term, &career.company) || SearchTerms::matches(term, &career.job)) {
+        1
+      } else {
+        0
+      }
+    }).sum()
+  }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+#[async_trait]
+impl Repository for InMemoryRepository {
+  async fn search(&self, terms: SearchTerms, limit: u64) -> Result<Vec<UserEntity>, SearchError> {
+    if self.error {
+      return Err(SearchError::Unknown(in_memory_failure()));
+    }
+
+    let mut hits = self.users.iter()
+      .map(|user| (self.score(&terms, user), user))
+      .filter(|(score, _)| *score > 0)
+      .collect::<Vec<_>>();
+    hits.sort_by(|(a, a_user), (b, b_user)| b.cmp(a).then(a_user.id.cmp(&b_user.id)));
+
+    Ok(hits.into_iter().take(limit as usize).map(|(_, user)| user.clone()).collect())
+  }
+}
+
+pub struct PgRepository {
+  conn: DatabaseConnection,
+}
+
+impl PgRepository {
+  pub fn new(conn: DatabaseConnection) -> Self {
+    Self { conn }
+  }
+}
+
+// ranks on the generated `search_vector` columns; a user's own match counts on top of every matching
+// career, hidden careers match nothing
+const SEARCH: &str = r#"
+  SELECT "user".* FROM "user"
+  LEFT JOIN "career" ON "career"."user_id" = "user"."id" AND NOT "career"."hidden"
+    AND "career"."search_vector" @@ to_tsquery('simple', $1)
+  WHERE "user"."search_vector" @@ to_tsquery('simple', $1) OR "career"."id" IS NOT NULL
+  GROUP BY "user"."id"
+  ORDER BY ts_rank("user"."search_vector", to_tsquery('simple', $1)) * 2
+    + COALESCE(SUM(ts_rank("career"."search_vector", to_tsquery('simple', $1))), 0) DESC, "user"."id"
+  LIMIT $2
+"#;
+
+#[async_trait]
+impl Repository for PgRepository {
+  async fn search(&self, terms: SearchTerms, limit: u64) -> Result<Vec<UserEntity>, SearchError> {
+    let statement = Statement::from_sql_and_values(DbBackend::Postgres, SEARCH, vec![terms.to_tsquery().into(), (limit as i64).into()]);
+
+    match User::find().from_raw_sql(statement).all(&self.conn).await {
+      Ok(users) => Ok(users.into_iter().map(UserEntity::from).collect()),
+      Err(e) => Err(SearchError::from(DbError::from(e))),
+    }
+  }
+}
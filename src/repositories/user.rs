@@ -1,63 +1,263 @@
+#[cfg(any(test, feature = "test-support"))]
 use std::sync::Mutex;
 
+use std::fmt::{Display, Formatter};
+
 use async_trait::async_trait;
-use entity::user;
+use entity::{career, user, user_login_history};
 use entity::user::Entity as User;
-use sea_orm::DatabaseConnection;
+use chrono::{FixedOffset, Utc};
+use sea_orm::{Condition, DatabaseConnection, DbErr, QueryFilter, QueryOrder, QuerySelect, prelude::{DateTimeWithTimeZone, Json}, sea_query::{Expr, Func, OnConflict, Query}};
 use sea_orm::{entity::*};
 
-use crate::{domain::{user::entity::{UserId, UserEntity, UserName, UserLogin, UserAvatar}}, infrastructure::database::{Database}};
+use crate::{domain::user::entity::{UserId, UserEntity, UserName, UserLogin, UserAvatar, UserEmail, ContactChannels}, middleware::trace_id};
+#[cfg(any(test, feature = "test-support"))]
+use crate::domain::career::entity::CareerEntity;
+
+use super::error::DbError;
+#[cfg(any(test, feature = "test-support"))]
+use super::error::in_memory_failure;
 
 #[derive(Debug)]
 pub enum InsertError {
+  /// another user has the login
   Conflict,
-  Unknown,
+  /// the database could not be reached, the insert may be retried
+  Unavailable,
+  Unknown(DbErr),
 }
 
+#[derive(Debug)]
 pub enum UpdateError {
-  Unknown,
+  Unavailable,
+  Unknown(DbErr),
+}
+
+#[derive(Debug)]
+pub enum FetchError {
+  Unavailable,
+  Unknown(DbErr),
 }
 
+#[derive(Debug)]
 pub enum FetchOneError {
   NotFound,
-  Unknown,
+  /// says nothing about whether the user exists
+  Unavailable,
+  Unknown(DbErr),
+}
+
+impl Display for InsertError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      InsertError::Conflict => write!(f, "login taken by another user"),
+      InsertError::Unavailable => write!(f, "database unavailable"),
+      InsertError::Unknown(e) => write!(f, "insert failed: {}", e),
+    }
+  }
+}
+
+impl std::error::Error for InsertError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      InsertError::Unknown(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl Display for UpdateError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      UpdateError::Unavailable => write!(f, "database unavailable"),
+      UpdateError::Unknown(e) => write!(f, "update failed: {}", e),
+    }
+  }
+}
+
+impl std::error::Error for UpdateError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      UpdateError::Unknown(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl Display for FetchError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      FetchError::Unavailable => write!(f, "database unavailable"),
+      FetchError::Unknown(e) => write!(f, "fetch failed: {}", e),
+    }
+  }
+}
+
+impl std::error::Error for FetchError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      FetchError::Unknown(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl Display for FetchOneError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      FetchOneError::NotFound => write!(f, "user not found"),
+      FetchOneError::Unavailable => write!(f, "database unavailable"),
+      FetchOneError::Unknown(e) => write!(f, "fetch failed: {}", e),
+    }
+  }
+}
+
+impl std::error::Error for FetchOneError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      FetchOneError::Unknown(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<DbError> for InsertError {
+  fn from(e: DbError) -> Self {
+    match e {
+      DbError::Conflict(_) => InsertError::Conflict,
+      DbError::Unavailable(_) => InsertError::Unavailable,
+      DbError::InvalidReference(e) | DbError::Unknown(e) => InsertError::Unknown(e),
+    }
+  }
+}
+
+impl From<DbError> for UpdateError {
+  fn from(e: DbError) -> Self {
+    match e {
+      DbError::Unavailable(_) => UpdateError::Unavailable,
+      DbError::Conflict(e) | DbError::InvalidReference(e) | DbError::Unknown(e) => UpdateError::Unknown(e),
+    }
+  }
+}
+
+impl From<DbError> for FetchError {
+  fn from(e: DbError) -> Self {
+    match e {
+      DbError::Unavailable(_) => FetchError::Unavailable,
+      DbError::Conflict(e) | DbError::InvalidReference(e) | DbError::Unknown(e) => FetchError::Unknown(e),
+    }
+  }
+}
+
+impl From<DbError> for FetchOneError {
+  fn from(e: DbError) -> Self {
+    match e {
+      DbError::Unavailable(_) => FetchOneError::Unavailable,
+      DbError::Conflict(e) | DbError::InvalidReference(e) | DbError::Unknown(e) => FetchOneError::Unknown(e),
+    }
+  }
+}
+
+/// Order of `list`, ties are broken by id.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UserSort {
+  /// newest first
+  CreatedAt,
+  /// alphabetical
+  Name,
+}
+
+/// The last user of the previous page, in the terms of the sort.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cursor {
+  CreatedAt(DateTimeWithTimeZone, i64),
+  Name(String, i64),
+}
+
+/// Every filter narrows the result, text filters ignore case.
+#[derive(Debug, Clone, Default)]
+pub struct UserFilter {
+  /// has a career, current or past, at the company
+  pub company: Option<String>,
+  /// has a current career with the job title
+  pub job: Option<String>,
+  pub has_email: Option<bool>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ListQuery {
+  pub sort: UserSort,
+  pub filter: UserFilter,
+  pub after: Option<Cursor>,
+  pub limit: u64,
 }
 
 #[async_trait]
 pub trait Repository: Send + Sync {
-  async fn insert(
+  async fn update(
     &self,
     id: UserId,
-    login: UserLogin,
     name: UserName,
     avatar_url: UserAvatar,
-  ) -> Result<UserEntity, InsertError>;
+  ) -> Result<UserEntity, UpdateError>;
 
-  async fn update(
+  /// Sets an address the user has proven to own.
+  async fn update_email(&self, id: UserId, email: UserEmail) -> Result<UserEntity, UpdateError>;
+
+  /// Replaces every contact channel of the user.
+  async fn update_channels(&self, id: UserId, channels: ContactChannels) -> Result<UserEntity, UpdateError>;
+
+  async fn fetch_one(&self, id: UserId) -> Result<UserEntity, FetchOneError>;
+
+  /// At most `limit` users in the order of `sort`, starting after `after`.
+  async fn list(&self, query: ListQuery) -> Result<Vec<UserEntity>, FetchError>;
+
+  /// Finds the user by login ignoring case, or by a login the user had before.
+  async fn fetch_by_login(&self, login: UserLogin) -> Result<UserEntity, FetchOneError>;
+
+  /// Creates the user on a first sign-in, otherwise takes over the login, name and avatar
+  /// the provider has now. Either way the sign-in time is recorded, in one statement.
+  /// The provider's email only fills in a missing one, an address the user changed stays.
+  /// A changed login keeps the old one around for `fetch_by_login`.
+  async fn upsert_from_provider(
     &self,
     id: UserId,
+    login: UserLogin,
     name: UserName,
     avatar_url: UserAvatar,
-  ) -> Result<UserEntity, UpdateError>;
-
-  async fn fetch_one(&self, id: UserId) -> Result<UserEntity, FetchOneError>;
+    email: Option<UserEmail>,
+  ) -> Result<UserEntity, InsertError>;
 }
 
+#[cfg(any(test, feature = "test-support"))]
 pub struct InMemoryRepository {
   error: bool,
   users: Mutex<Vec<UserEntity>>,
+  // (lowercased old login, user id)
+  old_logins: Mutex<Vec<(String, i64)>>,
+  // what the company and job filters of `list` look at
+  careers: Vec<CareerEntity>,
 }
 
+#[cfg(any(test, feature = "test-support"))]
 impl InMemoryRepository {
   pub fn _new() -> Self {
     let users = Mutex::new(vec![]);
     Self {
       error: false,
       users,
+      old_logins: Mutex::new(vec![]),
+      careers: vec![],
+    }
+  }
+
+  pub fn with_careers(self, careers: Vec<CareerEntity>) -> Self {
+    Self {
+      careers,
+      ..self
     }
   }
 
-  #[cfg(test)]
   pub fn with_error(self) -> Self {
     Self {
       error: true,
@@ -66,71 +266,204 @@ impl InMemoryRepository {
   }
 }
 
+#[cfg(any(test, feature = "test-support"))]
 #[async_trait]
 impl Repository for InMemoryRepository {
-  async fn insert(
-    &self,
-    id: UserId,
-    login: UserLogin,
-    name: UserName,
-    avatar_url: UserAvatar,
-  ) -> Result<UserEntity, InsertError> {
+  async fn fetch_one(&self, id: UserId) -> Result<UserEntity, FetchOneError> {
+    if self.error {
+      return Err(FetchOneError::Unknown(in_memory_failure()));
+    }
+
+    let lock = match self.users.lock() {
+      Ok(lock) => lock,
+      _ => return Err(FetchOneError::Unknown(in_memory_failure())),
+    };
+
+    match lock.iter().find(|user| user.id == i64::from(id)) {
+      Some(user) => Ok(user.clone()),
+      None => Err(FetchOneError::NotFound),
+    }
+  }
+
+  async fn list(&self, query: ListQuery) -> Result<Vec<UserEntity>, FetchError> {
+    if self.error {
+      return Err(FetchError::Unknown(in_memory_failure()));
+    }
+
+    let lock = match self.users.lock() {
+      Ok(lock) => lock,
+      _ => return Err(FetchError::Unknown(in_memory_failure())),
+    };
+
+    let ListQuery { sort, filter, after, limit } = query;
+    let has_career = |user: &UserEntity, matches: &dyn Fn(&CareerEntity) -> bool| self.careers.iter().any(|c| c.user_id == user.id && !c.hidden && matches(c));
+    let mut users = lock.iter()
+      .filter(|user| filter.has_email.is_none_or(|has_email| user.email.is_some() == has_email))
+      .filter(|user| filter.company.as_ref().is_none_or(|company| has_career(user, &|c| c.company.eq_ignore_ascii_case(company))))
+      .filter(|user| filter.job.as_ref().is_none_or(|job| has_career(user, &|c| c.out_at.is_none() && c.job.eq_ignore_ascii_case(job))))
+      .filter(|user| match &after {
+        Some(Cursor::CreatedAt(created_at, id)) => (user.created_at, user.id) < (*created_at, *id),
+        Some(Cursor::Name(name, id)) => (&user.name, user.id) > (name, *id),
+        None => true,
+      })
+      .cloned()
+      .collect::<Vec<_>>();
+
+    match sort {
+      UserSort::CreatedAt => users.sort_by_key(|user| std::cmp::Reverse((user.created_at, user.id))),
+      UserSort::Name => users.sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id))),
+    }
+    users.truncate(limit as usize);
+
+    Ok(users)
+  }
+
+  async fn fetch_by_login(&self, login: UserLogin) -> Result<UserEntity, FetchOneError> {
     if self.error {
-      return Err(InsertError::Unknown);
+      return Err(FetchOneError::Unknown(in_memory_failure()));
+    }
+
+    let login = String::from(login).to_lowercase();
+    let users = self.users.lock().unwrap();
+    let user = users.iter().find(|user| user.login.to_lowercase() == login).or_else(|| {
+      let old_logins = self.old_logins.lock().unwrap();
+      let (_, id) = old_logins.iter().find(|(old, _)| *old == login)?;
+      users.iter().find(|user| user.id == *id)
+    });
+
+    user.cloned().ok_or(FetchOneError::NotFound)
+  }
+
+  async fn update(&self, id: UserId, name: UserName, avatar_url: UserAvatar) -> Result<UserEntity, UpdateError> {
+    if self.error {
+      return Err(UpdateError::Unknown(in_memory_failure()));
     }
 
     let mut lock = match self.users.lock() {
       Ok(lock) => lock,
-      _ => return Err(InsertError::Unknown),
+      _ => return Err(UpdateError::Unknown(in_memory_failure())),
     };
 
-    if lock.iter().any(|user| user.id == i64::from(id)) {
-      return Err(InsertError::Conflict);
+    match lock.iter_mut().find(|user| user.id == i64::from(id)) {
+      Some(user) => {
+        user.name = String::from(name);
+        user.avatar_url = String::from(avatar_url);
+
+        Ok(user.clone())
+      },
+      None => Err(UpdateError::Unknown(in_memory_failure()))
     }
+  }
 
-    let user = UserEntity::new(
-      id,
-      login,
-      name,
-      avatar_url,
-    );
-    lock.push(user.clone());
+  async fn update_email(&self, id: UserId, email: UserEmail) -> Result<UserEntity, UpdateError> {
+    if self.error {
+      return Err(UpdateError::Unknown(in_memory_failure()));
+    }
 
-    Ok(user)
+    let mut lock = match self.users.lock() {
+      Ok(lock) => lock,
+      _ => return Err(UpdateError::Unknown(in_memory_failure())),
+    };
+
+    match lock.iter_mut().find(|user| user.id == i64::from(id)) {
+      Some(user) => {
+        user.email = Some(String::from(email));
+
+        Ok(user.clone())
+      },
+      None => Err(UpdateError::Unknown(in_memory_failure()))
+    }
   }
 
-  async fn fetch_one(&self, id: UserId) -> Result<UserEntity, FetchOneError> {
+  async fn update_channels(&self, id: UserId, channels: ContactChannels) -> Result<UserEntity, UpdateError> {
     if self.error {
-      return Err(FetchOneError::Unknown);
+      return Err(UpdateError::Unknown(in_memory_failure()));
     }
 
-    let lock = match self.users.lock() {
+    let mut lock = match self.users.lock() {
       Ok(lock) => lock,
-      _ => return Err(FetchOneError::Unknown),
+      _ => return Err(UpdateError::Unknown(in_memory_failure())),
     };
 
-    match lock.iter().find(|user| user.id == i64::from(id)) {
-      Some(user) => Ok(user.clone()),
-      None => Err(FetchOneError::NotFound),
+    match lock.iter_mut().find(|user| user.id == i64::from(id)) {
+      Some(user) => {
+        user.channels = channels;
+
+        Ok(user.clone())
+      },
+      None => Err(UpdateError::Unknown(in_memory_failure()))
     }
   }
 
-  async fn update(&self, id: UserId, name: UserName, avatar_url: UserAvatar) -> Result<UserEntity, UpdateError> {
+  async fn upsert_from_provider(&self, id: UserId, login: UserLogin, name: UserName, avatar_url: UserAvatar, email: Option<UserEmail>) -> Result<UserEntity, InsertError> {
+    if self.error {
+      return Err(InsertError::Unknown(in_memory_failure()));
+    }
+
     let mut lock = match self.users.lock() {
       Ok(lock) => lock,
-      _ => todo!()
+      _ => return Err(InsertError::Unknown(in_memory_failure())),
     };
 
-    match lock.iter_mut().map(|user| {
-      if user.id == i64::from(id) {
-        user.name = String::from(name.clone());
-        user.avatar_url = String::from(avatar_url.clone());
-      };
+    let now = Utc::now().with_timezone(&FixedOffset::east(9 * 3600));
+    let signed_in = UserEntity {
+      email: email.map(String::from),
+      last_signed_in_at: Some(now),
+      ..UserEntity::new(id, login, name, avatar_url)
+    };
+    if lock.iter().any(|user| user.id != signed_in.id && user.login.eq_ignore_ascii_case(&signed_in.login)) {
+      return Err(InsertError::Conflict);
+    }
 
-      user
-    }).find(|user| user.id == i64::from(id)) {
-      Some(user) => Ok(user.clone()),
-      None => Err(UpdateError::Unknown)
+    match lock.iter_mut().find(|user| user.id == signed_in.id) {
+      Some(user) => {
+        if !user.login.eq_ignore_ascii_case(&signed_in.login) {
+          let mut old_logins = self.old_logins.lock().unwrap();
+          old_logins.retain(|(old, _)| *old != user.login.to_lowercase());
+          old_logins.push((user.login.to_lowercase(), user.id));
+        }
+        user.login = signed_in.login;
+        user.name = signed_in.name;
+        user.avatar_url = signed_in.avatar_url;
+        user.email = user.email.take().or(signed_in.email);
+        user.updated_at = now;
+        user.last_signed_in_at = Some(now);
+
+        Ok(user.clone())
+      },
+      None => {
+        lock.push(signed_in.clone());
+
+        Ok(signed_in)
+      },
+    }
+  }
+}
+
+// a column that does not parse is logged and read as no channels, it never fails the profile
+fn channels(json: Option<Json>) -> ContactChannels {
+  match json.map(serde_json::from_value::<ContactChannels>) {
+    Some(Ok(channels)) => channels,
+    Some(Err(e)) => {
+      log::warn!("[{}] unreadable contact channels: {:?}", trace_id::current(), e);
+      ContactChannels::default()
+    },
+    None => ContactChannels::default(),
+  }
+}
+
+impl From<user::Model> for UserEntity {
+  fn from(model: user::Model) -> Self {
+    Self {
+      id: model.id,
+      login: model.login,
+      name: model.name,
+      avatar_url: model.avatar_url,
+      email: model.email,
+      created_at: model.created_at,
+      updated_at: model.updated_at,
+      last_signed_in_at: model.last_signed_in_at,
+      channels: channels(model.channel),
     }
   }
 }
@@ -140,67 +473,104 @@ pub struct PgRepository {
 }
 
 impl PgRepository {
-  pub async fn try_new() -> Self {
-    let pool = Database::establish_connection().await;
-    
+  pub fn new(conn: DatabaseConnection) -> Self {
     Self {
-      conn: pool,
+      conn,
     }
   }
 }
 
 #[async_trait]
 impl Repository for PgRepository {
-  async fn insert(
-    &self,
-    id: UserId,
-    login: UserLogin,
-    name: UserName,
-    avatar_url: UserAvatar,
-  ) -> Result<UserEntity, InsertError> {
+  async fn fetch_one(&self, user_id: UserId) -> Result<UserEntity, FetchOneError> {
     let conn = &self.conn;
+  
+    match User::find_by_id(i64::from(user_id)).one(conn).await {
+      Ok(user) => match user {
+        Some(user) => Ok(UserEntity::from(user)),
+        None => Err(FetchOneError::NotFound),
+      },
+      Err(e) => Err(FetchOneError::from(DbError::from(e))),
+    }
+  }
 
-    let user = UserEntity::new(id, login, name, avatar_url);
+  async fn list(&self, query: ListQuery) -> Result<Vec<UserEntity>, FetchError> {
+    let ListQuery { sort, filter, after, limit } = query;
+    let lower = |column: career::Column| Expr::expr(Func::lower(Expr::col((career::Entity, column))));
 
-    let user_model = user::ActiveModel {
-      id: Set(user.id),
-      login: Set(user.login.clone()),
-      name: Set(user.name.clone()),
-      avatar_url: Set(user.avatar_url.clone()),
-      email: Set(None),
-      created_at: Set(user.created_at),
-      updated_at: Set(user.updated_at),
-      channel: Set(None),
+    let mut condition = Condition::all();
+    if let Some(has_email) = filter.has_email {
+      condition = condition.add(if has_email { user::Column::Email.is_not_null() } else { user::Column::Email.is_null() });
+    }
+    if let Some(company) = filter.company {
+      condition = condition.add(user::Column::Id.in_subquery(
+        Query::select()
+          .column(career::Column::UserId)
+          .from(career::Entity)
+          .and_where(lower(career::Column::Company).eq(company.to_lowercase()))
+          .and_where(Expr::col((career::Entity, career::Column::Hidden)).eq(false))
+          .to_owned()
+      ));
+    }
+    if let Some(job) = filter.job {
+      condition = condition.add(user::Column::Id.in_subquery(
+        Query::select()
+          .column(career::Column::UserId)
+          .from(career::Entity)
+          .and_where(lower(career::Column::Job).eq(job.to_lowercase()))
+          .and_where(Expr::col((career::Entity, career::Column::OutAt)).is_null())
+          .and_where(Expr::col((career::Entity, career::Column::Hidden)).eq(false))
+          .to_owned()
+      ));
+    }
+    // keyset pagination: the page starts right after the cursor's row, however many rows come before it
+    match after {
+      Some(Cursor::CreatedAt(created_at, id)) => condition = condition.add(
+        Condition::any()
+          .add(user::Column::CreatedAt.lt(created_at))
+          .add(user::Column::CreatedAt.eq(created_at).and(user::Column::Id.lt(id)))
+      ),
+      Some(Cursor::Name(name, id)) => condition = condition.add(
+        Condition::any()
+          .add(user::Column::Name.gt(name.clone()))
+          .add(user::Column::Name.eq(name).and(user::Column::Id.gt(id)))
+      ),
+      None => {},
+    }
+
+    let select = User::find().filter(condition);
+    let select = match sort {
+      UserSort::CreatedAt => select.order_by_desc(user::Column::CreatedAt).order_by_desc(user::Column::Id),
+      UserSort::Name => select.order_by_asc(user::Column::Name).order_by_asc(user::Column::Id),
     };
 
-    let res = user_model.insert(conn).await;
-    
-    match res {
-      Ok(_) => Ok(user),
-      Err(e) => {
-        println!("{:?}", e);
-        Err(InsertError::Conflict)
-      },
+    match select.limit(limit).all(&self.conn).await {
+      Ok(users) => Ok(users.into_iter().map(UserEntity::from).collect()),
+      Err(e) => Err(FetchError::from(DbError::from(e))),
     }
   }
 
-  async fn fetch_one(&self, user_id: UserId) -> Result<UserEntity, FetchOneError> {
+  async fn fetch_by_login(&self, login: UserLogin) -> Result<UserEntity, FetchOneError> {
     let conn = &self.conn;
-  
-    match User::find_by_id(i64::from(user_id)).one(conn).await {
-      Ok(user) => match user {
-        Some(user) => Ok(UserEntity {
-          id: user.id,
-          login: user.login,
-          name: user.name,
-          email: user.email,
-          avatar_url: user.avatar_url,
-          created_at: user.created_at,
-          updated_at: user.updated_at,
-        }),
-        None => Err(FetchOneError::NotFound),
-      },
-      Err(_) => Err(FetchOneError::NotFound),
+    let login = String::from(login).to_lowercase();
+
+    let user = User::find()
+      .filter(Expr::expr(Func::lower(Expr::col(user::Column::Login))).eq(login.clone()))
+      .one(conn)
+      .await
+      .map_err(|e| FetchOneError::from(DbError::from(e)))?;
+    if let Some(user) = user {
+      return Ok(UserEntity::from(user));
+    }
+
+    let renamed = user_login_history::Entity::find_by_id(login)
+      .find_also_related(User)
+      .one(conn)
+      .await
+      .map_err(|e| FetchOneError::from(DbError::from(e)))?;
+    match renamed {
+      Some((_, Some(user))) => Ok(UserEntity::from(user)),
+      _ => Err(FetchOneError::NotFound),
     }
   }
 
@@ -225,11 +595,106 @@ impl Repository for PgRepository {
         email: user.email.unwrap(),
         created_at: user.created_at.unwrap(),
         updated_at: user.updated_at.unwrap(),
+        last_signed_in_at: user.last_signed_in_at.unwrap(),
+        channels: channels(user.channel.unwrap()),
       }),
-      Err(e) => {
-        println!("{:?}", e);
-        Err(UpdateError::Unknown)
-      },
+      Err(e) => Err(UpdateError::from(DbError::from(e))),
+    }
+  }
+
+  async fn update_email(&self, id: UserId, email: UserEmail) -> Result<UserEntity, UpdateError> {
+    let user = user::ActiveModel {
+      id: Set(i64::from(id)),
+      email: Set(Some(String::from(email))),
+      ..Default::default()
+    };
+
+    match user.update(&self.conn).await {
+      Ok(user) => Ok(UserEntity::from(user)),
+      Err(e) => Err(UpdateError::from(DbError::from(e))),
+    }
+  }
+
+  async fn update_channels(&self, id: UserId, channels: ContactChannels) -> Result<UserEntity, UpdateError> {
+    let channel = match serde_json::to_value(&channels) {
+      Ok(channel) => channel,
+      Err(e) => return Err(UpdateError::Unknown(DbErr::Custom(e.to_string()))),
+    };
+
+    let user = user::ActiveModel {
+      id: Set(i64::from(id)),
+      channel: Set(Some(channel)),
+      ..Default::default()
+    };
+
+    match user.update(&self.conn).await {
+      Ok(user) => Ok(UserEntity::from(user)),
+      Err(e) => Err(UpdateError::from(DbError::from(e))),
+    }
+  }
+
+  async fn upsert_from_provider(&self, id: UserId, login: UserLogin, name: UserName, avatar_url: UserAvatar, email: Option<UserEmail>) -> Result<UserEntity, InsertError> {
+    let now = Utc::now().with_timezone(&FixedOffset::east(9 * 3600));
+    let previous = User::find_by_id(i64::from(id))
+      .one(&self.conn)
+      .await
+      .map_err(|e| InsertError::from(DbError::from(e)))?;
+
+    let user_model = user::ActiveModel {
+      id: Set(i64::from(id)),
+      login: Set(String::from(login)),
+      name: Set(String::from(name)),
+      avatar_url: Set(String::from(avatar_url)),
+      email: Set(email.map(String::from)),
+      created_at: Set(now),
+      updated_at: Set(now),
+      channel: Set(None),
+      last_signed_in_at: Set(Some(now)),
+    };
+
+    // concurrent first sign-ins both land here, the later one becomes the update
+    let res = User::insert(user_model)
+      .on_conflict(
+        OnConflict::column(user::Column::Id)
+          .values([
+            (user::Column::Login, Expr::cust(r#""excluded"."login""#)),
+            (user::Column::Name, Expr::cust(r#""excluded"."name""#)),
+            (user::Column::AvatarUrl, Expr::cust(r#""excluded"."avatar_url""#)),
+            (user::Column::Email, Expr::cust(r#"COALESCE("user"."email", "excluded"."email")"#)),
+            (user::Column::UpdatedAt, Expr::cust(r#""excluded"."updated_at""#)),
+            (user::Column::LastSignedInAt, Expr::cust(r#""excluded"."last_signed_in_at""#)),
+          ])
+          .to_owned()
+      )
+      .exec_with_returning(&self.conn)
+      .await;
+
+    let user = match res {
+      Ok(user) => user,
+      Err(e) => return Err(InsertError::from(DbError::from(e))),
+    };
+
+    if let Some(previous) = previous.filter(|previous| !previous.login.eq_ignore_ascii_case(&user.login)) {
+      let old_login = user_login_history::ActiveModel {
+        login: Set(previous.login.to_lowercase()),
+        user_id: Set(user.id),
+        created_at: Set(now),
+      };
+      // the handle may have belonged to somebody else before, the latest owner wins
+      let recorded = user_login_history::Entity::insert(old_login)
+        .on_conflict(
+          OnConflict::column(user_login_history::Column::Login)
+            .update_columns([user_login_history::Column::UserId, user_login_history::Column::CreatedAt])
+            .to_owned()
+        )
+        .exec_without_returning(&self.conn)
+        .await;
+      // the sign-in itself succeeded, only the redirect from the old handle is lost
+      if let Err(e) = recorded {
+        log::error!("[{}] {:?}", trace_id::current(), e);
+      }
     }
+
+    Ok(UserEntity::from(user))
   }
-}
\ No newline at end of file
+}
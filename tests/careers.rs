@@ -0,0 +1,100 @@
+mod common;
+
+use std::sync::Arc;
+
+use actix_web::{http::StatusCode, test, App};
+use serde_json::{json, Value};
+
+use rust_decafo::{infrastructure::mailer::InMemoryMailer, middleware::trace_id::TraceId};
+
+use common::{bearer, services, settings, sign_in};
+
+#[actix_web::test]
+async fn it_should_be_create_list_and_export_a_career() {
+  let app = test::init_service(App::new().wrap(TraceId).configure(services(settings(), Arc::new(InMemoryMailer::new())))).await;
+  let access_token = sign_in(&app).await;
+
+  let req = test::TestRequest::post()
+    .uri("/career")
+    .insert_header(bearer(&access_token))
+    .set_json(json!({ "company": "PineApple", "job": "Server Engineer", "inAt": "2022-01-01" }))
+    .to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  let created = body["data"].clone();
+  assert_eq!(created["inAt"], json!("2022-01-01"));
+
+  let req = test::TestRequest::get().uri("/users/by-login/OCTOCAT/careers?currentOnly=true&limit=10").insert_header(bearer(&access_token)).to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  // the listing hands out the same career the creation did
+  assert_eq!(body["data"][0], created);
+  assert_eq!(body["total"], json!(1));
+  assert_eq!(body["nextCursor"], Value::Null);
+
+  let req = test::TestRequest::get().uri("/users/1/experience").insert_header(bearer(&access_token)).to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(body["data"]["current"]["company"], json!("PineApple"));
+  assert_eq!(body["data"]["gaps"], json!([]));
+
+  let req = test::TestRequest::get().uri("/users/1/resume.json").insert_header(bearer(&access_token)).to_request();
+  let resume: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(resume["basics"]["email"], json!("octocat@github.com"));
+  assert_eq!(resume["work"][0]["name"], json!("PineApple"));
+}
+
+#[actix_web::test]
+async fn it_should_be_update_and_delete_a_career() {
+  let app = test::init_service(App::new().wrap(TraceId).configure(services(settings(), Arc::new(InMemoryMailer::new())))).await;
+  let access_token = sign_in(&app).await;
+  let req = test::TestRequest::post()
+    .uri("/career")
+    .insert_header(bearer(&access_token))
+    .set_json(json!({ "company": "PineApple", "job": "Server Engineer", "inAt": "2022-01-01" }))
+    .to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  let career_id = body["data"]["id"].as_i64().unwrap();
+
+  let req = test::TestRequest::patch()
+    .uri(&format!("/career/{}", career_id))
+    .insert_header(bearer(&access_token))
+    .set_json(json!({ "company": "Wercel", "job": "Server Engineer", "inAt": "2022-01-01", "outAt": "2022-12-31" }))
+    .to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(body["data"]["company"], json!("Wercel"));
+
+  let req = test::TestRequest::patch()
+    .uri(&format!("/career/{}", career_id))
+    .insert_header(bearer(&access_token))
+    .set_json(json!({ "company": "", "job": "Server Engineer", "inAt": "2022-01-01", "outAt": "2021-12-31" }))
+    .to_request();
+  let res = test::call_service(&app, req).await;
+  assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+  assert_eq!(res.headers().get("content-type").unwrap(), "application/problem+json");
+  let body: Value = test::read_body_json(res).await;
+  assert_eq!(body["code"], json!("validation_failed"));
+  assert_eq!(body["errors"].as_array().unwrap().iter().map(|e| e["field"].clone()).collect::<Vec<_>>(), vec![json!("company"), json!("outAt")]);
+
+  let req = test::TestRequest::delete().uri(&format!("/career/{}", career_id)).insert_header(bearer(&access_token)).to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+
+  let req = test::TestRequest::get().uri("/career/1").insert_header(bearer(&access_token)).to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(body["data"], json!([]));
+  assert_eq!(body["total"], json!(0));
+}
+
+#[actix_web::test]
+async fn it_should_be_leave_hiding_to_moderators() {
+  let app = test::init_service(App::new().wrap(TraceId).configure(services(settings(), Arc::new(InMemoryMailer::new())))).await;
+  let access_token = sign_in(&app).await;
+  let req = test::TestRequest::post()
+    .uri("/career")
+    .insert_header(bearer(&access_token))
+    .set_json(json!({ "company": "PineApple", "job": "Server Engineer", "inAt": "2022-01-01" }))
+    .to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  let career_id = body["data"]["id"].as_i64().unwrap();
+
+  // hiding is moderation, not something owners do to their own careers
+  let req = test::TestRequest::put().uri(&format!("/admin/careers/{}/hidden", career_id)).insert_header(bearer(&access_token)).to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+}
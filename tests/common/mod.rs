@@ -0,0 +1,98 @@
+//! The app the flow tests drive: every route on in-memory repositories, with GitHub
+//! pointed at a mock provider running next to it.
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use actix_http::Request;
+use actix_web::{body::MessageBody, dev::{Service, ServiceResponse}, http::header::LOCATION, test, web, App, Error, HttpServer};
+use oauth2::url::Url;
+use serde_json::{json, Value};
+
+use rust_decafo::{
+  domain::auth::provider::Providers,
+  infrastructure::{keys::KeyStore, mailer::{InMemoryMailer, Mailer}, mock_provider::{self, MockProvider}, routes, settings::Settings},
+  repositories::{user, career, refresh_token, revocation, pending_authorization, identity, role, personal_access_token, email_verification, search},
+};
+
+/// Settings of a test run, with GitHub served by a freshly started mock provider.
+pub fn settings() -> Settings {
+  let mut settings = Settings::test();
+
+  let mock = web::Data::new(MockProvider::new(settings.mock_provider.users.clone()));
+  let mock_server = HttpServer::new(move || App::new().app_data(mock.clone()).configure(mock_provider::routes))
+    .workers(1)
+    .bind(("127.0.0.1", 0))
+    .unwrap();
+  let addr = mock_server.addrs()[0];
+  actix_web::rt::spawn(mock_server.run());
+
+  settings.github.auth_url = format!("http://{}/login/oauth/authorize", addr);
+  settings.github.token_url = format!("http://{}/login/oauth/access_token", addr);
+  settings.github.api_url = format!("http://{}", addr);
+
+  settings
+}
+
+/// Registers the routes and what they share, like `Server::run` does with the Postgres repositories.
+pub fn services(settings: Settings, mailer: Arc<InMemoryMailer>) -> impl FnOnce(&mut web::ServiceConfig) {
+  move |cfg| {
+    let keys = Arc::new(KeyStore::new(&settings.jwt).unwrap());
+    let user_repo: Arc<dyn user::Repository> = Arc::new(user::InMemoryRepository::_new());
+    let career_repo: Arc<dyn career::Repository> = Arc::new(career::InMemoryRepository::new());
+    let token_repo: Arc<dyn refresh_token::Repository> = Arc::new(refresh_token::InMemoryRepository::new());
+    let revocations: Arc<dyn revocation::Repository> = Arc::new(revocation::InMemoryRepository::new());
+    let authorizations: Arc<dyn pending_authorization::Repository> = Arc::new(pending_authorization::InMemoryRepository::new());
+    let identities: Arc<dyn identity::Repository> = Arc::new(identity::InMemoryRepository::new());
+    let roles: Arc<dyn role::Repository> = Arc::new(role::InMemoryRepository::new());
+    let personal_access_tokens: Arc<dyn personal_access_token::Repository> = Arc::new(personal_access_token::InMemoryRepository::new());
+    let verifications: Arc<dyn email_verification::Repository> = Arc::new(email_verification::InMemoryRepository::new());
+    let search_repo: Arc<dyn search::Repository> = Arc::new(search::InMemoryRepository::new(vec![], vec![]));
+    let mailer: Arc<dyn Mailer> = mailer;
+
+    cfg
+      .app_data(web::Data::new(Arc::new(Providers::new(&settings))))
+      .app_data(web::Data::new(settings))
+      .app_data(web::Data::new(keys))
+      .app_data(web::Data::new(user_repo))
+      .app_data(web::Data::new(identities))
+      .app_data(web::Data::new(roles))
+      .app_data(web::Data::new(personal_access_tokens))
+      .app_data(web::Data::new(verifications))
+      .app_data(web::Data::new(search_repo))
+      .app_data(web::Data::new(mailer))
+      .app_data(web::Data::new(career_repo))
+      .app_data(web::Data::new(token_repo))
+      .app_data(web::Data::new(revocations))
+      .app_data(web::Data::new(authorizations))
+      .configure(routes);
+  }
+}
+
+/// Signs the mock's first user, octocat, in through the whole code flow and returns the access token.
+pub async fn sign_in<S, B>(app: &S) -> String
+where
+  S: Service<Request, Response = ServiceResponse<B>, Error = Error>,
+  B: MessageBody,
+{
+  let req = test::TestRequest::get().uri("/authorization/code?provider=github").to_request();
+  let authorize_url: String = test::call_and_read_body_json(app, req).await;
+
+  // the mock grants straight away and redirects back with the code
+  let client = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none()).build().unwrap();
+  let res = client.get(&authorize_url).send().await.unwrap();
+  let callback = Url::parse(res.headers()[LOCATION].to_str().unwrap()).unwrap();
+  let param = |name: &str| callback.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.to_string()).unwrap();
+
+  let req = test::TestRequest::post()
+    .uri("/signin")
+    .set_json(json!({ "provider": "Github", "auth_code": param("code"), "state": param("state") }))
+    .to_request();
+  let body: Value = test::call_and_read_body_json(app, req).await;
+
+  body["data"]["accessToken"].as_str().unwrap().to_string()
+}
+
+pub fn bearer(access_token: &str) -> (&'static str, String) {
+  ("Authorization", format!("Bearer {}", access_token))
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use std::sync::Arc;
+
+use actix_web::{http::StatusCode, test, App};
+use serde_json::{json, Value};
+
+use rust_decafo::{infrastructure::mailer::InMemoryMailer, middleware::trace_id::TraceId};
+
+use common::{bearer, services, settings, sign_in};
+
+#[actix_web::test]
+async fn it_should_be_use_a_read_only_token_until_it_is_revoked() {
+  let app = test::init_service(App::new().wrap(TraceId).configure(services(settings(), Arc::new(InMemoryMailer::new())))).await;
+  let access_token = sign_in(&app).await;
+
+  let req = test::TestRequest::post()
+    .uri("/tokens")
+    .insert_header(bearer(&access_token))
+    .set_json(json!({ "name": "ci", "scopes": ["read"] }))
+    .to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  let personal_token = body["data"]["token"].as_str().unwrap().to_string();
+
+  let req = test::TestRequest::get().uri("/tokens").insert_header(bearer(&personal_token)).to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(body["data"][0]["name"], json!("ci"));
+  assert!(body["data"][0]["lastUsedAt"].is_string());
+  assert!(body["data"][0].get("token").is_none());
+
+  let req = test::TestRequest::patch()
+    .uri("/user")
+    .insert_header(bearer(&personal_token))
+    .set_json(json!({ "name": "kent", "avatarUrl": "https://example.com/a.png" }))
+    .to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+  let req = test::TestRequest::delete().uri("/tokens/1").insert_header(bearer(&access_token)).to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+
+  let req = test::TestRequest::get().uri("/user/1").insert_header(bearer(&personal_token)).to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use std::sync::Arc;
+
+use actix_web::{http::StatusCode, test, App};
+use serde_json::{json, Value};
+
+use rust_decafo::{infrastructure::mailer::InMemoryMailer, middleware::trace_id::TraceId};
+
+use common::{bearer, services, settings, sign_in};
+
+#[actix_web::test]
+async fn it_should_be_take_a_new_email_once_the_mailed_link_is_used() {
+  let sent_mail = Arc::new(InMemoryMailer::new());
+  let app = test::init_service(App::new().wrap(TraceId).configure(services(settings(), sent_mail.clone()))).await;
+  let access_token = sign_in(&app).await;
+
+  let req = test::TestRequest::patch()
+    .uri("/user")
+    .insert_header(bearer(&access_token))
+    .set_json(json!({ "name": "The Octocat", "avatarUrl": "https://example.com/a.png", "email": "octo@example.com" }))
+    .to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(body["data"]["pendingEmail"], json!("octo@example.com"));
+  let link = sent_mail.sent.lock().unwrap()[0].body.clone();
+  let token = link.split("?token=").nth(1).unwrap().split_whitespace().next().unwrap().to_string();
+
+  let req = test::TestRequest::post().uri("/user/email/verify").set_json(json!({ "token": token })).to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+  let req = test::TestRequest::post().uri("/user/email/verify").set_json(json!({ "token": token })).to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+
+  let req = test::TestRequest::get().uri("/user/1").insert_header(bearer(&access_token)).to_request();
+  let user: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(user["email"], json!("octo@example.com"));
+}
+
+#[actix_web::test]
+async fn it_should_be_show_hidden_channels_only_to_the_user() {
+  let app = test::init_service(App::new().wrap(TraceId).configure(services(settings(), Arc::new(InMemoryMailer::new())))).await;
+  let access_token = sign_in(&app).await;
+
+  let req = test::TestRequest::put()
+    .uri("/user/channels")
+    .insert_header(bearer(&access_token))
+    .set_json(json!({ "channels": [
+      { "kind": "twitter", "value": "@octocat", "visible": true },
+      { "kind": "phone", "value": "+1 415-555-0100", "visible": false },
+    ] }))
+    .to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(body["data"][0]["value"], json!("octocat"));
+
+  let req = test::TestRequest::get().uri("/user/1").insert_header(bearer(&access_token)).to_request();
+  let user: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(user["channels"].as_array().unwrap().len(), 2);
+  let req = test::TestRequest::get().uri("/user/1").to_request();
+  let user: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(user["channels"], json!([{ "kind": "twitter", "value": "octocat", "visible": true }]));
+
+  let req = test::TestRequest::put()
+    .uri("/user/channels")
+    .insert_header(bearer(&access_token))
+    .set_json(json!({ "channels": [{ "kind": "linkedin", "value": "http://example.com", "visible": true }] }))
+    .to_request();
+  let res = test::call_service(&app, req).await;
+  assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+  let body: Value = test::read_body_json(res).await;
+  assert_eq!(body["errors"][0]["field"], json!("channels.linkedin"));
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use std::sync::Arc;
+
+use actix_web::{http::StatusCode, test, App};
+use serde_json::{json, Value};
+
+use rust_decafo::{infrastructure::mailer::InMemoryMailer, middleware::trace_id::TraceId};
+
+use common::{bearer, services, settings, sign_in};
+
+#[actix_web::test]
+async fn it_should_be_sign_in_through_the_mock_provider() {
+  let app = test::init_service(App::new().wrap(TraceId).configure(services(settings(), Arc::new(InMemoryMailer::new())))).await;
+
+  let access_token = sign_in(&app).await;
+
+  let req = test::TestRequest::get().uri("/user/1").insert_header(bearer(&access_token)).to_request();
+  let user: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(user["login"], json!("octocat"));
+  assert_eq!(user["email"], json!("octocat@github.com"));
+}
+
+#[actix_web::test]
+async fn it_should_be_refuse_a_token_without_the_bearer_scheme() {
+  let app = test::init_service(App::new().wrap(TraceId).configure(services(settings(), Arc::new(InMemoryMailer::new())))).await;
+  let access_token = sign_in(&app).await;
+
+  let req = test::TestRequest::get().uri("/user/1").insert_header(("Authorization", access_token)).to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+}
+
+#[actix_web::test]
+async fn it_should_be_not_found_for_an_unknown_path_without_a_token() {
+  let app = test::init_service(App::new().wrap(TraceId).configure(services(settings(), Arc::new(InMemoryMailer::new())))).await;
+
+  let req = test::TestRequest::post().uri("/signinfoo").to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+  let req = test::TestRequest::get().uri("/tokens/1/unknown").to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn it_should_be_sign_out() {
+  let app = test::init_service(App::new().wrap(TraceId).configure(services(settings(), Arc::new(InMemoryMailer::new())))).await;
+  let access_token = sign_in(&app).await;
+
+  let req = test::TestRequest::post().uri("/signout").to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+  let req = test::TestRequest::post().uri("/signout").insert_header(bearer(&access_token)).to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+
+  let req = test::TestRequest::get().uri("/user/1").insert_header(bearer(&access_token)).to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use std::sync::Arc;
+
+use actix_web::{http::StatusCode, test, App};
+use serde_json::{json, Value};
+
+use rust_decafo::{infrastructure::mailer::InMemoryMailer, middleware::trace_id::TraceId};
+
+use common::{services, settings, sign_in};
+
+#[actix_web::test]
+async fn it_should_be_show_public_profiles_by_login() {
+  let app = test::init_service(App::new().wrap(TraceId).configure(services(settings(), Arc::new(InMemoryMailer::new())))).await;
+  sign_in(&app).await;
+
+  let req = test::TestRequest::get().uri("/users/by-login/OctoCat").to_request();
+  let user: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(user["id"], json!(1));
+  assert!(user.get("lastSignedInAt").is_none());
+
+  let req = test::TestRequest::get().uri("/users/by-login/nobody").to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn it_should_be_list_the_directory_without_emails() {
+  let app = test::init_service(App::new().wrap(TraceId).configure(services(settings(), Arc::new(InMemoryMailer::new())))).await;
+  sign_in(&app).await;
+
+  let req = test::TestRequest::get().uri("/users?sort=name&limit=10").to_request();
+  let page: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(page["data"][0]["login"], json!("octocat"));
+  assert_eq!(page["data"][0]["email"], Value::Null);
+  assert_eq!(page["nextCursor"], Value::Null);
+
+  let req = test::TestRequest::get().uri("/users?cursor=bogus").to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+  let req = test::TestRequest::get().uri("/users?hasEmail=true").to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn it_should_be_reject_a_blank_search() {
+  let app = test::init_service(App::new().wrap(TraceId).configure(services(settings(), Arc::new(InMemoryMailer::new())))).await;
+
+  let req = test::TestRequest::get().uri("/search?q=%20").to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+}